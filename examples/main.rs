@@ -42,18 +42,21 @@ use mg::{
     Alert,
     AppClose,
     Color,
+    ConfigSource,
     CustomCommand,
     DarkTheme,
     DeleteCompletionItem,
     DialogBuilder,
     Info,
     Mg,
+    MgBuilder,
     Mode,
     Modes,
     ModeChanged,
     SetMode,
     SetSetting,
     SettingChanged,
+    SettingValues,
     StatusBarItem,
     Title,
     Variables,
@@ -62,6 +65,7 @@ use mg::{
     input,
     question,
 };
+use mg_settings::Value;
 use relm::{Relm, Widget};
 use relm_attributes::widget;
 
@@ -169,7 +173,10 @@ impl Widget for Win {
 
     view! {
         #[name="mg"]
-        Mg<AppCommand, AppSettings>(MODES, Ok("examples/main.conf".into()), Some("/home/bouanto".into()), vec![]) {
+        Mg<AppCommand, AppSettings>(MgBuilder::new(MODES)
+            .config(ConfigSource::new("examples/main.conf"))
+            .include_path("/home/bouanto")
+            .setting_values()) {
             DarkTheme: true,
             Title: self.model.title.clone(),
             Variables: vec![("url", Box::new(|| "http://duckduckgo.com/lite".to_string()))],
@@ -213,7 +220,7 @@ impl Widget for Win {
             StatusBarItem {
                 text: "Test",
             },
-            AppClose => Command(Quit),
+            AppClose(_) => Command(Quit),
             CustomCommand(Insert) => mg@SetMode("insert"),
             CustomCommand(Normal) => mg@SetMode("normal"),
             CustomCommand(ref command) => Command(command.clone()),
@@ -266,3 +273,25 @@ pub struct AppSettings {
     width: i64,
     window_title: String,
 }
+
+impl SettingValues for AppSettings {
+    fn get_value(&self, name: &str) -> Option<Value> {
+        let value =
+            match name {
+                "boolean" => Value::Bool(self.boolean),
+                "custom-set" => {
+                    let choice =
+                        match self.custom_set {
+                            CustomSetting::Choice => "choice",
+                            CustomSetting::OtherChoice => "other-choice",
+                        };
+                    Value::Str(choice.to_string())
+                },
+                "title-len" => Value::Int(self.title_len),
+                "width" => Value::Int(self.width),
+                "window-title" => Value::Str(self.window_title.clone()),
+                _ => return None,
+            };
+        Some(value)
+    }
+}
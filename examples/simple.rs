@@ -40,9 +40,11 @@ use gtk::{
 use gtk::Orientation::Vertical;
 use mg::{
     AppClose,
+    ConfigSource,
     CustomCommand,
     DarkTheme,
     Mg,
+    MgBuilder,
     NoSettings,
     SetMode,
     StatusBarItem,
@@ -98,7 +100,7 @@ impl Widget for Win {
 
     view! {
         #[name="mg"]
-        Mg<AppCommand, NoSettings>(&[], Ok("examples/main.conf".into()), None, vec![]) {
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[]).config(ConfigSource::new("examples/main.conf"))) {
             DarkTheme: true,
             Title: "First Mg Program".to_string(),
             Variables: vec![("url", Box::new(|| "http://duckduckgo.com/lite".to_string()))],
@@ -117,7 +119,7 @@ impl Widget for Win {
             StatusBarItem {
                 text: "Test",
             },
-            AppClose => Command(Quit),
+            AppClose(_) => Command(Quit),
             CustomCommand(Insert) => mg@SetMode("insert"),
             CustomCommand(Normal) => mg@SetMode("normal"),
             CustomCommand(ref command) => Command(command.clone()),
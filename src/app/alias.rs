@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use mg_settings::errors::Error;
+
+use app::Mg;
+use app::help::command_names;
+use file::SourceLine;
+
+/// The name of the command defining an alias.
+pub const ALIAS_COMMAND: &str = "alias";
+
+/// Remove the commands defining a name (like `alias name expansion`) from the lines of a config
+/// file.
+/// The mg_settings parser does not know these commands, so the definitions are returned, with
+/// their line, to be added separately. The other lines keep their position in the file.
+pub fn extract_definitions(lines: Vec<SourceLine>, command_name: &str, errors: &mut Vec<Error>)
+    -> (Vec<SourceLine>, Vec<(SourceLine, String, String)>)
+{
+    let mut definitions = vec![];
+    let mut remaining_lines = vec![];
+    for line in lines {
+        let definition = {
+            let trimmed_line = line.text.trim_left();
+            if trimmed_line.split_whitespace().next() == Some(command_name) {
+                Some(parse_definition(&trimmed_line[command_name.len()..]))
+            }
+            else {
+                None
+            }
+        };
+        match definition {
+            Some(Some((name, expansion))) => definitions.push((line, name, expansion)),
+            Some(None) => errors.push(line.error(&format!("{}: Argument required", command_name))),
+            None => remaining_lines.push(line),
+        }
+    }
+    (remaining_lines, definitions)
+}
+
+/// Parse the name and the expansion of a definition.
+pub fn parse_definition(arguments: &str) -> Option<(String, String)> {
+    let arguments = arguments.trim();
+    let name_end = arguments.find(char::is_whitespace)?;
+    let expansion = arguments[name_end..].trim();
+    Some((arguments[..name_end].to_string(), expansion.to_string()))
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Add an alias: the commands starting with the alias are executed with the expansion
+    /// instead.
+    pub fn add_alias(&mut self, alias: &str, expansion: &str) {
+        if command_names::<COMM>().iter().any(|name| name == alias) {
+            self.warning(&format!("The alias {} shadows the command {}", alias, alias));
+        }
+        self.model.aliases.insert(alias.to_string(), expansion.to_string());
+        self.update_command_completer();
+    }
+
+    /// Add an alias defined in the config, to remove it when the config is reloaded.
+    pub fn add_config_alias(&mut self, alias: &str, expansion: &str) {
+        self.model.config_aliases.insert(alias.to_string());
+        self.add_alias(alias, expansion);
+    }
+
+    /// Replace the first word of the command by its expansion if it is an alias.
+    pub fn expand_alias(&self, command: &str) -> String {
+        let trimmed_command = command.trim_left();
+        let name_end = trimmed_command.find(char::is_whitespace).unwrap_or_else(|| trimmed_command.len());
+        match self.model.aliases.get(&trimmed_command[..name_end]) {
+            Some(expansion) => format!("{}{}", expansion, &trimmed_command[name_end..]),
+            None => command.to_string(),
+        }
+    }
+
+    /// Handle the `alias name command` command.
+    pub fn alias_command(&mut self, arguments: &str) {
+        match parse_definition(arguments) {
+            Some((alias, expansion)) => {
+                // NOTE: the alias redefined at runtime is kept when the config is reloaded.
+                self.model.config_aliases.remove(&alias);
+                self.add_alias(&alias, &expansion);
+            },
+            None => self.error(Error::Msg("Argument required".to_string())),
+        }
+    }
+}
@@ -21,35 +21,98 @@
 
 use std::collections::HashMap;
 
-use mg_settings::{EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use mg_settings::{EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+use mg_settings::errors::Error;
 use mg_settings::settings;
 
 use app::Mg;
+use app::help::{HELP_COMMAND, help_entries};
+use app::status_bar::Msg::ActivateEntry;
 use completion::{
     self,
     CommandCompleter,
+    HelpCompleter,
     SettingCompleter,
     DEFAULT_COMPLETER_IDENT,
 };
+use completion::completion_view::CompletionWrap;
 use completion::completion_view::Msg::{
+    ActivateSelected,
+    AddCompleters,
     DeleteCurrentCompletionItem,
     ShowCompletion,
     UpdateCompletions,
+    Wrap,
 };
 
+/// The name of the built-in setting making `complete-next` first insert the longest common prefix
+/// of the completions instead of selecting the next one.
+pub const COMPLETE_COMMON_PREFIX_SETTING: &str = "complete-common-prefix";
+/// The name of the built-in setting choosing what happens after the last completion: `wrap`,
+/// `stop` or `unselect`.
+pub const COMPLETION_WRAP_SETTING: &str = "completion-wrap";
+
 impl<COMM, SETT> Mg<COMM, SETT>
 where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
 {
+    /// Execute the selected completion as if it was the text of the entry, without accepting it
+    /// first.
+    /// Without selected completion, execute the text of the entry like Enter does.
+    pub fn complete_accept(&mut self) {
+        if self.model.completion_selected.get() {
+            self.completion_view.emit(ActivateSelected);
+        }
+        else {
+            // NOTE: activate the entry itself so that the command is executed exactly like when
+            // Enter is pressed.
+            self.status_bar.emit(ActivateEntry);
+        }
+    }
+
     /// Get the default completers.
-    /// One to complete the commands, the other to complete the settings.
+    /// They complete the commands, the settings and the help topics.
     pub fn default_completers() -> completion::Completers {
         let mut completers: HashMap<_, Box<completion::Completer>> = HashMap::new();
         completers.insert(DEFAULT_COMPLETER_IDENT, Box::new(CommandCompleter::<COMM>::new()));
+        completers.insert(HELP_COMMAND, Box::new(HelpCompleter::new(help_entries::<COMM, SETT>())));
         completers.insert("set", Box::new(SettingCompleter::<SETT>::new()));
         completers
     }
 
+    /// Complete the aliases, the application commands and the user commands with the commands.
+    pub fn update_command_completer(&self) {
+        let command_completer = CommandCompleter::<COMM>::new()
+            .aliases(&self.model.aliases)
+            .app_commands(&self.app_command_descriptions())
+            .categories(&self.model.command_categories)
+            .user_commands(&self.model.user_commands);
+        let mut completers: HashMap<_, Box<completion::Completer>> = HashMap::new();
+        completers.insert(DEFAULT_COMPLETER_IDENT, Box::new(command_completer));
+        self.completion_view.emit(AddCompleters(completers));
+    }
+
+    /// Set whether `complete-next` first inserts the longest common prefix of the completions from
+    /// the value of the `complete-common-prefix` setting.
+    pub fn set_complete_common_prefix(&mut self, value: &Value) {
+        match *value {
+            Value::Bool(complete_common_prefix) => self.model.complete_common_prefix = complete_common_prefix,
+            _ => self.error(Error::Msg(format!("Expecting a boolean for setting {}",
+                COMPLETE_COMMON_PREFIX_SETTING))),
+        }
+    }
+
+    /// Set what happens after the last completion from the value of the `completion-wrap` setting.
+    pub fn set_completion_wrap(&mut self, value: &Value) {
+        match completion_wrap(value) {
+            Ok(wrap) => {
+                self.model.completion_wrap = wrap;
+                self.completion_view.emit(Wrap(wrap));
+            },
+            Err(message) => self.error(Error::Msg(message)),
+        }
+    }
+
     /// Delete the current completion item.
     pub fn delete_current_completion_item(&self) {
         self.completion_view.emit(DeleteCurrentCompletionItem);
@@ -67,3 +130,35 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         self.completion_view.emit(UpdateCompletions(self.model.mode_string.clone(), input, self.is_normal_command()));
     }
 }
+
+/// Convert the value of the `completion-wrap` setting.
+fn completion_wrap(value: &Value) -> Result<CompletionWrap, String> {
+    match *value {
+        Value::Str(ref wrap) if wrap == "stop" => Ok(CompletionWrap::Stop),
+        Value::Str(ref wrap) if wrap == "unselect" => Ok(CompletionWrap::Unselect),
+        Value::Str(ref wrap) if wrap == "wrap" => Ok(CompletionWrap::Wrap),
+        _ => Err(format!("Expecting wrap, stop or unselect for setting {}", COMPLETION_WRAP_SETTING)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mg_settings::Value;
+
+    use completion::completion_view::CompletionWrap;
+    use super::completion_wrap;
+
+    #[test]
+    fn test_completion_wrap() {
+        assert_eq!(completion_wrap(&Value::Str("stop".to_string())), Ok(CompletionWrap::Stop));
+        assert_eq!(completion_wrap(&Value::Str("unselect".to_string())), Ok(CompletionWrap::Unselect));
+        assert_eq!(completion_wrap(&Value::Str("wrap".to_string())), Ok(CompletionWrap::Wrap));
+    }
+
+    #[test]
+    fn test_invalid_completion_wrap() {
+        let error = Err("Expecting wrap, stop or unselect for setting completion-wrap".to_string());
+        assert_eq!(completion_wrap(&Value::Str("Wrap".to_string())), error);
+        assert_eq!(completion_wrap(&Value::Bool(true)), error);
+    }
+}
@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+
+use app::Mg;
+use app::alias::ALIAS_COMMAND;
+use app::help::{HELP_COMMAND, QUIT_COMMAND, QUIT_SHORT_COMMAND};
+use app::message_log::MESSAGES_COMMAND;
+use app::user_command::USER_COMMAND_COMMAND;
+use self::BuiltinCommand::*;
+
+/// The name of the command writing the config.
+pub const MKRC_COMMAND: &str = "mkrc";
+/// The name of the command setting the value of a setting.
+pub const SET_COMMAND: &str = "set";
+/// The name of the command setting the value of a setting in a mode.
+pub const SETLOCAL_COMMAND: &str = "setlocal";
+
+/// A command handled by mg itself.
+#[derive(Debug, PartialEq)]
+pub enum BuiltinCommand {
+    /// `set name += amount` or `set name -= amount`.
+    AdjustSetting { name: String, operator: char, amount: String },
+    /// `alias name command`, with the arguments.
+    Alias(String),
+    /// `help [name]`, with the name of the command, setting or category.
+    Help(String),
+    /// `set all`.
+    ListAllSettings,
+    /// `set` without arguments.
+    ListSettings,
+    /// `messages`.
+    Messages,
+    /// `mkrc[!] [file]`.
+    Mkrc { force: bool, path: String },
+    /// `quit` or `q`, when the application does not define these commands.
+    Quit,
+    /// `setlocal mode name = value`, with the arguments.
+    SetLocal(String),
+    /// `!command args`, with the shell command.
+    Shell(String),
+    /// `set name` or `set name?`.
+    ShowSetting(String),
+    /// `set name!`.
+    ToggleSetting(String),
+    /// A command defined with `command`.
+    UserCommand { name: String, arguments: String },
+    /// `command name commands`, with the arguments.
+    UserCommandDefinition(String),
+}
+
+/// Parse the arguments of the `set` command when they are not handled by the parser of the config
+/// syntax.
+/// `has_all_setting` tells whether the application has a setting named all, which takes precedence
+/// over `set all`.
+pub fn parse_set_arguments(arguments: &str, has_all_setting: bool) -> Option<BuiltinCommand> {
+    let words: Vec<_> = arguments.split_whitespace().collect();
+    if words.is_empty() {
+        return Some(ListSettings);
+    }
+    if words == ["all"] && !has_all_setting {
+        return Some(ListAllSettings);
+    }
+    for &operator in &['+', '-'] {
+        if let Some(index) = arguments.find(&format!("{}=", operator)) {
+            let name = arguments[..index].trim();
+            // NOTE: check the name to avoid catching a string value containing the operator.
+            if !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '=') {
+                return Some(AdjustSetting {
+                    name: name.to_string(),
+                    operator,
+                    amount: arguments[index + 2..].trim().to_string(),
+                });
+            }
+        }
+    }
+    if words.len() == 1 && !words[0].contains('=') {
+        let name = words[0];
+        if name.ends_with('!') {
+            return Some(ToggleSetting(name[..name.len() - 1].to_string()));
+        }
+        else if name.ends_with('?') {
+            return Some(ShowSetting(name[..name.len() - 1].to_string()));
+        }
+        return Some(ShowSetting(name.to_string()));
+    }
+    None
+}
+
+/// Get the `quit` command of the application to execute for `q`, when the application defines
+/// `quit` but not `q`, so that `q` always closes the application.
+pub fn app_quit_command<COMM: EnumMetaData>(command: &str) -> Option<String> {
+    let metadata = COMM::get_metadata();
+    if command.trim() == QUIT_SHORT_COMMAND && metadata.contains_key(QUIT_COMMAND) &&
+        !metadata.contains_key(QUIT_SHORT_COMMAND)
+    {
+        Some(QUIT_COMMAND.to_string())
+    }
+    else {
+        None
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Get the built-in command if the command is one.
+    /// The other commands are parsed with the config syntax.
+    pub fn parse_builtin_command(&self, command: &str) -> Option<BuiltinCommand> {
+        let command = command.trim();
+        if command.starts_with('!') {
+            return Some(Shell(command[1..].trim().to_string()));
+        }
+        let name_end = command.find(char::is_whitespace).unwrap_or_else(|| command.len());
+        let name = &command[..name_end];
+        let arguments = command[name_end..].trim();
+        let builtin_command =
+            match name {
+                SET_COMMAND => return parse_set_arguments(arguments, SETT::get_metadata().contains_key("all")),
+                SETLOCAL_COMMAND => SetLocal(arguments.to_string()),
+                MKRC_COMMAND => Mkrc { force: false, path: arguments.to_string() },
+                _ if name == format!("{}!", MKRC_COMMAND) => Mkrc { force: true, path: arguments.to_string() },
+                HELP_COMMAND => Help(arguments.to_string()),
+                ALIAS_COMMAND => Alias(arguments.to_string()),
+                MESSAGES_COMMAND if arguments.is_empty() => Messages,
+                QUIT_COMMAND | QUIT_SHORT_COMMAND if arguments.is_empty() => {
+                    let metadata = COMM::get_metadata();
+                    if metadata.contains_key(QUIT_COMMAND) || metadata.contains_key(name) {
+                        return None;
+                    }
+                    Quit
+                },
+                USER_COMMAND_COMMAND => UserCommandDefinition(arguments.to_string()),
+                _ if self.model.user_commands.contains_key(name) =>
+                    UserCommand { name: name.to_string(), arguments: arguments.to_string() },
+                _ => return None,
+            };
+        Some(builtin_command)
+    }
+
+    /// Execute a built-in command.
+    /// Like the other commands, it does not change the mode: the caller returns to the normal mode
+    /// when the command was activated by the user. The commands listing items in the completion
+    /// view enter the command mode again afterwards.
+    pub fn execute_builtin_command(&mut self, command: BuiltinCommand, prefix: Option<u32>) {
+        match command {
+            AdjustSetting { name, operator, amount } => self.adjust_setting(&name, operator, &amount),
+            Alias(arguments) => self.alias_command(&arguments),
+            Help(name) => self.help_command(&name),
+            ListAllSettings => self.list_all_settings(),
+            ListSettings => self.list_settings(),
+            Messages => self.show_message_log(),
+            Mkrc { force, path } => self.mkrc_command(force, &path),
+            Quit => self.quit_command(),
+            SetLocal(arguments) => self.setlocal_command(&arguments),
+            Shell(shell_command) => self.shell_command(&shell_command),
+            ShowSetting(name) => self.show_setting(&name),
+            ToggleSetting(name) => self.toggle_setting(&name),
+            UserCommand { name, arguments } => self.run_user_command(&name, &arguments, prefix),
+            UserCommandDefinition(arguments) => self.user_command_definition(&arguments),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BuiltinCommand::*;
+    use super::{app_quit_command, parse_set_arguments};
+
+    #[derive(Commands)]
+    pub enum QuitCommand {
+        Open(String),
+        Quit,
+    }
+
+    #[derive(Commands)]
+    pub enum ShortQuitCommand {
+        Open(String),
+        Q,
+        Quit,
+    }
+
+    #[test]
+    fn test_parse_set_arguments() {
+        assert_eq!(parse_set_arguments("", false), Some(ListSettings));
+        assert_eq!(parse_set_arguments("all", false), Some(ListAllSettings));
+        // The setting named all takes precedence.
+        assert_eq!(parse_set_arguments("all", true), Some(ShowSetting("all".to_string())));
+        assert_eq!(parse_set_arguments("step?", false), Some(ShowSetting("step".to_string())));
+        assert_eq!(parse_set_arguments("step", false), Some(ShowSetting("step".to_string())));
+        assert_eq!(parse_set_arguments("enabled!", false), Some(ToggleSetting("enabled".to_string())));
+        assert_eq!(parse_set_arguments("step += 3", false),
+            Some(AdjustSetting { name: "step".to_string(), operator: '+', amount: "3".to_string() }));
+        assert_eq!(parse_set_arguments("step-=2", false),
+            Some(AdjustSetting { name: "step".to_string(), operator: '-', amount: "2".to_string() }));
+    }
+
+    #[test]
+    fn test_parse_set_arguments_for_parser() {
+        assert_eq!(parse_set_arguments("step = 7", false), None);
+        // The operator in a string value does not adjust the setting.
+        assert_eq!(parse_set_arguments("title = a += b", false), None);
+    }
+
+    #[test]
+    fn test_app_quit_command() {
+        assert_eq!(app_quit_command::<QuitCommand>("q"), Some("quit".to_string()));
+        assert_eq!(app_quit_command::<QuitCommand>(" q "), Some("quit".to_string()));
+        assert_eq!(app_quit_command::<QuitCommand>("quit"), None);
+        // The q command of the application is executed instead.
+        assert_eq!(app_quit_command::<ShortQuitCommand>("q"), None);
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+
+use app::{CloseDecision, CloseReason, Mg};
+use app::dialog::YesNoInputDialog;
+use app::Msg::{AppClose, CloseConfirmed};
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Close the application, by emitting `AppClose` for the application to close itself.
+    /// The blocking dialog is cancelled and the message log is flushed when this message is
+    /// handled.
+    fn close(&mut self, reason: CloseReason) {
+        self.model.relm.stream().emit(AppClose(reason));
+    }
+
+    /// Close the application if the user answered yes to the close confirmation.
+    pub fn close_confirmed(&mut self, confirmed: bool) {
+        let reason = self.model.close_reason.take();
+        if let Some(reason) = reason {
+            if confirmed {
+                self.close(reason);
+            }
+        }
+    }
+
+    /// Handle a request to close the window or the application.
+    /// `AppClose` is emitted for the application to close itself, unless the close handler vetoes
+    /// it or the user does not confirm it.
+    pub fn handle_close_request(&mut self, reason: CloseReason) {
+        let decision = self.model.close_handler.as_ref().map(|handler| handler(reason));
+        match decision {
+            None | Some(CloseDecision::Close) => self.close(reason),
+            Some(CloseDecision::Veto) => (),
+            Some(CloseDecision::Ask { prompt }) => {
+                self.model.close_reason = Some(reason);
+                let responder = YesNoInputDialog::new(&self.model.relm, CloseConfirmed);
+                self.yes_no_question(Box::new(responder), prompt);
+            },
+        }
+    }
+
+    /// Ask to close the application, like closing the window does.
+    pub fn request_close(&mut self) {
+        self.handle_close_request(CloseReason::Api);
+    }
+
+    /// Set the function deciding whether the application is closed when the window is closed or
+    /// the `quit` command is executed.
+    /// It receives the reason of the close request.
+    pub fn set_close_handler(&mut self, handler: Box<Fn(CloseReason) -> CloseDecision>) {
+        self.model.close_handler = Some(handler);
+    }
+}
@@ -36,6 +36,21 @@ use app::Mg;
 
 const TRANSPARENT: &RGBA = &RGBA { red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0 };
 
+/// The colors that can be specified by name.
+pub const NAMED_COLORS: &[(&str, (u8, u8, u8))] = &[
+    ("black", (0, 0, 0)),
+    ("blue", (0, 0, 255)),
+    ("cyan", (0, 255, 255)),
+    ("gray", (128, 128, 128)),
+    ("green", (0, 128, 0)),
+    ("magenta", (255, 0, 255)),
+    ("orange", (230, 140, 0)),
+    ("purple", (128, 0, 128)),
+    ("red", (255, 0, 0)),
+    ("white", (255, 255, 255)),
+    ("yellow", (255, 255, 0)),
+];
+
 impl<COMM, SETT> Mg<COMM, SETT>
     where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
           SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
@@ -62,6 +77,81 @@ impl<COMM, SETT> Mg<COMM, SETT>
     }
 }
 
+/// Parse a color written as `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb(r, g, b)`, `rgba(r, g, b, a)` or
+/// a name from `NAMED_COLORS`.
+pub fn parse_color(text: &str) -> Option<RGBA> {
+    let text = text.trim().to_lowercase();
+    if text.starts_with('#') {
+        parse_hex_color(&text[1..])
+    }
+    else if text.starts_with("rgba(") && text.ends_with(')') {
+        parse_color_components(&text[5..text.len() - 1], true)
+    }
+    else if text.starts_with("rgb(") && text.ends_with(')') {
+        parse_color_components(&text[4..text.len() - 1], false)
+    }
+    else {
+        NAMED_COLORS.iter()
+            .find(|&&(name, _)| name == text)
+            .map(|&(_, (red, green, blue))| rgba(red, green, blue, 1.0))
+    }
+}
+
+/// Parse the comma-separated components of a color.
+fn parse_color_components(components: &str, has_alpha: bool) -> Option<RGBA> {
+    let components: Vec<_> = components.split(',').map(str::trim).collect();
+    let expected_len = if has_alpha { 4 } else { 3 };
+    if components.len() != expected_len {
+        return None;
+    }
+    let red = components[0].parse().ok()?;
+    let green = components[1].parse().ok()?;
+    let blue = components[2].parse().ok()?;
+    let alpha =
+        if has_alpha {
+            let alpha: f64 = components[3].parse().ok()?;
+            if alpha < 0.0 || alpha > 1.0 {
+                return None;
+            }
+            alpha
+        }
+        else {
+            1.0
+        };
+    Some(rgba(red, green, blue, alpha))
+}
+
+/// Parse the hexadecimal digits of a color.
+fn parse_hex_color(digits: &str) -> Option<RGBA> {
+    let component = |index: usize, len: usize| -> Option<u8> {
+        let value = u8::from_str_radix(digits.get(index * len..(index + 1) * len)?, 16).ok()?;
+        // NOTE: a single digit is repeated (i.e. f is ff).
+        Some(if len == 1 { value * 17 } else { value })
+    };
+    match digits.len() {
+        3 => Some(rgba(component(0, 1)?, component(1, 1)?, component(2, 1)?, 1.0)),
+        6 => Some(rgba(component(0, 2)?, component(1, 2)?, component(2, 2)?, 1.0)),
+        8 => Some(rgba(component(0, 2)?, component(1, 2)?, component(2, 2)?, f64::from(component(3, 2)?) / 255.0)),
+        _ => None,
+    }
+}
+
+/// Create a color from its 8-bit components.
+fn rgba(red: u8, green: u8, blue: u8, alpha: f64) -> RGBA {
+    RGBA {
+        red: f64::from(red) / 255.0,
+        green: f64::from(green) / 255.0,
+        blue: f64::from(blue) / 255.0,
+        alpha,
+    }
+}
+
+/// Color the status bar background with the specified color.
+pub fn color_background<W: IsA<Object> + IsA<Widget> + WidgetExt>(widget: &W, color: &RGBA) {
+    widget.override_background_color(StateFlags::NORMAL, color);
+    white_foreground(widget);
+}
+
 /// Color the status bar in blue.
 pub fn color_blue<W: IsA<Object> + IsA<Widget> + WidgetExt>(widget: &W) {
     widget.override_background_color(StateFlags::NORMAL, &RGBA::blue());
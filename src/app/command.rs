@@ -20,6 +20,9 @@
  */
 
 use std::collections::HashMap;
+use std::env;
+use std::mem;
+use std::time::{Duration, Instant};
 
 use mg_settings::{
     self,
@@ -35,11 +38,23 @@ use mg_settings::errors::ErrorType::{MissingArgument, NoCommand, Parse, UnknownC
 use mg_settings::Command::{App, Custom, Map, Set, Unmap};
 
 use app::{
+    CommandFilterFn,
+    CommandFilterResult,
+    CommandSource,
+    FilteredCommand,
     Mg,
     Mode,
+    COMMAND_HISTORY_WINDOW,
+    COMPLETE_ACCEPT_COMMAND,
+    COMPLETE_ACCEPT_NEXT_COMMAND,
+    COMPLETE_FIRST_COMMAND,
+    COMPLETE_LAST_COMMAND,
     COMPLETE_NEXT_COMMAND,
     COMPLETE_PREVIOUS_COMMAND,
+    COMPLETION_DELETE_MARKED_COMMAND,
+    COMPLETION_TOGGLE_MARK_COMMAND,
     COPY,
+    COPY_MESSAGE,
     CUT,
     ENTRY_DELETE_NEXT_CHAR,
     ENTRY_DELETE_NEXT_WORD,
@@ -50,13 +65,23 @@ use app::{
     ENTRY_PREVIOUS_CHAR,
     ENTRY_PREVIOUS_WORD,
     ENTRY_SMART_HOME,
+    HISTORY_NEXT,
+    HISTORY_PREVIOUS,
     PASTE,
     PASTE_SELECTION,
+    REPEAT_LAST_ENTRY_COMMAND,
+    SHOW_ERROR_DETAILS,
+    SHOW_MAPPINGS,
 };
 use app::ActivationType::{self, Final};
+use app::CloseReason;
 use app::Msg::{
     self,
+    CloseRequested,
+    CommandContext,
+    CommandExecuted,
     CustomCommand,
+    CustomCommandId,
     EnterNormalModeAndReset,
 };
 use app::status_bar::Msg::{
@@ -74,75 +99,415 @@ use app::status_bar::Msg::{
     PreviousWord,
     SmartHome,
 };
-use app::ShortcutCommand::{self, Complete, Incomplete};
-use completion::completion_view::Msg::{SelectNext, SelectPrevious};
+use app::ShortcutCommand::{self, Complete, Incomplete, Silent};
+use app::builtin_command::{BuiltinCommand, app_quit_command};
+use app::config::ConfigCommand;
+use app::help::command_names;
+use app::settings::value_to_config;
+use app::shortcut::Mapping;
+use app::user_command::USER_COMMAND_COMMAND;
+use completion::closest_matches;
+use completion::completion_view::Msg::{
+    AcceptNext,
+    CompleteCommonPrefix,
+    DeleteMarked,
+    SelectFirst,
+    SelectLast,
+    SelectNext,
+    SelectPrevious,
+    ToggleMark,
+};
+use key_converter::{is_shifted_symbol, key_to_string, keys_to_string, normalize_key};
+
+/// The modifier at the start of a mapping action executing it silently.
+const SILENT_MODIFIER: &str = "<silent>";
+
+/// A built-in application command, i.e. a command usable in the mappings without being a
+/// command of the application.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BuiltinAppCommand {
+    /// Show the window listing the command history.
+    CommandHistoryWindow,
+    /// Execute the selected completion.
+    CompleteAccept,
+    /// Accept the next completion.
+    CompleteAcceptNext,
+    /// Select the first completion.
+    CompleteFirst,
+    /// Select the last completion.
+    CompleteLast,
+    /// Select the next completion.
+    CompleteNext,
+    /// Select the previous completion.
+    CompletePrevious,
+    /// Delete the marked completions.
+    CompletionDeleteMarked,
+    /// Mark or unmark the selected completion.
+    CompletionToggleMark,
+    /// Copy the selected text of the entry.
+    Copy,
+    /// Copy the message shown in the status bar.
+    CopyMessage,
+    /// Cut the selected text of the entry.
+    Cut,
+    /// Delete the character after the cursor.
+    EntryDeleteNextChar,
+    /// Delete the word after the cursor.
+    EntryDeleteNextWord,
+    /// Delete the word before the cursor.
+    EntryDeletePreviousWord,
+    /// Move the cursor to the end of the entry.
+    EntryEnd,
+    /// Move the cursor to the next character.
+    EntryNextChar,
+    /// Move the cursor to the next word.
+    EntryNextWord,
+    /// Move the cursor to the previous character.
+    EntryPreviousChar,
+    /// Move the cursor to the previous word.
+    EntryPreviousWord,
+    /// Move the cursor to the start of the entry.
+    EntrySmartHome,
+    /// Show the next command of the history starting with the typed text.
+    HistoryNext,
+    /// Show the previous command of the history starting with the typed text.
+    HistoryPrevious,
+    /// Paste the clipboard in the entry.
+    Paste,
+    /// Paste the primary selection in the entry.
+    PasteSelection,
+    /// Execute the last command typed in the entry again.
+    RepeatLastEntryCommand,
+    /// Show the details of the last error.
+    ShowErrorDetails,
+    /// Show the mappings of the current mode.
+    ShowMappings,
+}
+
+impl BuiltinAppCommand {
+    /// Get the built-in application command having this name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let command =
+            match name {
+                COMMAND_HISTORY_WINDOW => BuiltinAppCommand::CommandHistoryWindow,
+                COMPLETE_ACCEPT_COMMAND => BuiltinAppCommand::CompleteAccept,
+                COMPLETE_ACCEPT_NEXT_COMMAND => BuiltinAppCommand::CompleteAcceptNext,
+                COMPLETE_FIRST_COMMAND => BuiltinAppCommand::CompleteFirst,
+                COMPLETE_LAST_COMMAND => BuiltinAppCommand::CompleteLast,
+                COMPLETE_NEXT_COMMAND => BuiltinAppCommand::CompleteNext,
+                COMPLETE_PREVIOUS_COMMAND => BuiltinAppCommand::CompletePrevious,
+                COMPLETION_DELETE_MARKED_COMMAND => BuiltinAppCommand::CompletionDeleteMarked,
+                COMPLETION_TOGGLE_MARK_COMMAND => BuiltinAppCommand::CompletionToggleMark,
+                COPY => BuiltinAppCommand::Copy,
+                COPY_MESSAGE => BuiltinAppCommand::CopyMessage,
+                CUT => BuiltinAppCommand::Cut,
+                ENTRY_DELETE_NEXT_CHAR => BuiltinAppCommand::EntryDeleteNextChar,
+                ENTRY_DELETE_NEXT_WORD => BuiltinAppCommand::EntryDeleteNextWord,
+                ENTRY_DELETE_PREVIOUS_WORD => BuiltinAppCommand::EntryDeletePreviousWord,
+                ENTRY_END => BuiltinAppCommand::EntryEnd,
+                ENTRY_NEXT_CHAR => BuiltinAppCommand::EntryNextChar,
+                ENTRY_NEXT_WORD => BuiltinAppCommand::EntryNextWord,
+                ENTRY_PREVIOUS_CHAR => BuiltinAppCommand::EntryPreviousChar,
+                ENTRY_PREVIOUS_WORD => BuiltinAppCommand::EntryPreviousWord,
+                ENTRY_SMART_HOME => BuiltinAppCommand::EntrySmartHome,
+                HISTORY_NEXT => BuiltinAppCommand::HistoryNext,
+                HISTORY_PREVIOUS => BuiltinAppCommand::HistoryPrevious,
+                PASTE => BuiltinAppCommand::Paste,
+                PASTE_SELECTION => BuiltinAppCommand::PasteSelection,
+                REPEAT_LAST_ENTRY_COMMAND => BuiltinAppCommand::RepeatLastEntryCommand,
+                SHOW_ERROR_DETAILS => BuiltinAppCommand::ShowErrorDetails,
+                SHOW_MAPPINGS => BuiltinAppCommand::ShowMappings,
+                _ => return None,
+            };
+        Some(command)
+    }
+}
 
 impl<COMM, SETT> Mg<COMM, SETT>
     where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
           SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
 {
     /// Convert an action String to a command String.
+    /// The command is complete when it ends with `<Enter>` (or its alias `<CR>`).
+    /// A complete command can chain several commands separated by `;`: they are split by
+    /// handle_command().
+    /// A literal `<` is written `<lt>` in the action, so that `<lt>Enter>` does not complete the
+    /// command.
+    /// The `<lt>` of an incomplete command are unescaped after the variables are substituted.
+    /// An action starting with `<silent>` is always complete: it is executed without showing the
+    /// entry nor the messages other than the errors.
     pub fn action_to_command(&self, action: &str) -> ShortcutCommand {
+        if action.starts_with(SILENT_MODIFIER) {
+            let action = action[SILENT_MODIFIER.len()..].trim_left();
+            let action =
+                if let Some(':') = action.chars().next() {
+                    let end = action.find("<Enter>").or_else(|| action.find("<CR>")).unwrap_or_else(|| action.len());
+                    &action[1..end]
+                }
+                else {
+                    action
+                };
+            return Silent(unescape_lt(action));
+        }
         if let Some(':') = action.chars().next() {
-            if let Some(index) = action.find("<Enter>") {
-                Complete(action[1..index].to_string())
+            if let Some(index) = action.find("<Enter>").or_else(|| action.find("<CR>")) {
+                Complete(unescape_lt(&action[1..index]))
             }
             else {
                 Incomplete(action[1..].to_string())
             }
         }
         else {
-            Complete(action.to_string())
+            Complete(unescape_lt(action))
         }
     }
 
     /// Handle an application command.
-    fn app_command(&self, command: &str) {
-        match command {
-            COMPLETE_NEXT_COMMAND => self.completion_view.emit(SelectNext),
-            COMPLETE_PREVIOUS_COMMAND => self.completion_view.emit(SelectPrevious),
-            COPY => self.status_bar.emit(Copy),
-            CUT => self.status_bar.emit(Cut),
-            ENTRY_DELETE_NEXT_CHAR => self.status_bar.emit(DeleteNextChar),
-            ENTRY_DELETE_NEXT_WORD => self.status_bar.emit(DeleteNextWord),
-            ENTRY_DELETE_PREVIOUS_WORD => self.status_bar.emit(DeletePreviousWord),
-            ENTRY_END => self.status_bar.emit(End),
-            ENTRY_NEXT_CHAR => self.status_bar.emit(NextChar),
-            ENTRY_NEXT_WORD => self.status_bar.emit(NextWord),
-            ENTRY_PREVIOUS_CHAR => self.status_bar.emit(PreviousChar),
-            ENTRY_PREVIOUS_WORD => self.status_bar.emit(PreviousWord),
-            ENTRY_SMART_HOME => self.status_bar.emit(SmartHome),
-            PASTE => self.status_bar.emit(Paste),
-            PASTE_SELECTION => self.status_bar.emit(PasteSelection),
-            _ => unreachable!(),
+    fn app_command(&mut self, command: &str) {
+        // NOTE: the commands registered by the application are consulted first.
+        let handler = self.model.app_commands.get(command).map(|&(_, ref handler)| handler.clone());
+        if let Some(handler) = handler {
+            handler(self);
+            return;
+        }
+        let builtin_command =
+            match BuiltinAppCommand::from_name(command) {
+                Some(builtin_command) => builtin_command,
+                None => {
+                    // NOTE: the parser and the dispatcher can get out of sync, so this is not a
+                    // panic.
+                    self.error(Error::Msg(format!("unknown application command: {}", command)));
+                    return;
+                },
+            };
+        match builtin_command {
+            BuiltinAppCommand::CommandHistoryWindow => self.show_command_history_window(),
+            BuiltinAppCommand::CompleteAccept => self.complete_accept(),
+            BuiltinAppCommand::CompleteAcceptNext => self.completion_view.emit(AcceptNext),
+            BuiltinAppCommand::CompleteFirst => self.completion_view.emit(SelectFirst),
+            BuiltinAppCommand::CompleteLast => self.completion_view.emit(SelectLast),
+            BuiltinAppCommand::CompleteNext => {
+                if self.model.complete_next_accepts {
+                    self.completion_view.emit(AcceptNext);
+                }
+                else if self.model.complete_common_prefix {
+                    self.completion_view.emit(CompleteCommonPrefix);
+                }
+                else {
+                    self.completion_view.emit(SelectNext);
+                }
+            },
+            BuiltinAppCommand::CompletePrevious => self.completion_view.emit(SelectPrevious),
+            BuiltinAppCommand::CompletionDeleteMarked => self.completion_view.emit(DeleteMarked),
+            BuiltinAppCommand::CompletionToggleMark => self.completion_view.emit(ToggleMark),
+            BuiltinAppCommand::Copy => self.status_bar.emit(Copy),
+            BuiltinAppCommand::CopyMessage => self.copy_message(),
+            BuiltinAppCommand::Cut => self.status_bar.emit(Cut),
+            BuiltinAppCommand::EntryDeleteNextChar => self.status_bar.emit(DeleteNextChar),
+            BuiltinAppCommand::EntryDeleteNextWord => self.status_bar.emit(DeleteNextWord),
+            BuiltinAppCommand::EntryDeletePreviousWord => self.status_bar.emit(DeletePreviousWord),
+            BuiltinAppCommand::EntryEnd => self.status_bar.emit(End),
+            BuiltinAppCommand::EntryNextChar => self.status_bar.emit(NextChar),
+            BuiltinAppCommand::EntryNextWord => self.status_bar.emit(NextWord),
+            BuiltinAppCommand::EntryPreviousChar => self.status_bar.emit(PreviousChar),
+            BuiltinAppCommand::EntryPreviousWord => self.status_bar.emit(PreviousWord),
+            BuiltinAppCommand::EntrySmartHome => self.status_bar.emit(SmartHome),
+            BuiltinAppCommand::HistoryNext => self.history_next(),
+            BuiltinAppCommand::HistoryPrevious => self.history_previous(),
+            BuiltinAppCommand::Paste => self.status_bar.emit(Paste),
+            BuiltinAppCommand::PasteSelection => self.status_bar.emit(PasteSelection),
+            BuiltinAppCommand::RepeatLastEntryCommand => self.repeat_last_entry_command(),
+            BuiltinAppCommand::ShowErrorDetails => self.show_error_details(),
+            BuiltinAppCommand::ShowMappings => self.show_mappings_overlay(),
         }
     }
 
+    /// Parse the line after expanding the environment variables in it, so that they can be used
+    /// in any argument of the commands.
+    /// The mappings are parsed without expansion since their action is expanded when it is
+    /// executed.
+    fn parse_line_with_env(&mut self, line: &str, prefix: Option<u32>) -> ParseResult<COMM> {
+        let parse_result = self.model.settings_parser.parse_line(line, prefix);
+        if !line.contains('$') || parse_result.commands.iter().any(is_mapping_command) {
+            return parse_result;
+        }
+        let line = self.expand_env(line);
+        let expanded_result = self.model.settings_parser.parse_line(&line, prefix);
+        if expanded_result.commands.iter().any(is_mapping_command) {
+            parse_result
+        }
+        else {
+            expanded_result
+        }
+    }
+
+    /// Expand the environment variables in the text.
+    /// A warning is shown the first time an undefined variable is used.
+    pub fn expand_env(&mut self, text: &str) -> String {
+        let (expanded, undefined_variables) = expand_env_variables(text);
+        for name in undefined_variables {
+            if self.model.undefined_env_variables.insert(name.clone()) {
+                self.warning(&format!("Undefined environment variable: {}", name));
+            }
+        }
+        expanded
+    }
+
     /// Call the callback with the command or show an error if the command cannot be parsed.
+    /// The command filter is consulted first and can skip or replace the command.
     fn call_command(&mut self, command: Command<COMM>) {
+        let result = self.filter_command(FilteredCommand::Parsed(&command));
+        match result {
+            CommandFilterResult::Run => self.run_command(command),
+            CommandFilterResult::Skip => (),
+            CommandFilterResult::Replace(command) => self.run_command(command),
+        }
+    }
+
+    /// Execute the command, without consulting the command filter.
+    /// The CommandExecuted message is emitted after the command is executed, or when a deferred
+    /// custom command is sent.
+    fn run_command(&mut self, command: Command<COMM>) {
+        let name = self.command_to_string(&command);
         match command {
             App(command) => self.app_command(&command),
-            Custom(command) => self.model.relm.stream().emit(CustomCommand(command)),
+            Custom(command) => {
+                let source = self.model.command_source.clone();
+                if let Some(ref mut deferred_commands) = self.model.deferred_commands {
+                    // NOTE: CommandExecuted is emitted when the deferred command is sent.
+                    deferred_commands.push((source, command, name));
+                    return;
+                }
+                self.send_custom_command(source, command, &name);
+            },
             Map { action, keys, mode } => {
-                let mode_mappings = self.model.mappings.entry(self.model.modes[mode.as_str()].name)
+                let shifted_symbol = keys.iter().find(|key| is_shifted_symbol(key)).map(key_to_string);
+                if let Some(key) = shifted_symbol {
+                    self.error(Error::Msg(format!("Cannot map {}: the shift modifier cannot be applied to a symbol, \
+                                                   map the shifted symbol instead", key)));
+                    return;
+                }
+                let keys: Vec<_> = keys.into_iter().map(normalize_key).collect();
+                let mode_name = self.model.modes[mode.as_str()].name;
+                if let CommandSource::Config { .. } = self.model.command_source {
+                    // NOTE: remember the mappings from the config to remove them when it is
+                    // reloaded.
+                    self.model.config_mappings.insert((mode_name, keys.clone()));
+                }
+                let mode_mappings = self.model.mappings.entry(mode_name)
                     .or_insert_with(HashMap::new);
-                mode_mappings.insert(keys, action);
+                mode_mappings.insert(keys, Mapping::new(&action));
             },
-            Set(name, value) => {
-                match SETT::to_variant(&name, value) {
-                    Ok(setting) => self.set_setting(setting),
-                    Err(error) => {
-                        self.error(Error::Msg("Error setting value".to_string()));
-                        error!("{}", error);
-                    },
+            // NOTE: the mode is not changed here so that executing the config has no side effect
+            // on the mode and the entry: execute_commands() returns to the normal mode when the
+            // command was activated by the user.
+            Set(name, value) => self.set_setting_by_name(name, value),
+            Unmap { keys, mode } => {
+                let keys: Vec<_> = keys.into_iter().map(normalize_key).collect();
+                let removed = {
+                    let mode_mappings = self.model.mappings.entry(self.model.modes[mode.as_str()].name)
+                        .or_insert_with(HashMap::new);
+                    mode_mappings.remove(&keys).is_some()
+                };
+                if !removed {
+                    // NOTE: the keys are written as in the config so that the user can fix the
+                    // line.
+                    self.warning(&format!("No such mapping: {}unmap {}", mode, keys_to_string(&keys)));
                 }
-                self.return_to_normal_mode();
             },
-            Unmap { keys, mode } => {
-                let mode_mappings = self.model.mappings.entry(self.model.modes[mode.as_str()].name)
-                    .or_insert_with(HashMap::new);
-                mode_mappings.remove(&keys);
+        }
+        let origin = self.model.command_source.clone();
+        self.model.relm.stream().emit(CommandExecuted { name, origin });
+    }
+
+    /// Get a printable representation of the command.
+    /// A custom command cannot be converted back to text, so the text of the command line is used
+    /// when it is known (it is not for the commands of the config).
+    fn command_to_string(&self, command: &Command<COMM>) -> String {
+        match *command {
+            App(ref command) => command.clone(),
+            Custom(_) => self.model.command_text.clone().unwrap_or_else(|| "custom command".to_string()),
+            Map { ref action, ref keys, ref mode } => format!("{}map {} {}", mode, keys_to_string(keys), action),
+            Set(ref name, ref value) => format!("set {} = {}", name, value_to_config(value)),
+            Unmap { ref keys, ref mode } => format!("{}unmap {}", mode, keys_to_string(keys)),
+        }
+    }
+
+    /// Execute a command as if it was typed in the command entry, with the `Programmatic` source.
+    /// Unlike a typed command, its name is not expanded from a prefix.
+    pub fn execute_command(&mut self, command: &str) {
+        self.execute_command_from(CommandSource::Programmatic, command);
+    }
+
+    /// Execute a command received from another process (like a socket of the application), with
+    /// the `Remote` source.
+    /// Its name is not expanded from a prefix.
+    pub fn execute_remote_command(&mut self, command: &str) {
+        self.execute_command_from(CommandSource::Remote, command);
+    }
+
+    /// Execute a command as if it was typed in the command entry, with the source.
+    fn execute_command_from(&mut self, source: CommandSource, command: &str) {
+        let source = mem::replace(&mut self.model.command_source, source);
+        // NOTE: the command is executed as a normal command even if a special command is being
+        // typed.
+        let command_mode = self.model.current_command_mode;
+        self.set_current_identifier(':');
+        let message = self.handle_command(Some(command.to_string()), false, None);
+        self.model.current_command_mode = command_mode;
+        self.model.command_source = source;
+        if let Some(message) = message {
+            self.model.relm.stream().emit(message);
+        }
+    }
+
+    /// Show the result of a custom command reported by the application: the message is shown as
+    /// an info on success and the error is shown otherwise.
+    /// The time taken by the command is shown along with the message when it is slow.
+    /// The result is ignored when a newer custom command was sent since.
+    pub fn command_result(&mut self, id: u64, result: Result<Option<String>, String>) {
+        if id != self.model.custom_command_id {
+            return;
+        }
+        let time_message = self.model.custom_command_start.take()
+            .and_then(|(_, name, start)| slow_command_message(&name, start.elapsed(), self.model.slow_command_threshold));
+        match result {
+            Ok(Some(message)) => {
+                match time_message {
+                    Some(time_message) => self.info(&format!("{} ({})", message, time_message)),
+                    None => self.info(&message),
+                }
+            },
+            Ok(None) => {
+                if let Some(time_message) = time_message {
+                    self.info(&time_message);
+                }
             },
+            Err(error) => self.error(Error::Msg(error)),
+        }
+    }
+
+    /// Send a custom command to the application, after its context and its id.
+    /// The id is one more than the one of the previous custom command, so that the application
+    /// can report the result of the command and the results of the older commands are ignored.
+    /// The time taken by the command is measured until the application reports its result.
+    pub fn send_custom_command(&mut self, source: CommandSource, command: COMM, name: &str) -> u64 {
+        self.model.custom_command_id += 1;
+        let id = self.model.custom_command_id;
+        // NOTE: Only measure the time when a threshold is set to avoid the overhead.
+        self.model.custom_command_start = self.model.slow_command_threshold
+            .map(|_| (id, name.to_string(), Instant::now()));
+        self.model.relm.stream().emit(CommandContext(source));
+        self.model.relm.stream().emit(CustomCommandId(id));
+        self.model.relm.stream().emit(CustomCommand(command));
+        id
+    }
+
+    /// Ask the command filter what to do with the command.
+    fn filter_command(&self, command: FilteredCommand<COMM>) -> CommandFilterResult<COMM> {
+        match self.model.command_filter {
+            Some(ref filter) => filter(command, &self.model.command_source),
+            None => CommandFilterResult::Run,
         }
     }
 
@@ -153,8 +518,12 @@ impl<COMM, SETT> Mg<COMM, SETT>
             if current_mode == Mode::Input || current_mode == Mode::BlockingInput {
                 let mut should_reset = false;
                 if let Some(callback) = self.model.input_callback.take() {
-                    self.model.answer = input.clone();
-                    callback(input, self.model.shortcut_pressed);
+                    self.model.dialog_generation += 1;
+                    // NOTE: a password is not kept.
+                    if !self.model.password_input {
+                        self.model.answer = input.clone();
+                    }
+                    callback.call_box((input, self.model.shortcut_pressed));
                     should_reset = true;
                 }
                 self.model.choices.clear();
@@ -166,6 +535,13 @@ impl<COMM, SETT> Mg<COMM, SETT>
                 }
             }
             else {
+                // NOTE: running a command from the history window returns to the normal mode.
+                self.model.mode_before_history = None;
+                if self.model.current_command_mode == ':' {
+                    if let Some(ref command) = input {
+                        self.add_to_history(command);
+                    }
+                }
                 self.handle_command(input, true, None)
             };
         if let Some(message) = message {
@@ -173,7 +549,64 @@ impl<COMM, SETT> Mg<COMM, SETT>
         }
     }
 
+    /// Execute a built-in command, written `text`, if the command filter allows it.
+    fn run_builtin_command(&mut self, text: &str, command: BuiltinCommand, prefix: Option<u32>) {
+        // NOTE: the filter is consulted before executing the built-in commands too, so that it can
+        // block them (like `!`) or replace them.
+        match self.filter_command(FilteredCommand::Builtin(text)) {
+            CommandFilterResult::Run => self.execute_builtin_command(command, prefix),
+            CommandFilterResult::Skip => (),
+            CommandFilterResult::Replace(replacement) => self.run_command(replacement),
+        }
+    }
+
+    /// Execute the commands of a config file in order, with the `Config` source.
+    /// The lines that the config syntax does not support are executed as built-in commands (like
+    /// `setlocal` or a user command) and the errors of the other lines are returned.
+    pub fn execute_config_commands(&mut self, commands: Vec<ConfigCommand<COMM>>) -> Vec<Error> {
+        let mut errors = vec![];
+        for config_command in commands {
+            self.model.command_source = CommandSource::Config {
+                file: Some(config_command.line.file.clone()),
+                line: Some(config_command.line.number),
+            };
+            let command = self.expand_config_command(config_command.command, &config_command.line.text);
+            match command {
+                Ok(command) => self.call_command(command),
+                Err(error) => {
+                    let text = config_command.line.text.trim();
+                    match self.parse_builtin_command(text) {
+                        Some(builtin_command) => self.run_builtin_command(text, builtin_command, None),
+                        None => errors.push(error),
+                    }
+                },
+            }
+        }
+        errors
+    }
+
+    /// Parse the line of a config command again after expanding the environment variables in it.
+    /// The command is kept as is when the expanded line does not parse.
+    fn expand_config_command(&mut self, command: Result<Command<COMM>, Error>, line: &str)
+        -> Result<Command<COMM>, Error>
+    {
+        let is_mapping = command.as_ref().map(is_mapping_command).unwrap_or(false);
+        if !line.contains('$') || is_mapping {
+            return command;
+        }
+        let mut parse_result = self.parse_line_with_env(line.trim(), None);
+        if parse_result.errors.is_empty() && parse_result.commands.len() == 1 {
+            Ok(parse_result.commands.remove(0))
+        }
+        else {
+            command
+        }
+    }
+
     /// Execute the commands and show the errors contained in the parse result.
+    /// The commands do not change the mode: only when `activated` is true, the normal mode is
+    /// entered after executing them, so that a batch of commands (like the config) behaves the
+    /// same as executing them one by one.
     pub fn execute_commands(&mut self, mut parse_result: ParseResult<COMM>, activated: bool) {
         for command in parse_result.commands.drain(..) {
             self.call_command(command);
@@ -186,47 +619,181 @@ impl<COMM, SETT> Mg<COMM, SETT>
         }
     }
 
+    /// Handle the `quit` (or `q`) command like closing the window.
+    pub fn quit_command(&mut self) {
+        self.model.relm.stream().emit(CloseRequested(CloseReason::QuitCommand));
+    }
+
     /// Handle the command activate event.
     pub fn handle_command(&mut self, command: Option<String>, activated: bool, prefix: Option<u32>)
         -> Option<Msg<COMM, SETT>>
     {
         if let Some(command) = command {
+            let command =
+                if self.is_normal_command() {
+                    let commands = split_commands(&command);
+                    if commands.len() > 1 {
+                        return self.handle_command_chain(commands, activated, prefix);
+                    }
+                    if is_shell_command(&command) {
+                        command
+                    }
+                    else {
+                        self.expand_alias(&unescape_semicolons(&command))
+                    }
+                }
+                else {
+                    command
+                };
+            let builtin_command =
+                if self.is_normal_command() {
+                    self.parse_builtin_command(&command)
+                }
+                else {
+                    None
+                };
+            if let Some(builtin_command) = builtin_command {
+                self.run_builtin_command(&command, builtin_command, prefix);
+                if activated {
+                    self.return_to_normal_mode();
+                }
+                return None;
+            }
             if self.is_normal_command() || !activated {
-                let parse_result = self.model.settings_parser.parse_line(&command, prefix);
+                // NOTE: the quit command of the application takes precedence over the built-in
+                // one, for `q` too.
+                if let Some(quit_command) = app_quit_command::<COMM>(&command) {
+                    return self.handle_command(Some(quit_command), activated, prefix);
+                }
+                // NOTE: Only measure the time when a threshold is set to avoid the overhead.
+                let start = self.model.slow_command_threshold.map(|_| Instant::now());
+                // NOTE: the action of a mapping was expanded before being executed.
+                let parse_result =
+                    match self.model.command_source {
+                        CommandSource::Mapping { .. } => self.model.settings_parser.parse_line(&command, prefix),
+                        _ => self.parse_line_with_env(&command, prefix),
+                    };
+                // NOTE: the prefixes are only expanded for the commands typed or executed by a
+                // mapping, the config and the commands sent by the application are parsed
+                // strictly.
+                let unknown_name =
+                    if expands_command_prefix(&self.model.command_source) {
+                        unknown_command_name(&parse_result)
+                    }
+                    else {
+                        None
+                    };
+                if let Some(name) = unknown_name {
+                    match expand_command_prefix(&command, &name, &command_names::<COMM>()) {
+                        Ok(Some(full_command)) => return self.handle_command(Some(full_command), activated, prefix),
+                        Ok(None) => (),
+                        Err(candidates) => {
+                            self.error(Error::Msg(format!("Ambiguous command: {} ({})", name, candidates.join(", "))));
+                            if activated {
+                                self.return_to_normal_mode();
+                            }
+                            return None;
+                        },
+                    }
+                }
+                self.model.command_text = Some(command.clone());
                 self.execute_commands(parse_result, activated);
+                self.model.command_text = None;
+                if let Some(start) = start {
+                    self.report_command_time(&command, start.elapsed());
+                }
             }
             else {
                 // If activated is true, it means the user pressed Enter to finish the special
                 // command. If it was false, that means that the user activated a command via a
                 // shortcut.
-                return self.handle_special_command(Final, &command);
+                self.handle_special_command(Final, &command);
             }
         }
         None
     }
 
+    /// Execute the commands of a chain in order.
+    /// The remaining commands are not executed after an error if the chain is set to stop on
+    /// errors.
+    fn handle_command_chain(&mut self, commands: Vec<String>, activated: bool, prefix: Option<u32>)
+        -> Option<Msg<COMM, SETT>>
+    {
+        for command in commands {
+            self.model.command_failed = false;
+            let chained_command =
+                if is_shell_command(&command) {
+                    command.trim().to_string()
+                }
+                else {
+                    unescape_semicolons(command.trim())
+                };
+            self.model.chained_command = Some(chained_command);
+            let message = self.handle_command(Some(command), false, prefix);
+            self.model.chained_command = None;
+            if let Some(message) = message {
+                self.model.relm.stream().emit(message);
+            }
+            if self.model.command_failed && self.model.stop_command_chain_on_error {
+                break;
+            }
+        }
+        if activated {
+            self.return_to_normal_mode();
+        }
+        None
+    }
+
+    /// Set a function deciding whether each command is executed, skipped or replaced by another
+    /// one. It receives where the command comes from, to treat the config differently.
+    /// To also filter the commands of the config executed at startup, the filter must be given
+    /// when creating the `Mg` widget.
+    pub fn set_command_filter(&mut self, filter: CommandFilterFn<COMM>) {
+        self.model.command_filter = Some(filter);
+    }
+
+    /// Set whether the remaining commands of a chain are skipped after a command fails.
+    pub fn set_stop_command_chain_on_error(&mut self, stop: bool) {
+        self.model.stop_command_chain_on_error = stop;
+    }
+
     /// Handle a special command activate or key press event.
-    pub fn handle_special_command(&mut self, activation_type: ActivationType, command: &str) -> Option<Msg<COMM, SETT>> {
+    pub fn handle_special_command(&mut self, activation_type: ActivationType, command: &str) {
+        if activation_type == Final {
+            // NOTE: Only the activated commands are remembered, not the ones cancelled with Escape.
+            self.model.last_special_commands.insert(self.model.current_command_mode, command.to_string());
+        }
         if let Ok(special_command) = COMM::identifier_to_command(self.model.current_command_mode, command) {
             if activation_type == Final {
                 self.return_to_normal_mode();
             }
-            Some(CustomCommand(special_command))
+            let source = self.model.command_source.clone();
+            self.send_custom_command(source, special_command, command);
         }
-        else {
-            None
+    }
+
+    /// Show the time taken by a command if it exceeds the slow command threshold.
+    fn report_command_time(&mut self, command: &str, elapsed: Duration) {
+        if let Some(message) = slow_command_message(command, elapsed, self.model.slow_command_threshold) {
+            self.info(&message);
         }
     }
 
-    fn show_parse_error(&mut self, error: Error) {
-        if let Error::Parse(ref parse_error) = error {
+    /// Set the duration from which the time taken by a command is shown to the user.
+    /// Use None to disable the measurement.
+    pub fn set_slow_command_threshold(&mut self, threshold: Option<Duration>) {
+        self.model.slow_command_threshold = threshold;
+    }
+
+    /// Show a parse error to the user.
+    pub fn show_parse_error(&mut self, error: Error) {
+        let command_names = command_names::<COMM>();
+        if let Some(message) = parse_error_message(&error, &command_names) {
+            // NOTE: tell which command of the chain failed.
             let message =
-                match parse_error.typ {
-                    MissingArgument => "Argument required".to_string(),
-                    NoCommand => return,
-                    Parse => format!("Parse error: unexpected {}, expecting: {}", parse_error.unexpected,
-                                     parse_error.expected),
-                    UnknownCommand => format!("Not a command: {}", parse_error.unexpected),
+                match self.model.chained_command {
+                    Some(ref command) => format!("{}: {}", command, message),
+                    None => message,
                 };
             self.error(Error::Msg(message));
         }
@@ -234,3 +801,336 @@ impl<COMM, SETT> Mg<COMM, SETT>
         error!("{}", error);
     }
 }
+
+/// Replace the command name by the only command name starting with it.
+/// Returns the candidates when several commands start with the name.
+pub(crate) fn expand_command_prefix(command: &str, name: &str, command_names: &[String])
+    -> ::std::result::Result<Option<String>, Vec<String>>
+{
+    let command = command.trim_left();
+    if name.is_empty() || !command.starts_with(name) {
+        return Ok(None);
+    }
+    let mut candidates: Vec<_> = command_names.iter()
+        .filter(|command_name| command_name.starts_with(name))
+        .cloned()
+        .collect();
+    // NOTE: a name that is already complete is not expanded to avoid expanding it again and again.
+    if candidates.iter().any(|candidate| candidate == name) {
+        return Ok(None);
+    }
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(format!("{}{}", candidates[0], &command[name.len()..]))),
+        _ => {
+            candidates.sort();
+            Err(candidates)
+        },
+    }
+}
+
+/// Check if the prefixes of the command names are expanded for the commands from this source.
+fn expands_command_prefix(source: &CommandSource) -> bool {
+    match *source {
+        CommandSource::Mapping { .. } | CommandSource::Typed => true,
+        CommandSource::Config { .. } | CommandSource::Programmatic | CommandSource::Remote => false,
+    }
+}
+
+/// Get the name of the command if the only error of the parse result is an unknown command.
+fn unknown_command_name<COMM>(parse_result: &ParseResult<COMM>) -> Option<String> {
+    if parse_result.errors.len() != 1 {
+        return None;
+    }
+    if let Error::Parse(ref parse_error) = parse_result.errors[0] {
+        if let UnknownCommand = parse_error.typ {
+            return Some(parse_error.unexpected.clone());
+        }
+    }
+    None
+}
+
+/// Get the message shown to the user for a parse error.
+/// The closest command names are suggested for an unknown command.
+/// Returns None if the error is not a parse error or should not be shown.
+pub fn parse_error_message(error: &Error, command_names: &[String]) -> Option<String> {
+    if let Error::Parse(ref parse_error) = *error {
+        match parse_error.typ {
+            MissingArgument => Some("Argument required".to_string()),
+            NoCommand => None,
+            Parse => Some(format!("Parse error: unexpected {}, expecting: {}", parse_error.unexpected,
+                                  parse_error.expected)),
+            UnknownCommand => {
+                let suggestions = closest_matches(&parse_error.unexpected, command_names);
+                if suggestions.is_empty() {
+                    Some(format!("Not a command: {}", parse_error.unexpected))
+                }
+                else {
+                    Some(format!("Not a command: {}, did you mean: {}?", parse_error.unexpected,
+                                 suggestions.join(", ")))
+                }
+            },
+        }
+    }
+    else {
+        None
+    }
+}
+
+/// Replace the environment variables written `$VAR` or `${VAR}` by their value.
+/// A literal `$` is written `$$`.
+/// The undefined variables are replaced by an empty string and their names are returned.
+pub fn expand_env_variables(text: &str) -> (String, Vec<String>) {
+    let mut result = String::new();
+    let mut undefined_variables = vec![];
+    let mut rest = text;
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        if rest.starts_with('$') {
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+        let (name, len) =
+            if rest.starts_with('{') {
+                match rest.find('}') {
+                    Some(end) => (&rest[1..end], end + 1),
+                    None => ("", 0),
+                }
+            }
+            else {
+                let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or_else(|| rest.len());
+                (&rest[..end], end)
+            };
+        if name.is_empty() {
+            // NOTE: a `$` that does not start a variable is kept as is.
+            result.push('$');
+        }
+        else {
+            match env::var(name) {
+                Ok(value) => result.push_str(&value),
+                Err(_) => undefined_variables.push(name.to_string()),
+            }
+            rest = &rest[len..];
+        }
+    }
+    result.push_str(rest);
+    (result, undefined_variables)
+}
+
+/// Get the message telling the time taken by a command if it exceeds the slow command threshold.
+fn slow_command_message(command: &str, elapsed: Duration, threshold: Option<Duration>) -> Option<String> {
+    let threshold = threshold?;
+    if elapsed < threshold {
+        return None;
+    }
+    let name = command.split_whitespace().next().unwrap_or_default();
+    let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0;
+    let message = format!("{} took {:.1}s", name, seconds);
+    debug!("{}", message);
+    Some(message)
+}
+
+/// Check if the command creates or removes a mapping.
+fn is_mapping_command<COMM>(command: &Command<COMM>) -> bool {
+    match *command {
+        Map { .. } | Unmap { .. } => true,
+        _ => false,
+    }
+}
+
+/// Check if the command is a shell command (`!command`), whose text is kept as is.
+fn is_shell_command(command: &str) -> bool {
+    command.trim_left().starts_with('!')
+}
+
+/// Split the commands separated by unescaped `;`.
+/// The definition of a user command is not split since its expansion contains the separators.
+/// A shell command extends to the end of the line since `;` is part of the shell syntax.
+/// The `\;` escape sequences are kept in the commands.
+pub(crate) fn split_commands(command: &str) -> Vec<String> {
+    if command.split_whitespace().next() == Some(USER_COMMAND_COMMAND) {
+        return vec![command.to_string()];
+    }
+    let mut commands = vec![];
+    let mut current_command = String::new();
+    let mut escaped = false;
+    for (index, character) in command.char_indices() {
+        if character == '!' && current_command.trim().is_empty() {
+            current_command.push_str(&command[index..]);
+            break;
+        }
+        if character == ';' && !escaped {
+            commands.push(current_command);
+            current_command = String::new();
+        }
+        else {
+            current_command.push(character);
+        }
+        escaped = character == '\\';
+    }
+    commands.push(current_command);
+    commands.into_iter()
+        .filter(|command| !command.trim().is_empty())
+        .collect()
+}
+
+/// Replace the `\;` escape sequences by a literal `;`.
+/// The other backslashes are kept, so that `\\` stays as is.
+pub(crate) fn unescape_semicolons(command: &str) -> String {
+    command.replace(r"\;", ";")
+}
+
+/// Replace the `<lt>` escape sequences by a literal `<`.
+pub fn unescape_lt(action: &str) -> String {
+    action.replace("<lt>", "<")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::time::Duration;
+
+    use app::{CommandSource, builtin_application_commands};
+    use super::{
+        BuiltinAppCommand,
+        expand_command_prefix,
+        expand_env_variables,
+        expands_command_prefix,
+        slow_command_message,
+        split_commands,
+        unescape_lt,
+        unescape_semicolons,
+    };
+
+    fn strings(elements: &[&str]) -> Vec<String> {
+        elements.iter().map(|element| element.to_string()).collect()
+    }
+
+    #[test]
+    fn test_expands_command_prefix() {
+        assert!(expands_command_prefix(&CommandSource::Typed));
+        assert!(expands_command_prefix(&CommandSource::Mapping { keys: vec![] }));
+        // The commands sent by the application must use the full names.
+        assert!(!expands_command_prefix(&CommandSource::Programmatic));
+        assert!(!expands_command_prefix(&CommandSource::Remote));
+        assert!(!expands_command_prefix(&CommandSource::Config { file: None, line: None }));
+    }
+
+    #[test]
+    fn test_split_commands() {
+        assert_eq!(split_commands("set a = 1; set b = 2"), strings(&["set a = 1", " set b = 2"]));
+        assert_eq!(split_commands(r"open a\;b; quit"), strings(&[r"open a\;b", " quit"]));
+        // Only the semicolon is escaped, so the backslash before it is always an escape.
+        assert_eq!(split_commands(r"open a\\; quit"), strings(&[r"open a\\; quit"]));
+        assert_eq!(split_commands(r"open a\\ ; quit"), strings(&[r"open a\\ ", " quit"]));
+        assert_eq!(split_commands("quit;;"), strings(&["quit"]));
+    }
+
+    #[test]
+    fn test_split_shell_commands() {
+        assert_eq!(split_commands("!a; b"), strings(&["!a; b"]));
+        assert_eq!(split_commands(" !a; b"), strings(&[" !a; b"]));
+        assert_eq!(split_commands("set a = 1; !a; b"), strings(&["set a = 1", " !a; b"]));
+        assert_eq!(split_commands("open !a; quit"), strings(&["open !a", " quit"]));
+    }
+
+    #[test]
+    fn test_split_user_command_definition() {
+        assert_eq!(split_commands("command name set a = 1; quit"), strings(&["command name set a = 1; quit"]));
+    }
+
+    #[test]
+    fn test_unescape_semicolons() {
+        assert_eq!(unescape_semicolons(r"open a\;b"), "open a;b");
+        assert_eq!(unescape_semicolons(r"open a\\;b"), r"open a\;b");
+        assert_eq!(unescape_semicolons(r"open a\\\;b"), r"open a\\;b");
+        assert_eq!(unescape_semicolons(r"open a\\b"), r"open a\\b");
+        assert_eq!(unescape_semicolons(r"open a\nb"), r"open a\nb");
+        assert_eq!(unescape_semicolons(r"open a\"), r"open a\");
+    }
+
+    #[test]
+    fn test_unique_prefix() {
+        let names = strings(&["open", "quit", "set", "setlocal"]);
+        assert_eq!(expand_command_prefix("qui", "qui", &names), Ok(Some("quit".to_string())));
+        assert_eq!(expand_command_prefix("  op crates.io", "op", &names), Ok(Some("open crates.io".to_string())));
+        assert_eq!(expand_command_prefix("setl boolean = true", "setl", &names),
+            Ok(Some("setlocal boolean = true".to_string())));
+    }
+
+    #[test]
+    fn test_ambiguous_prefix() {
+        let names = strings(&["setlocal", "set", "open", "quit"]);
+        // The candidates are sorted.
+        assert_eq!(expand_command_prefix("se boolean", "se", &names), Err(strings(&["set", "setlocal"])));
+    }
+
+    #[test]
+    fn test_no_expansion() {
+        let names = strings(&["open", "quit", "set", "setlocal"]);
+        assert_eq!(expand_command_prefix("foo", "foo", &names), Ok(None));
+        assert_eq!(expand_command_prefix("", "", &names), Ok(None));
+        // A complete name is not expanded, even if it is the prefix of another name.
+        assert_eq!(expand_command_prefix("set boolean = true", "set", &names), Ok(None));
+    }
+
+    #[test]
+    fn test_every_application_command_is_dispatched() {
+        for name in builtin_application_commands() {
+            assert!(BuiltinAppCommand::from_name(name).is_some(), "No dispatch for the application command {}", name);
+        }
+    }
+
+    #[test]
+    fn test_application_commands_are_distinct() {
+        let names = builtin_application_commands();
+        let commands: Vec<_> = names.iter()
+            .filter_map(|name| BuiltinAppCommand::from_name(name))
+            .collect();
+        for (index, command) in commands.iter().enumerate() {
+            assert!(!commands[index + 1..].contains(command), "{:?} is dispatched for two names", command);
+        }
+    }
+
+    #[test]
+    fn test_unknown_application_command() {
+        assert_eq!(BuiltinAppCommand::from_name("unknown-command"), None);
+    }
+
+    #[test]
+    fn test_expand_env_variables() {
+        env::set_var("MG_TEST_EXPAND_HOME", "/home/user");
+        env::set_var("MG_TEST_EXPAND_NAME", "mg");
+        assert_eq!(expand_env_variables("open $MG_TEST_EXPAND_HOME/${MG_TEST_EXPAND_NAME}.rs"),
+            ("open /home/user/mg.rs".to_string(), vec![]));
+        assert_eq!(expand_env_variables("echo $$MG_TEST_EXPAND_NAME"), ("echo $MG_TEST_EXPAND_NAME".to_string(), vec![]));
+        // A `$` that does not start a variable is kept.
+        assert_eq!(expand_env_variables("echo 5$ $ ${"), ("echo 5$ $ ${".to_string(), vec![]));
+    }
+
+    #[test]
+    fn test_expand_undefined_env_variables() {
+        env::remove_var("MG_TEST_EXPAND_UNDEFINED");
+        assert_eq!(expand_env_variables("open $MG_TEST_EXPAND_UNDEFINED/a ${MG_TEST_EXPAND_UNDEFINED}"),
+            ("open /a ".to_string(), strings(&["MG_TEST_EXPAND_UNDEFINED", "MG_TEST_EXPAND_UNDEFINED"])));
+    }
+
+    #[test]
+    fn test_unescape_lt() {
+        assert_eq!(unescape_lt("insert <lt>Esc>"), "insert <Esc>");
+        assert_eq!(unescape_lt("insert <Esc>"), "insert <Esc>");
+    }
+
+    #[test]
+    fn test_slow_command_message() {
+        let threshold = Some(Duration::from_millis(500));
+        assert_eq!(slow_command_message("open crates.io", Duration::from_millis(1300), threshold),
+            Some("open took 1.3s".to_string()));
+        assert_eq!(slow_command_message("open", Duration::from_millis(500), threshold), Some("open took 0.5s".to_string()));
+        assert_eq!(slow_command_message("open", Duration::from_millis(499), threshold), None);
+        // The time is not measured without a threshold.
+        assert_eq!(slow_command_message("open", Duration::from_secs(10), None), None);
+    }
+}
@@ -21,20 +21,39 @@
 
 use std::collections::HashMap;
 use std::fs::{File, create_dir_all};
-use std::io::{self, BufReader, Write};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
-use mg_settings::{Config, EnumFromStr, Parser, ParseResult};
+use mg_settings::{Command, Config, EnumFromStr, EnumMetaData, Parser, ParseResult};
+use mg_settings::errors::Error;
+use mg_settings::key::Key;
 
-use app::settings::DefaultConfig;
+use app::alias::{ALIAS_COMMAND, extract_definitions};
+use app::command::parse_error_message;
+use app::help::command_names;
+use app::dialog::DIALOG_MAP_COMMAND;
+use app::shortcut::KEYCODE_MAP_COMMAND;
+use app::user_command::USER_COMMAND_COMMAND;
+use app::path::expand_tilde;
+use app::settings::{DefaultConfig, xdg_base_dir};
+use key_converter::{string_to_key, string_to_keys};
 use {Mode, file};
+use file::{IncludeError, SourceLine};
 use super::{
     Modes,
     ModesHash,
+    COMMAND_HISTORY_WINDOW,
     COMMAND_MODE,
+    COMPLETE_ACCEPT_COMMAND,
+    COMPLETE_ACCEPT_NEXT_COMMAND,
+    COMPLETE_FIRST_COMMAND,
+    COMPLETE_LAST_COMMAND,
     COMPLETE_NEXT_COMMAND,
     COMPLETE_PREVIOUS_COMMAND,
+    COMPLETION_DELETE_MARKED_COMMAND,
+    COMPLETION_TOGGLE_MARK_COMMAND,
     COPY,
+    COPY_MESSAGE,
     CUT,
     ENTRY_DELETE_NEXT_CHAR,
     ENTRY_DELETE_NEXT_WORD,
@@ -45,36 +64,292 @@ use super::{
     ENTRY_PREVIOUS_CHAR,
     ENTRY_PREVIOUS_WORD,
     ENTRY_SMART_HOME,
+    HISTORY_NEXT,
+    HISTORY_PREVIOUS,
     NORMAL_MODE,
     PASTE,
     PASTE_SELECTION,
+    REPEAT_LAST_ENTRY_COMMAND,
+    SHOW_ERROR_DETAILS,
+    SHOW_MAPPINGS,
 };
 
+/// The default maximum depth of the nested includes of a config file.
+pub const DEFAULT_MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A config file to execute.
+/// The config files are executed in order, so that a file overrides the settings and mappings of
+/// the files before it.
+pub struct ConfigSource {
+    /// The maximum depth of the nested includes, from which the includes are skipped.
+    pub max_include_depth: usize,
+    /// Whether the file is skipped when it does not exist instead of reporting an error.
+    pub optional: bool,
+    /// The path of the file or the error that occurred while getting it.
+    pub path: io::Result<PathBuf>,
+}
+
+impl ConfigSource {
+    /// Create the source of a config file that must exist.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        ConfigSource {
+            max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+            optional: false,
+            path: Ok(path.into()),
+        }
+    }
+
+    /// Create the source of a config file that is skipped when it does not exist.
+    pub fn optional<P: Into<PathBuf>>(path: P) -> Self {
+        ConfigSource {
+            max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+            optional: true,
+            path: Ok(path.into()),
+        }
+    }
+
+    /// Set the maximum depth of the nested includes.
+    pub fn max_include_depth(mut self, max_include_depth: usize) -> Self {
+        self.max_include_depth = max_include_depth;
+        self
+    }
+
+    /// Create the sources for a single config file.
+    /// This is the config file that was given to `Mg` before it accepted multiple config files.
+    pub fn single(path: io::Result<PathBuf>) -> Vec<Self> {
+        vec![ConfigSource {
+            max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+            optional: false,
+            path,
+        }]
+    }
+}
+
+/// A command of a config file, with its line.
+pub struct ConfigCommand<COMM> {
+    /// The command parsed with the config syntax, or the error of the parser for the lines that
+    /// the config syntax does not support: they are executed as built-in commands (like
+    /// `setlocal` or a user command) if they are one, and the error is shown otherwise.
+    pub command: Result<Command<COMM>, Error>,
+    /// The line of the command.
+    pub line: SourceLine,
+}
+
+/// The result of parsing a config file.
+pub struct ParsedConfig<COMM> {
+    /// The aliases defined in the file.
+    pub aliases: Vec<(String, String)>,
+    /// The commands of the file, in order.
+    pub commands: Vec<ConfigCommand<COMM>>,
+    /// The shortcuts of the input dialogs defined in the file.
+    pub dialog_shortcuts: Vec<(Key, String)>,
+    /// The errors of the file, which are not commands.
+    pub errors: Vec<Error>,
+    /// The name of the file, which is None when the path of a required file could not be found.
+    pub file: Option<PathBuf>,
+    /// The mappings matched by the physical keys too, with the prefix of their mode.
+    pub keycode_mappings: Vec<(String, Vec<Key>)>,
+    /// The commands defined in the file.
+    pub user_commands: Vec<(String, String)>,
+}
+
+impl<COMM> ParsedConfig<COMM> {
+    fn new(file: Option<PathBuf>) -> Self {
+        ParsedConfig {
+            aliases: vec![],
+            commands: vec![],
+            dialog_shortcuts: vec![],
+            errors: vec![],
+            file,
+            keycode_mappings: vec![],
+            user_commands: vec![],
+        }
+    }
+}
+
+/// Copy the config sources whose path was found, to parse them again later.
+pub fn clone_sources(sources: &[ConfigSource]) -> Vec<ConfigSource> {
+    sources.iter()
+        .filter_map(|source| source.path.as_ref().ok().map(|path| ConfigSource {
+            max_include_depth: source.max_include_depth,
+            optional: source.optional,
+            path: Ok(path.clone()),
+        }))
+        .collect()
+}
+
 /// Create the default config directories and files.
-pub fn create_default_config(default_config: Vec<DefaultConfig>) -> Result<(), io::Error> {
+/// Every item is created even if another one failed.
+/// The paths that were created are returned with the errors of all the items.
+pub fn create_default_config(default_config: Vec<DefaultConfig>) -> (Vec<PathBuf>, Vec<io::Error>) {
+    let mut created = vec![];
+    let mut errors = vec![];
+    let mut failed_directories = vec![];
     for config_item in default_config {
         match config_item {
-            DefaultConfig::Dir(directory) => create_dir_all(directory?)?,
-            DefaultConfig::File(name, content) => create_default_config_file(&name?, content)?,
+            DefaultConfig::Dir(directory) => {
+                let directory =
+                    match directory {
+                        Ok(directory) => expand_tilde(&directory),
+                        Err(error) => {
+                            errors.push(error);
+                            continue;
+                        },
+                    };
+                match create_default_config_dir(&directory) {
+                    Ok(true) => created.push(directory),
+                    Ok(false) => (),
+                    Err(error) => {
+                        errors.push(path_error("directory", &directory, error));
+                        failed_directories.push(directory);
+                    },
+                }
+            },
+            DefaultConfig::File(name, content) => {
+                let name =
+                    match name {
+                        Ok(name) => expand_tilde(&name),
+                        Err(error) => {
+                            errors.push(error);
+                            continue;
+                        },
+                    };
+                if in_failed_directory(&name, &failed_directories) {
+                    continue;
+                }
+                match create_default_config_file(&name, content) {
+                    Ok(true) => created.push(name),
+                    Ok(false) => (),
+                    Err(error) => errors.push(path_error("file", &name, error)),
+                }
+            },
+            DefaultConfig::Tree(root, files) => {
+                let root =
+                    match root {
+                        Ok(root) => expand_tilde(&root),
+                        Err(error) => {
+                            errors.push(error);
+                            continue;
+                        },
+                    };
+                create_default_config_tree(&root, files, &mut created, &mut errors, &mut failed_directories);
+            },
         }
     }
-    Ok(())
+    (created, errors)
+}
+
+/// Create the directory if it does not exist.
+/// Returns whether the directory was created.
+fn create_default_config_dir(directory: &Path) -> Result<bool, io::Error> {
+    if directory.exists() {
+        Ok(false)
+    }
+    else {
+        create_dir_all(directory)?;
+        Ok(true)
+    }
+}
+
+/// Create the files of a tree, with their intermediate directories, if they do not exist.
+/// The files in a directory that could not be created are skipped.
+fn create_default_config_tree(root: &Path, files: &[(&str, &'static str)], created: &mut Vec<PathBuf>,
+    errors: &mut Vec<io::Error>, failed_directories: &mut Vec<PathBuf>)
+{
+    for &(relative_path, content) in files {
+        let path = root.join(relative_path);
+        if in_failed_directory(&path, failed_directories) {
+            continue;
+        }
+        if let Some(directory) = path.parent() {
+            match create_default_config_dir(directory) {
+                Ok(true) => created.push(directory.to_path_buf()),
+                Ok(false) => (),
+                Err(error) => {
+                    errors.push(path_error("directory", directory, error));
+                    failed_directories.push(directory.to_path_buf());
+                    continue;
+                },
+            }
+        }
+        match create_default_config_file(&path, content) {
+            Ok(true) => created.push(path),
+            Ok(false) => (),
+            Err(error) => errors.push(path_error("file", &path, error)),
+        }
+    }
+}
+
+/// Check if the file is in a directory that could not be created.
+/// NOTE: the error of the directory was already reported, so the file is skipped to avoid a
+/// cascade of errors.
+fn in_failed_directory(path: &Path, failed_directories: &[PathBuf]) -> bool {
+    let failed = failed_directories.iter().any(|directory| path.starts_with(directory));
+    if failed {
+        debug!("skipping default config file `{}` since its directory could not be created",
+            path.to_string_lossy());
+    }
+    failed
+}
+
+/// Create an error telling which path could not be created.
+fn path_error(kind: &str, path: &Path, error: io::Error) -> io::Error {
+    io::Error::new(error.kind(), format!("failed to create {} {}: {}", kind, path.to_string_lossy(), error))
 }
 
 /// Create the config file with its default content if it does not exist.
-pub fn create_default_config_file(path: &Path, content: &'static str) -> Result<(), io::Error> {
-    if !path.exists() {
-        let mut file = File::create(path)?;
-        write!(file, "{}", content)?;
+/// Returns whether the file was created.
+pub fn create_default_config_file(path: &Path, content: &'static str) -> Result<bool, io::Error> {
+    let path = expand_tilde(path);
+    if path.exists() {
+        return Ok(false);
     }
-    Ok(())
+    let mut file = File::create(path)?;
+    write!(file, "{}", content)?;
+    Ok(true)
+}
+
+/// Get the names of the built-in application commands, like `complete-next` or `entry-end`.
+pub fn builtin_application_commands() -> Vec<&'static str> {
+    vec![COMMAND_HISTORY_WINDOW, COMPLETE_ACCEPT_COMMAND, COMPLETE_ACCEPT_NEXT_COMMAND, COMPLETE_FIRST_COMMAND,
+        COMPLETE_LAST_COMMAND, COMPLETE_NEXT_COMMAND, COMPLETE_PREVIOUS_COMMAND, COMPLETION_DELETE_MARKED_COMMAND,
+        COMPLETION_TOGGLE_MARK_COMMAND, COPY, COPY_MESSAGE, CUT, ENTRY_DELETE_NEXT_CHAR, ENTRY_DELETE_NEXT_WORD,
+        ENTRY_DELETE_PREVIOUS_WORD, ENTRY_END, ENTRY_NEXT_CHAR, ENTRY_NEXT_WORD, ENTRY_PREVIOUS_CHAR,
+        ENTRY_PREVIOUS_WORD, ENTRY_SMART_HOME, HISTORY_NEXT, HISTORY_PREVIOUS, PASTE, PASTE_SELECTION,
+        REPEAT_LAST_ENTRY_COMMAND, SHOW_ERROR_DETAILS, SHOW_MAPPINGS]
 }
 
-/// Parse a configuration file.
-pub fn parse_config<P: AsRef<Path>, COMM: EnumFromStr>(filename: P, user_modes: Modes, include_path: Option<PathBuf>)
+/// Parse the configuration files in order and merge their commands.
+/// The errors are prefixed with the name of the file they come from.
+pub fn parse_config<COMM: EnumFromStr + EnumMetaData>(sources: Vec<ConfigSource>, user_modes: Modes, include_path: Option<PathBuf>)
     -> (Parser<COMM>, ParseResult<COMM>, ModesHash)
 {
-    let mut parse_result = ParseResult::new();
+    let (parser, parse_results, modes, warnings) = parse_config_with_warnings(sources, user_modes, include_path, &[]);
+    for warning in warnings {
+        warn!("{}", warning);
+    }
+    let mut merged_result = ParseResult::new();
+    for parsed_config in parse_results {
+        merged_result.errors.extend(parsed_config.errors);
+        for config_command in parsed_config.commands {
+            match config_command.command {
+                Ok(command) => merged_result.commands.push(command),
+                Err(error) => merged_result.errors.push(error),
+            }
+        }
+    }
+    (parser, merged_result, modes)
+}
+
+/// Parse the configuration files in order.
+/// The errors that are not fatal (like an include from a missing include path) are returned as
+/// warnings.
+pub fn parse_config_with_warnings<COMM: EnumFromStr + EnumMetaData>(sources: Vec<ConfigSource>, user_modes: Modes,
+    include_path: Option<PathBuf>, app_commands: &[&'static str])
+    -> (Parser<COMM>, Vec<ParsedConfig<COMM>>, ModesHash, Vec<String>)
+{
+    let mut parse_results = vec![];
+    let mut warnings = vec![];
 
     let mut modes = HashMap::new();
     for mode in user_modes {
@@ -85,19 +360,207 @@ pub fn parse_config<P: AsRef<Path>, COMM: EnumFromStr>(filename: P, user_modes:
     assert!(modes.insert("c", Mode { name: COMMAND_MODE, prefix: "c", show_count: false }).is_none(),
         "Duplicate mode prefix c.");
     let config = Config {
-        application_commands: vec![COMPLETE_NEXT_COMMAND, COMPLETE_PREVIOUS_COMMAND, COPY, CUT, ENTRY_DELETE_NEXT_CHAR,
-            ENTRY_DELETE_NEXT_WORD, ENTRY_DELETE_PREVIOUS_WORD, ENTRY_END, ENTRY_NEXT_CHAR, ENTRY_NEXT_WORD,
-            ENTRY_PREVIOUS_CHAR, ENTRY_PREVIOUS_WORD, ENTRY_SMART_HOME, PASTE, PASTE_SELECTION],
+        application_commands: builtin_application_commands().into_iter()
+            .chain(app_commands.iter().cloned())
+            .collect(),
         mapping_modes: modes.keys().cloned().collect(),
     };
     let mut parser = Parser::new_with_config(config);
-    if let Some(include_path) = include_path {
-        parser.set_include_path(include_path);
+    let include_path = include_path.map(|path| expand_tilde(&path));
+    let mut missing_include_path = None;
+    if let Some(ref include_path) = include_path {
+        if !include_path.exists() && is_in_user_dir(include_path) {
+            if let Err(error) = create_dir_all(include_path) {
+                warn!("cannot create include path `{}`: {}", include_path.to_string_lossy(), error);
+            }
+        }
+        if !include_path.exists() {
+            missing_include_path = Some(include_path.clone());
+        }
+        parser.set_include_path(include_path.clone());
     }
 
-    let file = file::open(&filename);
-    let file = rtry_no_return!(parse_result, file, { return (parser, parse_result, modes); });
-    let buf_reader = BufReader::new(file);
-    let parse_result = parser.parse(buf_reader, None);
-    (parser, parse_result, modes)
+    for source in sources {
+        let filename =
+            match source.path {
+                Ok(filename) => filename,
+                Err(error) => {
+                    if !source.optional {
+                        let mut parsed_config = ParsedConfig::new(None);
+                        parsed_config.errors.push(error.into());
+                        parse_results.push(parsed_config);
+                    }
+                    continue;
+                },
+            };
+        if source.optional && !filename.exists() {
+            debug!("skipping optional config `{}` since it does not exist", filename.to_string_lossy());
+            continue;
+        }
+        let parsed_config = parse_config_file(&mut parser, filename, source.max_include_depth, include_path.as_ref(),
+            missing_include_path.as_ref(), &mut warnings);
+        parse_results.push(parsed_config);
+    }
+    (parser, parse_results, modes, warnings)
+}
+
+/// Parse a configuration file with the parser shared by all the configuration files.
+/// The includes are replaced by the lines of the included files before parsing, so that the
+/// include cycles can be detected. The errors tell the file and the line where they are.
+fn parse_config_file<COMM: EnumFromStr + EnumMetaData>(parser: &mut Parser<COMM>, filename: PathBuf, max_include_depth: usize,
+    include_path: Option<&PathBuf>, missing_include_path: Option<&PathBuf>, warnings: &mut Vec<String>)
+    -> ParsedConfig<COMM>
+{
+    let mut include_errors = vec![];
+    let lines = file::read_with_includes(&filename, include_path.map(|path| path.as_path()),
+        max_include_depth, &mut include_errors);
+    let mut parsed_config = ParsedConfig::new(Some(filename));
+    let lines =
+        match lines {
+            Ok(lines) => lines,
+            Err(error) => {
+                parsed_config.errors.push(error);
+                return parsed_config;
+            },
+        };
+    let mut errors = vec![];
+    for (line, error) in include_errors {
+        match (error, missing_include_path) {
+            // NOTE: the includes cannot work when the include path is missing, so these errors are
+            // downgraded to warnings.
+            (error @ IncludeError::Read { .. }, Some(include_path)) =>
+                warnings.push(format!("include path `{}` does not exist: {}", include_path.to_string_lossy(), error)),
+            (error, _) => errors.push(line.error(&error.to_string())),
+        }
+    }
+    let (lines, aliases) = extract_definitions(lines, ALIAS_COMMAND, &mut errors);
+    let (lines, user_commands) = extract_definitions(lines, USER_COMMAND_COMMAND, &mut errors);
+    let (lines, dialog_maps) = extract_definitions(lines, DIALOG_MAP_COMMAND, &mut errors);
+    for (line, keys, answer) in dialog_maps {
+        match string_to_key(&keys) {
+            Some(key) => parsed_config.dialog_shortcuts.push((key, answer)),
+            None => errors.push(line.error(&format!("{}: Invalid key {}", DIALOG_MAP_COMMAND, keys))),
+        }
+    }
+    let (lines, keycode_maps) = extract_definitions(lines, KEYCODE_MAP_COMMAND, &mut errors);
+    for (line, mode_prefix, keys) in keycode_maps {
+        match string_to_keys(&keys) {
+            Some(keys) => parsed_config.keycode_mappings.push((mode_prefix, keys)),
+            None => errors.push(line.error(&format!("{}: Invalid keys {}", KEYCODE_MAP_COMMAND, keys))),
+        }
+    }
+    let command_names = command_names::<COMM>();
+    // NOTE: the lines are parsed one by one since they do not all come from the same file.
+    for line in lines {
+        if line.text.trim().is_empty() || line.text.trim().starts_with('#') {
+            continue;
+        }
+        let line_result = parser.parse_line(&line.text, None);
+        if let Some(error) = line_result.errors.first() {
+            let error = line_error(&line, error, &command_names);
+            parsed_config.commands.push(ConfigCommand { command: Err(error), line });
+            continue;
+        }
+        for command in line_result.commands {
+            parsed_config.commands.push(ConfigCommand { command: Ok(command), line: line.clone() });
+        }
+    }
+    parsed_config.aliases = definitions(aliases);
+    parsed_config.user_commands = definitions(user_commands);
+    parsed_config.errors = errors;
+    parsed_config
+}
+
+/// Remove the lines of the definitions.
+fn definitions(definitions: Vec<(SourceLine, String, String)>) -> Vec<(String, String)> {
+    definitions.into_iter()
+        .map(|(_, name, expansion)| (name, expansion))
+        .collect()
+}
+
+/// Create an error telling from which config file and line the error comes.
+/// The closest command names are suggested for an unknown command.
+fn line_error(line: &SourceLine, error: &Error, command_names: &[String]) -> Error {
+    let message = parse_error_message(error, command_names).unwrap_or_else(|| error.to_string());
+    line.error(&message)
+}
+
+/// Check if the path is in the user config or data directory.
+fn is_in_user_dir(path: &Path) -> bool {
+    user_dirs().iter().any(|directory| path.starts_with(directory))
+}
+
+/// Get the user config and data directories.
+fn user_dirs() -> Vec<PathBuf> {
+    let config_dir = xdg_base_dir("XDG_CONFIG_HOME", ".config").ok();
+    let data_dir = xdg_base_dir("XDG_DATA_HOME", ".local/share").ok();
+    config_dir.into_iter().chain(data_dir).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File, create_dir_all, remove_dir_all};
+    use std::path::PathBuf;
+
+    use app::settings::DefaultConfig;
+    use super::create_default_config;
+
+    const TREE: &[(&str, &str)] = &[
+        ("config", "set boolean = true\n"),
+        ("themes/dark.css", "window { background: black; }\n"),
+        ("scripts/run.sh", "#!/bin/sh\n"),
+    ];
+
+    /// Create an empty directory for a test.
+    fn test_dir(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(name);
+        let _ = remove_dir_all(&directory);
+        create_dir_all(&directory).expect("create directory");
+        directory
+    }
+
+    #[test]
+    fn test_create_default_config_tree() {
+        let root = test_dir("mg-unit-test-default-config-tree");
+        fs::write(root.join("config"), "user config\n").expect("write config");
+        let (mut created, errors) = create_default_config(vec![DefaultConfig::Tree(Ok(root.clone()), TREE)]);
+        assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+        created.sort();
+        // The intermediate directories are created and the existing file is not reported.
+        assert_eq!(created, vec![
+            root.join("scripts"),
+            root.join("scripts/run.sh"),
+            root.join("themes"),
+            root.join("themes/dark.css"),
+        ]);
+        assert_eq!(fs::read_to_string(root.join("themes/dark.css")).expect("read theme"),
+            "window { background: black; }\n");
+        // The existing file is not overwritten.
+        assert_eq!(fs::read_to_string(root.join("config")).expect("read config"), "user config\n");
+        // Nothing is created the second time.
+        let (created, errors) = create_default_config(vec![DefaultConfig::Tree(Ok(root.clone()), TREE)]);
+        assert!(created.is_empty() && errors.is_empty());
+        remove_dir_all(root).expect("remove directory");
+    }
+
+    #[test]
+    fn test_create_default_config_errors() {
+        let base = test_dir("mg-unit-test-default-config-errors");
+        // A file is created where a directory is expected, so that this directory cannot be created.
+        let blocked_dir = base.join("blocker").join("dir");
+        File::create(base.join("blocker")).expect("create file");
+        let (created, errors) = create_default_config(vec![
+            DefaultConfig::Dir(Ok(blocked_dir.clone())),
+            DefaultConfig::File(Ok(blocked_dir.join("config")), ""),
+            DefaultConfig::File(Ok(base.join("config")), ""),
+        ]);
+        // The file of this directory is skipped instead of producing another error, and the failure
+        // does not prevent the creation of the other items.
+        assert_eq!(created, vec![base.join("config")]);
+        assert_eq!(errors.len(), 1, "unexpected errors: {:?}", errors);
+        assert!(errors[0].to_string().starts_with(
+            &format!("failed to create directory {}", blocked_dir.to_string_lossy())));
+        remove_dir_all(base).expect("remove directory");
+    }
 }
@@ -0,0 +1,188 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::mem::transmute;
+use std::ptr;
+
+use gio::File;
+use gio_sys::{self, GFile, GFileMonitor, GFileMonitorEvent, G_FILE_MONITOR_EVENT_CHANGES_DONE_HINT, G_FILE_MONITOR_NONE};
+use glib::{self, CallbackGuard};
+use glib::signal::connect;
+use glib::translate::{ToGlibPtr, from_glib_full};
+use libc::c_void;
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+
+use app::{CommandSource, Mg};
+use app::config::{clone_sources, parse_config_with_warnings};
+use app::Msg::ConfigFileChanged;
+use app::settings::value_to_string;
+
+/// The state of the config file watch.
+#[derive(Default)]
+pub struct ConfigWatch {
+    /// The monitors of the config files, which stop watching them when they are dropped.
+    monitors: Vec<glib::Object>,
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Execute the config files again.
+    /// The aliases, user commands, mappings and settings from the previous config are removed
+    /// first, while the ones added at runtime are kept.
+    pub fn reload_config(&mut self) {
+        for (mode, keys) in self.model.config_mappings.drain() {
+            if let Some(mappings) = self.model.mappings.get_mut(mode) {
+                mappings.remove(&keys);
+            }
+        }
+        for mapping in self.model.config_keycode_mappings.drain() {
+            self.model.keycode_mappings.remove(&mapping);
+        }
+        for alias in self.model.config_aliases.drain() {
+            self.model.aliases.remove(&alias);
+        }
+        for name in self.model.config_user_commands.drain() {
+            self.model.user_commands.remove(&name);
+        }
+        self.update_command_completer();
+        for key in self.model.config_dialog_shortcuts.drain() {
+            self.model.dialog_shortcuts.remove(&key);
+        }
+        let old_config_settings: Vec<_> = self.model.config_settings.drain().collect();
+        let sources = clone_sources(&self.model.config_sources);
+        let app_commands: Vec<_> = self.model.app_commands.keys().cloned().collect();
+        let (_, parse_results, _, warnings) = parse_config_with_warnings::<COMM>(sources, self.model.user_modes,
+            self.model.include_path.clone(), &app_commands);
+        for warning in warnings {
+            warn!("{}", warning);
+        }
+        let mut has_errors = false;
+        for parsed_config in parse_results {
+            // NOTE: the errors are prefixed by the file name, so they are shown directly instead
+            // of by execute_commands().
+            for error in parsed_config.errors {
+                has_errors = true;
+                self.error(error);
+            }
+            for (alias, expansion) in parsed_config.aliases {
+                self.add_config_alias(&alias, &expansion);
+            }
+            for (name, expansion) in parsed_config.user_commands {
+                self.add_config_user_command(&name, &expansion);
+            }
+            for (key, answer) in parsed_config.dialog_shortcuts {
+                self.add_config_dialog_shortcut(key, &answer);
+            }
+            for (mode_prefix, keys) in parsed_config.keycode_mappings {
+                self.add_config_keycode_mapping(&mode_prefix, keys);
+            }
+            for error in self.execute_config_commands(parsed_config.commands) {
+                has_errors = true;
+                self.error(error);
+            }
+        }
+        self.model.command_source = CommandSource::Typed;
+        self.reset_removed_config_settings(old_config_settings);
+        if !has_errors {
+            self.info("config reloaded");
+        }
+    }
+
+    /// Reset to their default value the settings that were set by the previous config, but not
+    /// by the reloaded one, so that the application is notified of their change.
+    fn reset_removed_config_settings(&mut self, old_config_settings: Vec<String>) {
+        let defaults = SETT::default();
+        for name in old_config_settings {
+            if self.model.config_settings.contains(&name) {
+                continue;
+            }
+            let old_value = self.get_setting_value(&name);
+            if let Some(value) = (self.model.setting_values)(&defaults, &name) {
+                if old_value.as_ref().map(value_to_string) == Some(value_to_string(&value)) {
+                    continue;
+                }
+                if let Ok(setting) = SETT::to_variant(&name, value) {
+                    self.apply_setting(setting);
+                }
+            }
+        }
+    }
+
+    /// Set whether the config files are reloaded when one of them is modified.
+    pub fn watch_config(&mut self, watch: bool) {
+        // NOTE: dropping the monitors cancels them.
+        self.model.config_watch.monitors.clear();
+        if !watch {
+            return;
+        }
+        let paths: Vec<_> = self.model.config_sources.iter()
+            .filter_map(|source| source.path.as_ref().ok())
+            .cloned()
+            .collect();
+        if paths.is_empty() {
+            warn!("cannot watch the config since there is no config file");
+            return;
+        }
+        for path in paths {
+            let stream = self.model.relm.stream().clone();
+            // NOTE: the changes are done when the editor finished writing the file, so that it
+            // is not read while it is incomplete.
+            let monitor = monitor_file(&File::new_for_path(&path), move |event| {
+                if event == G_FILE_MONITOR_EVENT_CHANGES_DONE_HINT {
+                    stream.emit(ConfigFileChanged);
+                }
+            });
+            match monitor {
+                Ok(monitor) => self.model.config_watch.monitors.push(monitor),
+                Err(error) => warn!("cannot watch the config file {}: {}", path.display(), error),
+            }
+        }
+    }
+}
+
+/// Monitor the changes of the file, calling `callback` with the type of each change.
+/// NOTE: gio 0.4 has no binding for `File::monitor_file()`, so the C functions are called
+/// directly.
+fn monitor_file<F: Fn(GFileMonitorEvent) + 'static>(file: &File, callback: F) -> Result<glib::Object, glib::Error> {
+    unsafe {
+        let mut error = ptr::null_mut();
+        let monitor = gio_sys::g_file_monitor_file(file.to_glib_none().0, G_FILE_MONITOR_NONE, ptr::null_mut(),
+            &mut error);
+        if !error.is_null() {
+            return Err(from_glib_full(error));
+        }
+        let callback: Box<Box<Fn(GFileMonitorEvent) + 'static>> = Box::new(Box::new(callback));
+        connect(monitor as *mut _, "changed", transmute(changed_trampoline as *const ()),
+            Box::into_raw(callback) as *mut _);
+        Ok(from_glib_full(monitor as *mut _))
+    }
+}
+
+/// Call the callback of the monitor with the type of the change.
+unsafe extern "C" fn changed_trampoline(_monitor: *mut GFileMonitor, _file: *mut GFile, _other_file: *mut GFile,
+    event: GFileMonitorEvent, callback: *mut c_void)
+{
+    let _guard = CallbackGuard::new();
+    let callback: &&(Fn(GFileMonitorEvent) + 'static) = transmute(callback);
+    callback(event)
+}
@@ -23,8 +23,10 @@
 
 use std::collections::HashMap;
 use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::time::Duration;
 
-use gtk;
+use glib::MainLoop;
+use gtk::{self, Continue};
 use mg_settings::{EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
 use mg_settings::key::Key;
 use mg_settings::settings;
@@ -36,34 +38,84 @@ use relm::{
     Widget,
 };
 
-use app::{Mg, BLOCKING_INPUT_MODE, INPUT_MODE};
+use app::{Mg, Mode, BLOCKING_INPUT_MODE, INPUT_MODE};
 use app::color::color_blue;
 use app::Msg::{
+    self,
     BlockingCustomDialog,
     BlockingInput,
+    BlockingInputWithDefault,
+    BlockingPasswordInput,
     BlockingQuestion,
     BlockingYesNoQuestion,
+    Confirm,
+    ConfirmWithTimeout,
+    DialogAnswer,
+    DialogTimeout,
     EnterNormalModeAndReset,
     Input,
+    InputWithCompletion,
+    InputWithDefault,
+    LabeledQuestion,
+    PasswordInput,
     Question,
     ResetInput,
     YesNoQuestion,
 };
-use app::status_bar::Msg::{Identifier, ShowIdentifier};
+use app::status_bar::Msg::{EntryVisibility, Identifier, IdentifierVisible, SelectAll, ShowIdentifier};
+use app::timeout::duration_to_milliseconds;
 use completion::NO_COMPLETER_IDENT;
+use key_converter::{key_to_string, normalize_key};
 use completion::completion_view::Msg::SetOriginalInput;
 use self::DialogResult::{Answer, Shortcut};
 
+/// The name of the config command adding a shortcut to the input dialogs.
+pub const DIALOG_MAP_COMMAND: &str = "dialog-map";
+
+/// A boxed function which can only be called once.
+/// NOTE: a `Box<FnOnce>` cannot be called with this version of Rust, so the function is called
+/// with `call_box()`, which takes the box by value.
+pub(crate) trait FnOnceBox<ARG, RET> {
+    /// Call the function.
+    fn call_box(self: Box<Self>, arg: ARG) -> RET;
+}
+
+impl<ARG, RET, F: FnOnce(ARG) -> RET> FnOnceBox<ARG, RET> for F {
+    fn call_box(self: Box<Self>, arg: ARG) -> RET {
+        (*self)(arg)
+    }
+}
+
 /// A Responder is a way to send back the answer of a dialog to the code that showed this dialog.
 pub trait Responder {
     /// Send the answer back.
     /// It can be to a widget or a channel.
-    fn respond(&self, answer: DialogResult);
+    /// A responder is only called once, so it can give away the data it owns.
+    fn respond(self: Box<Self>, answer: DialogResult);
 }
 
 /// Blocking input dialog responder.
 /// This is used to send the message to a channel when the user answers the dialog.
+/// The dialog runs its own main loop, which is quit when the user answers.
+///
+/// # Re-entrancy
+///
+/// The nested main loop dispatches every event source except the one currently being dispatched,
+/// which glib blocks while its callback runs. When a `blocking_*` function is called from the
+/// `update()` of a widget, this means that:
+///
+///  * the messages emitted to this widget are queued and only handled after the function
+///    returns, so the widget must not wait for one of its own messages while the dialog is shown;
+///  * the other widgets, including `Mg`, and the timeouts keep being updated, so the model of the
+///    application can change before the function returns;
+///  * a blocking dialog shown from one of these handlers is queued after this one, and since its
+///    main loop runs inside the one of this dialog, this function only returns once the nested
+///    dialog is answered too.
+///
+/// The callback-based functions, like `input()` or `question()`, do not have these constraints
+/// and should be preferred.
 pub struct BlockingInputDialog {
+    main_loop: MainLoop,
     tx: SyncSender<Option<String>>,
 }
 
@@ -73,36 +125,44 @@ impl BlockingInputDialog {
     pub fn new() -> (Self, Receiver<Option<String>>) {
         let (tx, rx) = sync_channel(1);
         (BlockingInputDialog {
+            main_loop: MainLoop::new(None, false),
             tx,
         }, rx)
     }
+
+    /// Get the main loop to run until the user answers the dialog.
+    pub fn main_loop(&self) -> MainLoop {
+        self.main_loop.clone()
+    }
 }
 
 impl Responder for BlockingInputDialog {
-    fn respond(&self, answer: DialogResult) {
+    fn respond(self: Box<Self>, answer: DialogResult) {
         match answer {
             Answer(answer) => {
                 self.tx.send(answer).expect("Sending answer shouldn't fail")
             },
-            _ => unimplemented!(),
+            Shortcut(answer) => {
+                self.tx.send(Some(answer)).expect("Sending answer shouldn't fail")
+            },
         }
-        gtk::main_quit();
+        self.main_loop.quit();
     }
 }
 
 /// Input dialog responder.
 /// This is used to specify which message to send to which widget when the user answers the dialog.
 pub struct InputDialog<WIDGET: Widget> {
-    callback: Box<Fn(Option<String>) -> WIDGET::Msg>,
+    callback: Box<FnOnceBox<Option<String>, WIDGET::Msg>>,
     stream: EventStream<WIDGET::Msg>,
 }
 
 impl<WIDGET: Widget> InputDialog<WIDGET> {
     /// Create a new input dialog responder.
-    /// The `callback` is a message constructor.
+    /// The `callback` is a message constructor, called once with the answer.
     /// The message will be sent to `relm` stream.
     pub fn new<F>(relm: &Relm<WIDGET>, callback: F) -> Self
-        where F: Fn(Option<String>) -> WIDGET::Msg + 'static,
+        where F: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
     {
         InputDialog {
             callback: Box::new(callback),
@@ -112,10 +172,48 @@ impl<WIDGET: Widget> InputDialog<WIDGET> {
 }
 
 impl<WIDGET: Widget> Responder for InputDialog<WIDGET> {
-    fn respond(&self, answer: DialogResult) {
+    fn respond(self: Box<Self>, answer: DialogResult) {
+        let InputDialog { callback, stream } = *self;
+        match answer {
+            Answer(answer) => stream.emit(callback.call_box(answer)),
+            Shortcut(answer) => stream.emit(callback.call_box(Some(answer))),
+        }
+    }
+}
+
+/// Identified dialog responder.
+/// This is used to send the answer in the `DialogAnswer` message of `Mg`, with the identifier of
+/// the dialog, so that the caller does not need to give a callback.
+pub struct IdentifiedDialog<COMM, SETT>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    id: String,
+    stream: EventStream<Msg<COMM, SETT>>,
+}
+
+impl<COMM, SETT> IdentifiedDialog<COMM, SETT>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    /// Create a new identified dialog responder.
+    /// The answer will be sent to the `Mg` stream with the `id`.
+    pub fn new(stream: &EventStream<Msg<COMM, SETT>>, id: &str) -> Self {
+        IdentifiedDialog {
+            id: id.to_string(),
+            stream: stream.clone(),
+        }
+    }
+}
+
+impl<COMM, SETT> Responder for IdentifiedDialog<COMM, SETT>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    fn respond(self: Box<Self>, answer: DialogResult) {
         match answer {
-            Answer(answer) => self.stream.emit((self.callback)(answer)),
-            _ => unimplemented!(),
+            Answer(answer) => self.stream.emit(DialogAnswer(self.id.clone(), answer)),
+            Shortcut(answer) => self.stream.emit(DialogAnswer(self.id.clone(), Some(answer))),
         }
     }
 }
@@ -123,13 +221,16 @@ impl<WIDGET: Widget> Responder for InputDialog<WIDGET> {
 /// Yes/no question input dialog responder.
 /// This is used to specify which message to send to which widget when the user answers the dialog.
 pub struct YesNoInputDialog<WIDGET: Widget> {
-    callback: Box<Fn(bool) -> WIDGET::Msg>,
+    callback: Box<FnOnceBox<bool, WIDGET::Msg>>,
     stream: EventStream<WIDGET::Msg>,
 }
 
 impl<WIDGET: Widget> YesNoInputDialog<WIDGET> {
+    /// Create a new yes/no question responder.
+    /// The `callback` is a message constructor, called once with true when the user answers yes.
+    /// The message will be sent to `relm` stream.
     pub fn new<F>(relm: &Relm<WIDGET>, callback: F) -> Self
-        where F: Fn(bool) -> WIDGET::Msg + 'static,
+        where F: FnOnce(bool) -> WIDGET::Msg + 'static,
     {
         YesNoInputDialog {
             callback: Box::new(callback),
@@ -139,10 +240,11 @@ impl<WIDGET: Widget> YesNoInputDialog<WIDGET> {
 }
 
 impl<WIDGET: Widget> Responder for YesNoInputDialog<WIDGET> {
-    fn respond(&self, answer: DialogResult) {
+    fn respond(self: Box<Self>, answer: DialogResult) {
+        let YesNoInputDialog { callback, stream } = *self;
         match answer {
-            Answer(answer) => self.stream.emit((self.callback)(answer == Some("y".to_string()))),
-            _ => unimplemented!(),
+            Answer(answer) => stream.emit(callback.call_box(answer == Some("y".to_string()))),
+            Shortcut(answer) => stream.emit(callback.call_box(answer == "y")),
         }
     }
 }
@@ -151,18 +253,32 @@ impl<WIDGET: Widget> Responder for YesNoInputDialog<WIDGET> {
 pub struct DialogBuilder {
     /// Whether the dialog should block the calling function.
     blocking: bool,
+    /// Whether the dialog shortcuts from the config file are available in this dialog.
+    global_shortcuts: bool,
+    /// The labels shown after the choices.
+    choice_labels: HashMap<char, String>,
     /// The available choices to the question.
     choices: Vec<char>,
     /// The text completer identifier for the input.
     completer: Option<String>,
     /// The default answer to the question.
     default_answer: String,
+    /// The choice selected when pressing Enter.
+    default_choice: Option<char>,
+    /// Whether the message is shown at the left of the input.
+    identifier_visible: bool,
     /// The message/question to show to the user.
     message: String,
+    /// Whether the typed characters are hidden.
+    password: bool,
     /// The wrapper over the callback function to call for an asynchronous input dialog.
     responder: Option<Box<Responder>>,
+    /// Whether the default answer is selected, so that typing replaces it.
+    select_default_answer: bool,
     /// The available shortcuts.
     shortcuts: HashMap<Key, String>,
+    /// The duration after which the dialog is answered with the specified answer.
+    timeout: Option<(Duration, String)>,
 }
 
 impl DialogBuilder {
@@ -171,12 +287,19 @@ impl DialogBuilder {
     pub fn new() -> Self {
         DialogBuilder {
             blocking: false,
+            global_shortcuts: true,
+            choice_labels: HashMap::new(),
             choices: vec![],
             completer: None,
             default_answer: String::new(),
+            default_choice: None,
+            identifier_visible: true,
             message: String::new(),
+            password: false,
             responder: None,
+            select_default_answer: false,
             shortcuts: HashMap::new(),
+            timeout: None,
         }
     }
 
@@ -204,23 +327,62 @@ impl DialogBuilder {
         self
     }
 
+    /// Set the choice selected when pressing Enter.
+    /// It is shown in uppercase in the list of choices.
+    pub fn default_choice(mut self, choice: char) -> Self {
+        self.default_choice = Some(choice);
+        self
+    }
+
+    /// Set the choices available for the input, with a label shown for each of them.
+    pub fn labeled_choices(mut self, choices: Vec<(char, String)>) -> Self {
+        self.choices = choices.iter().map(|&(choice, _)| choice).collect();
+        self.choice_labels = choices.into_iter().collect();
+        self
+    }
+
+    /// Set whether the message is shown at the left of the input.
+    /// Hiding it gives a bare input line.
+    pub fn identifier_visible(mut self, visible: bool) -> Self {
+        self.identifier_visible = visible;
+        self
+    }
+
     /// Set the message/question to show to the user.
     pub fn message(mut self, message: String) -> Self {
         self.message = message;
         self
     }
 
+    /// Set whether the typed characters are hidden, for a password.
+    pub fn password(mut self, password: bool) -> Self {
+        self.password = password;
+        self
+    }
+
     /// Set a responder for an asynchronous dialog.
     pub fn responder(mut self, responder: Box<Responder>) -> Self {
         self.responder = Some(responder);
         self
     }
 
+    /// Set whether the default answer is selected, so that typing replaces it.
+    pub fn select_default_answer(mut self, select: bool) -> Self {
+        self.select_default_answer = select;
+        self
+    }
+
     /// Add a shortcut.
     pub fn shortcut(mut self, shortcut: Key, value: &str) -> Self {
         self.shortcuts.insert(shortcut, value.to_string());
         self
     }
+
+    /// Answer the dialog with `answer` if the user does not press a key before `duration`.
+    pub fn timeout(mut self, duration: Duration, answer: &str) -> Self {
+        self.timeout = Some((duration, answer.to_string()));
+        self
+    }
 }
 
 /// Struct representing a dialog result.
@@ -237,6 +399,20 @@ impl<COMM, SETT> Mg<COMM, SETT>
 where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
 {
+    /// Add a shortcut answering every input dialog with `answer`.
+    /// The shortcuts of a dialog take precedence over this one, and the questions with choices
+    /// do not use it.
+    pub fn add_dialog_shortcut(&mut self, key: Key, answer: &str) {
+        self.model.dialog_shortcuts.insert(normalize_key(key), answer.to_string());
+    }
+
+    /// Add a dialog shortcut defined in the config, to remove it when the config is reloaded.
+    pub fn add_config_dialog_shortcut(&mut self, key: Key, answer: &str) {
+        let key = normalize_key(key);
+        self.model.config_dialog_shortcuts.insert(key.clone());
+        self.model.dialog_shortcuts.insert(key, answer.to_string());
+    }
+
     /// Ask a question to the user and block until the user provides it (or cancel).
     pub fn blocking_custom_dialog(&mut self, responder: Box<Responder>, builder: DialogBuilder) {
         let builder = builder
@@ -255,6 +431,30 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         self.show_dialog_without_shortcuts(builder);
     }
 
+    /// Ask a question to the user with a selected default answer and block until the user provides
+    /// it (or cancel).
+    pub fn blocking_input_with_default(&mut self, responder: Box<Responder>, message: String,
+        default_answer: String)
+    {
+        let builder = DialogBuilder::new()
+            .blocking(true)
+            .default_answer(default_answer)
+            .message(message)
+            .responder(responder)
+            .select_default_answer(true);
+        self.show_dialog_without_shortcuts(builder);
+    }
+
+    /// Ask a password to the user and block until the user provides it (or cancel).
+    pub fn blocking_password_input(&mut self, responder: Box<Responder>, message: String) {
+        let builder = DialogBuilder::new()
+            .blocking(true)
+            .message(message)
+            .password(true)
+            .responder(responder);
+        self.show_dialog_without_shortcuts(builder);
+    }
+
     /// Ask a multiple-choice question to the user and block until the user provides it (or cancel).
     pub fn blocking_question(&mut self, responder: Box<Responder>, message: String, choices: Vec<char>) {
         let builder = DialogBuilder::new()
@@ -275,6 +475,56 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         self.show_dialog_without_shortcuts(builder);
     }
 
+    /// Ask a yes/no question with a default answer, selected by pressing Enter.
+    /// Pressing Escape answers no.
+    pub fn confirm(&mut self, responder: Box<Responder>, message: String, default: bool) {
+        let default_choice = yes_no_choice(default);
+        let builder = DialogBuilder::new()
+            .choices(vec!['y', 'n'])
+            .default_choice(default_choice)
+            .message(message)
+            .responder(responder);
+        self.show_dialog(builder);
+    }
+
+    /// Ask a yes/no question with a default answer, which is given when the user does not press a
+    /// key before `timeout`.
+    pub fn confirm_with_timeout(&mut self, responder: Box<Responder>, message: String, default: bool,
+        timeout: Duration)
+    {
+        let default_choice = yes_no_choice(default);
+        let builder = DialogBuilder::new()
+            .choices(vec!['y', 'n'])
+            .default_choice(default_choice)
+            .message(message)
+            .responder(responder)
+            .timeout(timeout, &default_choice.to_string());
+        self.show_dialog(builder);
+    }
+
+    /// Answer None to the blocking dialog, if any, and to the queued dialogs, so that the main
+    /// loops of the blocking dialogs are quit.
+    pub fn cancel_blocking_dialog(&mut self) {
+        if self.model.current_mode.get() == Mode::BlockingInput {
+            if let Some(callback) = self.model.input_callback.take() {
+                callback.call_box((None, false));
+            }
+        }
+        for dialog_builder in self.model.queued_dialogs.drain(..) {
+            if let Some(responder) = dialog_builder.responder {
+                responder.respond(Answer(None));
+            }
+        }
+    }
+
+    /// Answer the dialog with the default answer when its timeout expires.
+    /// The timeout is ignored when a key was pressed or the dialog was closed since.
+    pub fn dialog_timeout(&mut self, generation: u64, answer: &str) {
+        if generation == self.model.dialog_generation && self.model.dialog_timeout_pending {
+            self.set_dialog_answer(answer);
+        }
+    }
+
     /// Ask a question to the user.
     // TODO: use Option<String> for default_answer?
     pub fn input(&mut self, responder: Box<Responder>, message: String, default_answer: String) {
@@ -285,6 +535,46 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         self.show_dialog(builder);
     }
 
+    /// Ask a question to the user, with the completions of the completer `completer`.
+    /// The completion mappings work as in the command mode.
+    pub fn input_with_completion(&mut self, responder: Box<Responder>, message: String, completer: &str) {
+        let builder = DialogBuilder::new()
+            .completer(completer)
+            .message(message)
+            .responder(responder);
+        self.show_dialog(builder);
+    }
+
+    /// Ask a question to the user with a default answer, selected so that typing replaces it and
+    /// Enter accepts it.
+    pub fn input_with_default(&mut self, responder: Box<Responder>, message: String, default_answer: String) {
+        let builder = DialogBuilder::new()
+            .default_answer(default_answer)
+            .message(message)
+            .responder(responder)
+            .select_default_answer(true);
+        self.show_dialog(builder);
+    }
+
+    /// Ask a multiple-choice question to the user, showing a label for each choice.
+    pub fn labeled_question(&mut self, responder: Box<Responder>, message: String, choices: Vec<(char, String)>) {
+        let builder = DialogBuilder::new()
+            .responder(responder)
+            .message(message)
+            .labeled_choices(choices);
+        self.show_dialog(builder);
+    }
+
+    /// Ask a password to the user.
+    /// The typed characters are hidden and the answer is neither kept nor logged.
+    pub fn password_input(&mut self, responder: Box<Responder>, message: String) {
+        let builder = DialogBuilder::new()
+            .message(message)
+            .password(true)
+            .responder(responder);
+        self.show_dialog(builder);
+    }
+
     /// Ask a multiple-choice question to the user.
     pub fn question(&mut self, responder: Box<Responder>, message: String, choices: &[char]) {
         let builder = DialogBuilder::new()
@@ -294,11 +584,27 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         self.show_dialog(builder);
     }
 
+    /// Ask a multiple-choice question to the user without a callback.
+    /// The answer is sent in the `DialogAnswer` message with the `id`.
+    pub fn question_async(&mut self, id: &str, message: String, choices: &[char]) {
+        let responder = IdentifiedDialog::new(self.model.relm.stream(), id);
+        self.question(Box::new(responder), message, choices);
+    }
+
+    /// Remove a shortcut added with `add_dialog_shortcut()`.
+    pub fn remove_dialog_shortcut(&mut self, key: &Key) {
+        let key = normalize_key(key.clone());
+        if self.model.dialog_shortcuts.remove(&key).is_none() {
+            self.warning(&format!("No such dialog shortcut: {}", key_to_string(&key)));
+        }
+    }
+
     /// Set the answer to return to the caller of the dialog.
     pub fn set_dialog_answer(&mut self, answer: &str) {
         let mut should_reset = false;
         if let Some(callback) = self.model.input_callback.take() {
-            callback(Some(answer.to_string()), self.model.shortcut_pressed);
+            self.model.dialog_generation += 1;
+            callback.call_box((Some(answer.to_string()), self.model.shortcut_pressed));
             self.model.choices.clear();
             should_reset = true;
         }
@@ -308,27 +614,47 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
     }
 
     /// Show a dialog created with a `DialogBuilder`.
+    /// When a dialog is waiting for an answer, the new dialog is queued and shown once this one is
+    /// answered.
     pub fn show_dialog(&mut self, mut dialog_builder: DialogBuilder) {
+        if self.model.input_callback.is_some() {
+            self.model.queued_dialogs.push_back(dialog_builder);
+            return;
+        }
         self.model.shortcut_pressed = false;
 
         self.model.shortcuts.clear();
+        if dialog_builder.choices.is_empty() && dialog_builder.global_shortcuts {
+            for (key, value) in &self.model.dialog_shortcuts {
+                self.model.shortcuts.insert(key.clone(), value.clone());
+            }
+        }
         for (key, value) in dialog_builder.shortcuts {
             self.model.shortcuts.insert(key, value);
         }
 
-        let choices = dialog_builder.choices.clone();
-        if !choices.is_empty() {
+        self.status_bar.emit(IdentifierVisible(dialog_builder.identifier_visible));
+        // NOTE: the visibility is restored when the entry is hidden.
+        self.model.password_input = dialog_builder.password;
+        if dialog_builder.password {
+            self.status_bar.emit(EntryVisibility(false));
+        }
+        self.model.default_choice = dialog_builder.default_choice;
+        if !dialog_builder.choices.is_empty() {
+            let choices = choices_prompt(&dialog_builder.choices, &dialog_builder.choice_labels,
+                dialog_builder.default_choice);
             self.model.choices.clear();
             self.model.choices.append(&mut dialog_builder.choices);
-            let choices: Vec<_> = choices.iter().map(|c| c.to_string()).collect();
-            let choices = choices.join("/");
-            self.status_bar.emit(Identifier(format!("{} ({}) ", dialog_builder.message, choices)));
+            self.status_bar.emit(Identifier(format!("{} {} ", dialog_builder.message, choices)));
             self.status_bar.emit(ShowIdentifier);
         }
         else {
             self.status_bar.emit(Identifier(format!("{} ", dialog_builder.message)));
             self.show_entry();
             self.set_input(&dialog_builder.default_answer);
+            if dialog_builder.select_default_answer {
+                self.status_bar.emit(SelectAll);
+            }
         }
 
         if let Some(completer) = dialog_builder.completer {
@@ -347,11 +673,22 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         else {
             self.set_mode(INPUT_MODE);
         }
+        self.model.dialog_generation += 1;
+        self.model.dialog_timeout_pending = dialog_builder.timeout.is_some();
+        if let Some((duration, answer)) = dialog_builder.timeout {
+            let generation = self.model.dialog_generation;
+            let stream = self.model.relm.stream().clone();
+            let milliseconds = duration_to_milliseconds(duration);
+            gtk::timeout_add(milliseconds, move || {
+                stream.emit(DialogTimeout(generation, answer.clone()));
+                Continue(false)
+            });
+        }
         if let Some(responder) = dialog_builder.responder {
-            self.model.input_callback = Some(Box::new(move |answer, shortcut_pressed| {
+            self.model.input_callback = Some(Box::new(move |(answer, shortcut_pressed): (Option<String>, bool)| {
                 let answer =
                     if shortcut_pressed {
-                        if let Some(answer) = answer.clone() {
+                        if let Some(answer) = answer {
                             Shortcut(answer)
                         }
                         else {
@@ -359,7 +696,7 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
                         }
                     }
                     else {
-                        Answer(answer.clone())
+                        Answer(answer)
                     };
                 responder.respond(answer);
             }));
@@ -367,13 +704,20 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
         color_blue(self.status_bar.widget());
     }
 
+    /// Show the next queued dialog, if any, once the previous one is answered.
+    pub fn show_queued_dialog(&mut self) {
+        if self.model.input_callback.is_none() {
+            if let Some(dialog_builder) = self.model.queued_dialogs.pop_front() {
+                self.show_dialog(dialog_builder);
+            }
+        }
+    }
+
     /// Show a dialog created with a `DialogBuilder` which does not contain shortcut.
-    pub fn show_dialog_without_shortcuts(&mut self, dialog_builder: DialogBuilder) {
+    /// The dialog shortcuts from the config file are not added to this dialog.
+    pub fn show_dialog_without_shortcuts(&mut self, mut dialog_builder: DialogBuilder) {
+        dialog_builder.global_shortcuts = false;
         self.show_dialog(dialog_builder);
-            /*Answer(answer) => answer,
-            Shortcut(_) => panic!("cannot return a shortcut in show_dialog_without_shortcuts()"),
-        }*/
-        // TODO
     }
 
     /// Show a yes/no question.
@@ -386,67 +730,127 @@ where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
     }
 }
 
-/// Ask a question to the user and block until the user provides it (or cancel).
-pub fn blocking_dialog<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, builder: DialogBuilder)
-    -> Option<String>
+/// Show a blocking dialog with the message created by `msg` and run its own main loop until the
+/// user answers it.
+/// NOTE: the handlers of the other widgets run while the main loop is running (see the
+/// re-entrancy notes of `BlockingInputDialog`).
+fn run_blocking_dialog<COMM, F, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, msg: F) -> Option<String>
 where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      F: FnOnce(Box<Responder>) -> Msg<COMM, SETT>,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
 {
     let (blocking_input_dialog, rx) = BlockingInputDialog::new();
-    let responder = Box::new(blocking_input_dialog);
-    mg.emit(BlockingCustomDialog(responder, builder));
-    gtk::main();
+    let main_loop = blocking_input_dialog.main_loop();
+    mg.emit(msg(Box::new(blocking_input_dialog)));
+    main_loop.run();
     mg.emit(ResetInput);
     // TODO: really return None if no value was received?
     rx.try_recv().unwrap_or(None)
 }
 
 /// Ask a question to the user and block until the user provides it (or cancel).
+/// The events are dispatched while waiting: see `BlockingInputDialog` for the constraints this
+/// puts on the caller.
+pub fn blocking_dialog<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, builder: DialogBuilder)
+    -> Option<String>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    run_blocking_dialog(mg, |responder| BlockingCustomDialog(responder, builder))
+}
+
+/// Ask a question to the user and block until the user provides it (or cancel).
+/// Prefer `input()`, which does not run a nested main loop (see `BlockingInputDialog`).
 pub fn blocking_input<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, msg: String,
     default_answer: String) -> Option<String>
 where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
 {
-    let (blocking_input_dialog, rx) = BlockingInputDialog::new();
-    let responder = Box::new(blocking_input_dialog);
-    mg.emit(BlockingInput(responder, msg, default_answer));
-    gtk::main();
-    mg.emit(ResetInput);
-    rx.try_recv().unwrap_or(None)
+    run_blocking_dialog(mg, |responder| BlockingInput(responder, msg, default_answer))
+}
+
+/// Ask a question to the user with a selected default answer and block until the user provides it
+/// (or cancel).
+/// Prefer `input_with_default()`, which does not run a nested main loop (see `BlockingInputDialog`).
+pub fn blocking_input_with_default<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, msg: String,
+    default_answer: String) -> Option<String>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    run_blocking_dialog(mg, |responder| BlockingInputWithDefault(responder, msg, default_answer))
+}
+
+/// Ask a password to the user and block until the user provides it (or cancel).
+/// Prefer `password_input()`, which does not run a nested main loop (see `BlockingInputDialog`).
+pub fn blocking_password_input<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, msg: String)
+    -> Option<String>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    run_blocking_dialog(mg, |responder| BlockingPasswordInput(responder, msg))
+}
+
+/// Get the choice of a yes/no question corresponding to the answer.
+fn yes_no_choice(yes: bool) -> char {
+    if yes {
+        'y'
+    }
+    else {
+        'n'
+    }
+}
+
+/// Show the choices of a question, like `(y/N)`, or `[y]es [n]o [a]lways` when they have labels.
+/// The default choice is shown in uppercase.
+fn choices_prompt(choices: &[char], labels: &HashMap<char, String>, default_choice: Option<char>) -> String {
+    let choices: Vec<_> = choices.iter()
+        .map(|&choice| {
+            let key: String =
+                if Some(choice) == default_choice {
+                    choice.to_uppercase().collect()
+                }
+                else {
+                    choice.to_string()
+                };
+            match labels.get(&choice) {
+                Some(label) if label.starts_with(choice) => format!("[{}]{}", key, &label[choice.len_utf8()..]),
+                Some(label) => format!("[{}] {}", key, label),
+                None => key,
+            }
+        })
+        .collect();
+    if labels.is_empty() {
+        format!("({})", choices.join("/"))
+    }
+    else {
+        choices.join(" ")
+    }
 }
 
 /// Ask a multiple-choice question to the user and block until the user provides it (or cancel).
+/// Prefer `question()`, which does not run a nested main loop (see `BlockingInputDialog`).
 pub fn blocking_question<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, msg: String,
     choices: &[char]) -> Option<String>
 where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
 {
-    let (blocking_input_dialog, rx) = BlockingInputDialog::new();
-    let responder = Box::new(blocking_input_dialog);
-    mg.emit(BlockingQuestion(responder, msg, choices.to_vec()));
-    gtk::main();
-    mg.emit(ResetInput);
-    rx.try_recv().unwrap_or(None)
+    run_blocking_dialog(mg, |responder| BlockingQuestion(responder, msg, choices.to_vec()))
 }
 
 /// Show a blocking yes/no question.
+/// Prefer `yes_no_question()`, which does not run a nested main loop (see `BlockingInputDialog`).
 pub fn blocking_yes_no_question<COMM, SETT>(mg: &EventStream<<Mg<COMM, SETT> as Update>::Msg>, msg: String)
     -> bool
 where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
 {
-    let (blocking_input_dialog, rx) = BlockingInputDialog::new();
-    let responder = Box::new(blocking_input_dialog);
-    mg.emit(BlockingYesNoQuestion(responder, msg));
-    gtk::main();
-    mg.emit(ResetInput);
-    rx.try_recv() == Ok(Some("y".to_string()))
+    run_blocking_dialog(mg, |responder| BlockingYesNoQuestion(responder, msg)) == Some("y".to_string())
 }
 
 /// Ask a question to the user.
 pub fn input<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>, msg: String,
     default_answer: String, callback: CALLBACK)
-where CALLBACK: Fn(Option<String>) -> WIDGET::Msg + 'static,
+where CALLBACK: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
       COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
       WIDGET: Widget + 'static,
@@ -455,10 +859,62 @@ where CALLBACK: Fn(Option<String>) -> WIDGET::Msg + 'static,
     mg.emit(Input(responder, msg, default_answer));
 }
 
+/// Ask a question to the user, with the completions of the completer `completer`.
+pub fn input_with_completion<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>,
+    relm: &Relm<WIDGET>, msg: String, completer: &str, callback: CALLBACK)
+where CALLBACK: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
+      COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+      WIDGET: Widget + 'static,
+{
+    let responder = Box::new(InputDialog::new(relm, callback));
+    mg.emit(InputWithCompletion(responder, msg, completer.to_string()));
+}
+
+/// Ask a question to the user with a default answer, selected so that typing replaces it.
+pub fn input_with_default<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>,
+    msg: String, default_answer: String, callback: CALLBACK)
+where CALLBACK: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
+      COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+      WIDGET: Widget + 'static,
+{
+    let responder = Box::new(InputDialog::new(relm, callback));
+    mg.emit(InputWithDefault(responder, msg, default_answer));
+}
+
+/// Ask a multiple-choice question to the user, showing a label for each choice, like
+/// `&[('y', "yes"), ('n', "no"), ('a', "always")]`.
+pub fn labeled_question<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>,
+    msg: String, choices: &[(char, &str)], callback: CALLBACK)
+where CALLBACK: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
+      COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+      WIDGET: Widget + 'static,
+{
+    let responder = Box::new(InputDialog::new(relm, callback));
+    let choices = choices.iter()
+        .map(|&(choice, label)| (choice, label.to_string()))
+        .collect();
+    mg.emit(LabeledQuestion(responder, msg, choices));
+}
+
+/// Ask a password to the user.
+pub fn password_input<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>,
+    msg: String, callback: CALLBACK)
+where CALLBACK: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
+      COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+      WIDGET: Widget + 'static,
+{
+    let responder = Box::new(InputDialog::new(relm, callback));
+    mg.emit(PasswordInput(responder, msg));
+}
+
 /// Ask a multiple-choice question to the user.
 pub fn question<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>, msg: String,
     choices: &'static [char], callback: CALLBACK)
-where CALLBACK: Fn(Option<String>) -> WIDGET::Msg + 'static,
+where CALLBACK: FnOnce(Option<String>) -> WIDGET::Msg + 'static,
       COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
       WIDGET: Widget + 'static,
@@ -467,10 +923,35 @@ where CALLBACK: Fn(Option<String>) -> WIDGET::Msg + 'static,
     mg.emit(Question(responder, msg, choices));
 }
 
+/// Ask a yes/no question with a default answer, selected by pressing Enter.
+pub fn confirm<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>,
+    msg: String, default: bool, callback: CALLBACK)
+where CALLBACK: FnOnce(bool) -> WIDGET::Msg + 'static,
+      COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+      WIDGET: Widget + 'static,
+{
+    let responder = Box::new(YesNoInputDialog::new(relm, callback));
+    mg.emit(Confirm(responder, msg, default));
+}
+
+/// Ask a yes/no question with a default answer, selected by pressing Enter or when the user does not
+/// press a key before `timeout`.
+pub fn confirm_with_timeout<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>,
+    relm: &Relm<WIDGET>, msg: String, default: bool, timeout: Duration, callback: CALLBACK)
+where CALLBACK: FnOnce(bool) -> WIDGET::Msg + 'static,
+      COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+      WIDGET: Widget + 'static,
+{
+    let responder = Box::new(YesNoInputDialog::new(relm, callback));
+    mg.emit(ConfirmWithTimeout(responder, msg, default, timeout));
+}
+
 /// Show a yes/no question.
 pub fn yes_no_question<CALLBACK, COMM, SETT, WIDGET>(mg: &ContainerComponent<Mg<COMM, SETT>>, relm: &Relm<WIDGET>,
     msg: String, callback: CALLBACK)
-where CALLBACK: Fn(bool) -> WIDGET::Msg + 'static,
+where CALLBACK: FnOnce(bool) -> WIDGET::Msg + 'static,
       COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
       SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
       WIDGET: Widget + 'static,
@@ -478,3 +959,27 @@ where CALLBACK: Fn(bool) -> WIDGET::Msg + 'static,
     let responder = Box::new(YesNoInputDialog::new(relm, callback));
     mg.emit(YesNoQuestion(responder, msg));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{choices_prompt, yes_no_choice};
+
+    #[test]
+    fn test_confirm_prompt() {
+        let no_labels = HashMap::new();
+        assert_eq!(choices_prompt(&['y', 'n'], &no_labels, Some(yes_no_choice(true))), "(Y/n)");
+        assert_eq!(choices_prompt(&['y', 'n'], &no_labels, Some(yes_no_choice(false))), "(y/N)");
+        assert_eq!(choices_prompt(&['y', 'n', 'a'], &no_labels, None), "(y/n/a)");
+    }
+
+    #[test]
+    fn test_labeled_choices_prompt() {
+        let mut labels = HashMap::new();
+        labels.insert('y', "yes".to_string());
+        labels.insert('n', "no".to_string());
+        labels.insert('!', "always".to_string());
+        assert_eq!(choices_prompt(&['y', 'n', '!'], &labels, Some('n')), "[y]es [N]o [!] always");
+    }
+}
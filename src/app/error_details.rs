@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use gdk::EventKey;
+use gdk::enums::key::Escape;
+use gtk::{
+    self,
+    ContainerExt,
+    GtkWindowExt,
+    LabelExt,
+    WidgetExt,
+    WindowPosition,
+    WindowType,
+};
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+
+use app::Mg;
+
+/// The spacing, in pixels, around the text of the error details.
+const DETAILS_SPACING: u32 = 10;
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Hide the window showing the details of the error.
+    /// Returns true if the key was used to hide it.
+    pub fn hide_error_details_on_escape(&mut self, key: &EventKey) -> bool {
+        if key.get_keyval() == Escape {
+            if let Some(window) = self.model.error_details_window.take() {
+                window.destroy();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Show a window with the full message of the error displayed in the status bar.
+    /// NOTE: the mg-settings errors have no source nor backtrace, so there is no chain of causes
+    /// to show.
+    /// The window is closed with Escape.
+    pub fn show_error_details(&mut self) {
+        let details = self.model.error_details.as_ref()
+            .and_then(|&(ref message, ref details)|
+                if *message == self.model.message {
+                    Some(details.clone())
+                }
+                else {
+                    None
+                });
+        let details =
+            match details {
+                Some(details) => details,
+                None => {
+                    self.info("No error to show");
+                    return;
+                },
+            };
+        if let Some(window) = self.model.error_details_window.take() {
+            window.destroy();
+        }
+        let label = gtk::Label::new(Some(details.as_str()));
+        label.set_selectable(true);
+        label.set_xalign(0.0);
+
+        let window = gtk::Window::new(WindowType::Popup);
+        window.set_accept_focus(false);
+        window.set_transient_for(&self.window);
+        window.set_position(WindowPosition::CenterOnParent);
+        window.set_border_width(DETAILS_SPACING);
+        window.add(&label);
+        window.show_all();
+        self.model.error_details_window = Some(window);
+    }
+}
@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::HashMap;
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use mg_settings::errors::Error;
+
+use app::{
+    Mg,
+    COMMAND_HISTORY_WINDOW,
+    COMPLETE_ACCEPT_COMMAND,
+    COMPLETE_ACCEPT_NEXT_COMMAND,
+    COMPLETE_FIRST_COMMAND,
+    COMPLETE_LAST_COMMAND,
+    COMPLETE_NEXT_COMMAND,
+    COMPLETE_PREVIOUS_COMMAND,
+    COMPLETION_DELETE_MARKED_COMMAND,
+    COMPLETION_TOGGLE_MARK_COMMAND,
+    COPY,
+    COPY_MESSAGE,
+    CUT,
+    ENTRY_DELETE_NEXT_CHAR,
+    ENTRY_DELETE_NEXT_WORD,
+    ENTRY_DELETE_PREVIOUS_WORD,
+    ENTRY_END,
+    ENTRY_NEXT_CHAR,
+    ENTRY_NEXT_WORD,
+    ENTRY_PREVIOUS_CHAR,
+    ENTRY_PREVIOUS_WORD,
+    ENTRY_SMART_HOME,
+    HISTORY_NEXT,
+    HISTORY_PREVIOUS,
+    PASTE,
+    PASTE_SELECTION,
+    REPEAT_LAST_ENTRY_COMMAND,
+    SHOW_ERROR_DETAILS,
+    SHOW_MAPPINGS,
+};
+use app::Msg::ShowHelp;
+use app::alias::ALIAS_COMMAND;
+use app::message_log::MESSAGES_COMMAND;
+use app::user_command::USER_COMMAND_COMMAND;
+use completion::{Completer, HelpCompleter, DEFAULT_CATEGORY};
+use completion::completion_view::Msg::AddCompleters;
+
+/// The name of the help command.
+pub const HELP_COMMAND: &str = "help";
+/// The name of the command closing the application.
+pub const QUIT_COMMAND: &str = "quit";
+/// The short name of the command closing the application.
+pub const QUIT_SHORT_COMMAND: &str = "q";
+
+/// The commands provided by mg with their description.
+const BUILTIN_COMMANDS: &[(&str, &str)] = &[
+    ("!", "Run a command in a shell"),
+    (ALIAS_COMMAND, "Define an alias of a command"),
+    (COMMAND_HISTORY_WINDOW, "Show the history of the commands to edit and run them again"),
+    (COMPLETE_ACCEPT_COMMAND, "Execute the selected completion or the command of the entry"),
+    (COMPLETE_ACCEPT_NEXT_COMMAND, "Accept the selected completion or select the next one"),
+    (COMPLETE_FIRST_COMMAND, "Select the first completion item"),
+    (COMPLETE_LAST_COMMAND, "Select the last completion item"),
+    (COMPLETE_NEXT_COMMAND, "Select the next completion item"),
+    (COMPLETE_PREVIOUS_COMMAND, "Select the previous completion item"),
+    (COMPLETION_DELETE_MARKED_COMMAND, "Delete the marked completion items"),
+    (COMPLETION_TOGGLE_MARK_COMMAND, "Mark or unmark the selected completion item and select the next one"),
+    (COPY, "Copy the selected text of the command entry"),
+    (COPY_MESSAGE, "Copy the message of the status bar"),
+    (CUT, "Cut the selected text of the command entry"),
+    (ENTRY_DELETE_NEXT_CHAR, "Delete the character after the cursor"),
+    (ENTRY_DELETE_NEXT_WORD, "Delete the word after the cursor"),
+    (ENTRY_DELETE_PREVIOUS_WORD, "Delete the word before the cursor"),
+    (ENTRY_END, "Move the cursor to the end of the command entry"),
+    (ENTRY_NEXT_CHAR, "Move the cursor to the next character"),
+    (ENTRY_NEXT_WORD, "Move the cursor to the next word"),
+    (ENTRY_PREVIOUS_CHAR, "Move the cursor to the previous character"),
+    (ENTRY_PREVIOUS_WORD, "Move the cursor to the previous word"),
+    (ENTRY_SMART_HOME, "Move the cursor to the start of the command entry"),
+    (HELP_COMMAND, "Show the help of the commands and settings"),
+    (HISTORY_NEXT, "Show the next command of the history starting with the text of the entry"),
+    (HISTORY_PREVIOUS, "Show the previous command of the history starting with the text of the entry"),
+    (USER_COMMAND_COMMAND, "Define a command executing other commands"),
+    ("map", "Create a new key binding"),
+    (MESSAGES_COMMAND, "Show the last messages of the status bar"),
+    ("mkrc", "Write the current mappings and settings to a config file"),
+    (PASTE, "Paste the clipboard in the command entry"),
+    (PASTE_SELECTION, "Paste the primary selection in the command entry"),
+    (QUIT_COMMAND, "Close the application"),
+    (REPEAT_LAST_ENTRY_COMMAND, "Execute again the last command executed from the command entry"),
+    ("set", "Change the value of a setting"),
+    ("setlocal", "Change the value of a setting in a mode"),
+    (SHOW_ERROR_DETAILS, "Show the causes of the error displayed in the status bar"),
+    (SHOW_MAPPINGS, "Show the mappings of the current mode"),
+    ("unmap", "Delete a key binding"),
+];
+
+/// Get the names of the commands, without the hidden ones.
+/// The built-in commands shadowed by a command of the application are not included.
+pub fn command_names<COMM: EnumMetaData>() -> Vec<String> {
+    let metadata = COMM::get_metadata();
+    let builtin_commands = BUILTIN_COMMANDS.iter()
+        .filter(|&&(name, _)| !metadata.contains_key(name))
+        .map(|&(name, _)| name.to_string());
+    let commands = metadata.iter()
+        .filter(|&(_, metadata)| !metadata.completion_hidden)
+        .map(|(name, _)| name.clone());
+    commands.chain(builtin_commands).collect()
+}
+
+/// Get the description of the commands, including the built-in ones, without the hidden ones,
+/// keyed by name.
+pub fn command_descriptions<COMM: EnumMetaData>() -> HashMap<String, String> {
+    let metadata = COMM::get_metadata();
+    let builtin_commands = BUILTIN_COMMANDS.iter()
+        .filter(|&&(name, _)| !metadata.contains_key(name))
+        .map(|&(name, help)| (name.to_string(), help.to_string()));
+    let commands = metadata.iter()
+        .filter(|&(_, metadata)| !metadata.completion_hidden)
+        .map(|(name, metadata)| (name.clone(), metadata.help_text.clone()));
+    commands.chain(builtin_commands).collect()
+}
+
+/// Get the help of the commands and settings, without the hidden ones.
+/// The settings are prefixed by `set` to distinguish them from the commands.
+pub fn help_entries<COMM: EnumMetaData, SETT: EnumMetaData>() -> Vec<(String, String)> {
+    let metadata = COMM::get_metadata();
+    let builtin_commands = BUILTIN_COMMANDS.iter()
+        .filter(|&&(name, _)| !metadata.contains_key(name))
+        .map(|&(name, help)| (name.to_string(), help.to_string()));
+    let commands = metadata.iter()
+        .filter(|&(_, metadata)| !metadata.completion_hidden)
+        .map(|(name, metadata)| (name.clone(), metadata.help_text.clone()));
+    let settings = SETT::get_metadata().into_iter()
+        .filter(|&(_, ref metadata)| !metadata.completion_hidden)
+        .map(|(name, metadata)| (format!("set {}", name), metadata.help_text));
+    let mut entries: Vec<_> = commands.chain(settings).chain(builtin_commands).collect();
+    entries.sort();
+    entries
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Get the names and descriptions of the application commands registered by the application.
+    pub fn app_command_descriptions(&self) -> HashMap<String, String> {
+        self.model.app_commands.iter()
+            .map(|(name, &(description, _))| (name.to_string(), description.to_string()))
+            .collect()
+    }
+
+    /// Get the description of the commands, including the application commands registered by the
+    /// application, keyed by name.
+    pub fn all_command_descriptions(&self) -> HashMap<String, String> {
+        let mut descriptions = command_descriptions::<COMM>();
+        descriptions.extend(self.app_command_descriptions());
+        descriptions
+    }
+
+    /// Get the help of the commands and settings, including the application commands registered
+    /// by the application.
+    fn all_help_entries(&self) -> Vec<(String, String)> {
+        let mut entries = help_entries::<COMM, SETT>();
+        entries.extend(self.app_command_descriptions());
+        entries.sort();
+        entries
+    }
+
+    /// Get the categories of the help entries, keyed by the name of the entry.
+    fn help_categories(&self) -> HashMap<String, String> {
+        let settings = self.model.setting_categories.iter()
+            .map(|(name, category)| (format!("set {}", name), category.clone()));
+        self.model.command_categories.clone().into_iter()
+            .chain(settings)
+            .collect()
+    }
+
+    /// Set the category of a command, used to group the commands in the completion view and to
+    /// filter the help with `help category`.
+    pub fn set_command_category(&mut self, name: &str, category: &str) {
+        self.model.command_categories.insert(name.to_string(), category.to_string());
+        self.update_command_completer();
+        self.update_help_completer();
+    }
+
+    /// Set the category of a setting, used to group the settings in the completion view and to
+    /// filter the help with `help category`.
+    pub fn set_setting_category(&mut self, name: &str, category: &str) {
+        self.model.setting_categories.insert(name.to_string(), category.to_string());
+        self.update_help_completer();
+    }
+
+    /// Group the help entries by category.
+    pub fn update_help_completer(&self) {
+        let completer = HelpCompleter::new(self.all_help_entries())
+            .categories(&self.help_categories());
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert(HELP_COMMAND, Box::new(completer));
+        self.completion_view.emit(AddCompleters(completers));
+    }
+
+    /// Handle the `help [name]` command.
+    /// Without argument, the help of all the commands and settings is listed in the completion
+    /// view. With a category, only the help of the commands and settings of this category is
+    /// listed. Otherwise, the help of the command or setting is shown in the status bar.
+    pub fn help_command(&mut self, name: &str) {
+        if name.is_empty() {
+            self.model.relm.stream().emit(ShowHelp(String::new()));
+            return;
+        }
+        let setting_name = format!("set {}", name);
+        let help: Vec<_> = self.all_help_entries().into_iter()
+            .filter(|&(ref entry_name, _)| *entry_name == name || *entry_name == setting_name)
+            .map(|(entry_name, help)| format!("{}: {}", entry_name, help))
+            .collect();
+        if help.is_empty() {
+            let categories = self.help_categories();
+            let is_category = !categories.is_empty() &&
+                (name == DEFAULT_CATEGORY || categories.values().any(|category| category == name));
+            if is_category {
+                self.model.relm.stream().emit(ShowHelp(name.to_string()));
+                return;
+            }
+            self.error(Error::Msg(format!("No help for {}", name)));
+        }
+        else {
+            self.info(&help.join("; "));
+        }
+    }
+}
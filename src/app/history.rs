@@ -0,0 +1,461 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk::{self, Continue};
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+use mg_settings::errors::Error;
+
+use app::{Mg, COMMAND_MODE, builtin_application_commands};
+use app::Msg::ShowHistory;
+use app::settings::split_list;
+use completion::{Completer, HistoryCompleter, HISTORY_COMPLETER_IDENT};
+use completion::completion_view::Msg::AddCompleters;
+
+/// The name of the built-in setting for the comma-separated prefixes of the commands which are not
+/// recorded in the history.
+pub const HISTORY_EXCLUDE_SETTING: &str = "history-exclude";
+/// The name of the built-in setting for the maximum number of commands in the history.
+pub const HISTORY_MAX_SIZE_SETTING: &str = "history-max-size";
+/// The default maximum number of commands in the history.
+pub const HISTORY_MAX_SIZE: usize = 1000;
+
+/// The delay, in milliseconds, before writing the history, so that many commands in a short time
+/// only write the file once.
+const WRITE_DELAY: u32 = 500;
+
+/// A file where the history of the commands is saved, the oldest first, one command per line.
+pub struct HistoryFile {
+    commands: Vec<String>,
+    path: PathBuf,
+    write_scheduled: bool,
+}
+
+impl HistoryFile {
+    /// Create a new history file.
+    pub fn new(path: PathBuf) -> Self {
+        HistoryFile {
+            commands: vec![],
+            path,
+            write_scheduled: false,
+        }
+    }
+
+    /// Read the commands of the file, the oldest first.
+    pub fn read(&self) -> io::Result<Vec<String>> {
+        let file = File::open(&self.path)?;
+        let mut commands = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let command = line.trim();
+            if !command.is_empty() {
+                commands.push(command.to_string());
+            }
+        }
+        Ok(commands)
+    }
+
+    /// Write the history to the file.
+    /// The history is written to a temporary file which is then renamed so that the file is never
+    /// partially written.
+    pub fn write(&self) -> io::Result<()> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        {
+            let mut file = File::create(&temp_path)?;
+            for command in &self.commands {
+                writeln!(file, "{}", command)?;
+            }
+        }
+        fs::rename(temp_path, &self.path)
+    }
+}
+
+/// Write the history file, logging the error if any.
+fn write(history_file: &HistoryFile) {
+    if let Err(error) = history_file.write() {
+        warn!("cannot write the history to `{}`: {}", history_file.path.to_string_lossy(), error);
+    }
+}
+
+/// The state of the navigation in the history from the command entry.
+pub struct HistoryNavigation {
+    /// The index of the command shown in the entry, the length of the history for the original text.
+    index: usize,
+    /// The text of the entry before navigating in the history.
+    original: String,
+    /// Only the commands starting with this prefix are shown.
+    prefix: String,
+    /// The text put in the entry, to know when the user edited it.
+    shown: String,
+}
+
+impl HistoryNavigation {
+    /// Start a navigation from the text of the entry, after the newest command of the history.
+    fn new(history: &[String], text: String) -> Self {
+        HistoryNavigation {
+            index: history.len(),
+            original: text.clone(),
+            prefix: text.clone(),
+            shown: text,
+        }
+    }
+
+    /// Get the next command of the history starting with the prefix and different from the text of
+    /// the entry, or the original text after the newest command.
+    fn next(&self, history: &[String], text: &str) -> (usize, String) {
+        history.iter().enumerate()
+            .skip(self.index + 1)
+            .find(|&(_, command)| command.starts_with(&self.prefix) && command != text)
+            .map(|(index, command)| (index, command.clone()))
+            .unwrap_or_else(|| (history.len(), self.original.clone()))
+    }
+
+    /// Get the previous command of the history starting with the prefix and different from the
+    /// text of the entry, if any.
+    fn previous(&self, history: &[String], text: &str) -> Option<(usize, String)> {
+        history[..self.index].iter().enumerate()
+            .rev()
+            .find(|&(_, command)| command.starts_with(&self.prefix) && command != text)
+            .map(|(index, command)| (index, command.clone()))
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Add a command executed from the command entry to the history.
+    /// A command already in the history is moved to the end.
+    /// The application commands, like `complete-next`, are not recorded.
+    pub fn add_to_history(&mut self, command: &str) {
+        let command = command.trim();
+        // NOTE: the repeat command is an application command, so it is not recorded and cannot
+        // repeat itself.
+        if command.is_empty() || self.is_application_command(command) || self.is_excluded_from_history(command) {
+            return;
+        }
+        push_unique(&mut self.model.command_history, command.to_string());
+        self.prune_history();
+    }
+
+    /// Get the commands executed from the command entry, the oldest first.
+    pub fn command_history(&self) -> &[String] {
+        &self.model.command_history
+    }
+
+    /// Show the next command of the history starting with the text of the entry when the
+    /// navigation started, or this text after the newest command.
+    pub fn history_next(&mut self) {
+        if !self.is_navigating_history() {
+            return;
+        }
+        let next = self.model.history_navigation.as_ref().expect("history navigation")
+            .next(&self.model.command_history, &self.model.status_bar_command);
+        self.show_history_entry(next);
+    }
+
+    /// Show the previous command of the history starting with the text of the entry when the
+    /// navigation started.
+    pub fn history_previous(&mut self) {
+        if !self.is_navigating_history() {
+            let text = self.model.status_bar_command.clone();
+            self.model.history_navigation = Some(HistoryNavigation::new(&self.model.command_history, text));
+        }
+        let previous = self.model.history_navigation.as_ref().expect("history navigation")
+            .previous(&self.model.command_history, &self.model.status_bar_command);
+        if let Some(previous) = previous {
+            self.show_history_entry(previous);
+        }
+    }
+
+    /// Check if the entry shows the text put by the history navigation.
+    /// When the user edited it, a new navigation starts with the new text as prefix.
+    fn is_navigating_history(&self) -> bool {
+        self.model.history_navigation.as_ref()
+            .map(|navigation| navigation.shown == self.model.status_bar_command)
+            .unwrap_or(false)
+    }
+
+    /// Show the command at this index of the history in the entry.
+    fn show_history_entry(&mut self, (index, command): (usize, String)) {
+        if let Some(ref mut navigation) = self.model.history_navigation {
+            navigation.index = index;
+            navigation.shown = command.clone();
+        }
+        self.set_input(&command);
+    }
+
+    /// Check if the command is an application command, built-in or registered by the application.
+    fn is_application_command(&self, command: &str) -> bool {
+        let name = command.split_whitespace().next().unwrap_or(command);
+        builtin_application_commands().contains(&name) || self.model.app_commands.contains_key(name)
+    }
+
+    /// Check if the command must not be recorded in the history, because it starts with one of
+    /// the prefixes of the `history-exclude` setting or the history filter rejects it.
+    fn is_excluded_from_history(&self, command: &str) -> bool {
+        let excluded = self.model.history_exclude.iter().any(|prefix| command.starts_with(prefix.as_str()));
+        let kept = self.model.history_filter.as_ref().map(|filter| filter(command)).unwrap_or(true);
+        excluded || !kept
+    }
+
+    /// Remove the excluded commands from the history and the oldest ones above the maximum size,
+    /// and save the history.
+    fn prune_history(&mut self) {
+        let history = mem::replace(&mut self.model.command_history, vec![]);
+        let history = prune(history, self.model.history_max_size, |command| self.is_excluded_from_history(command));
+        self.model.command_history = history;
+        self.model.history_navigation = None;
+        self.save_history();
+    }
+
+    /// Delete the commands deleted from the history window from the history.
+    pub fn delete_history_items(&mut self, commands: &[String]) {
+        // NOTE: the history has no duplicate, so a command identifies a single entry.
+        self.model.command_history.retain(|entry| !commands.contains(entry));
+        self.save_history();
+    }
+
+    /// Execute again the last command executed from the command entry.
+    pub fn repeat_last_entry_command(&mut self) {
+        let command = self.model.command_history.last().cloned();
+        match command {
+            Some(command) => {
+                self.info(&format!("Repeating: {}", command));
+                self.set_current_identifier(':');
+                if let Some(message) = self.handle_command(Some(command), false, None) {
+                    self.model.relm.stream().emit(message);
+                }
+            },
+            None => self.error(Error::Msg("No command to repeat".to_string())),
+        }
+    }
+
+    /// Restore the mode which was active when the history window was opened.
+    pub fn restore_mode_before_history(&mut self) {
+        if let Some(mode) = self.model.mode_before_history.take() {
+            self.set_mode(&mode);
+        }
+    }
+
+    /// Save the history to the history file, if any.
+    fn save_history(&self) {
+        if let Some(ref history_file) = self.model.history_file {
+            let mut file = history_file.borrow_mut();
+            file.commands = self.model.command_history.clone();
+            if !file.write_scheduled {
+                file.write_scheduled = true;
+                let history_file = history_file.clone();
+                gtk::timeout_add(WRITE_DELAY, move || {
+                    let mut file = history_file.borrow_mut();
+                    file.write_scheduled = false;
+                    write(&file);
+                    Continue(false)
+                });
+            }
+        }
+    }
+
+    /// Save the history to the specified file, and restore the commands that were saved in this
+    /// file before the ones already in the history.
+    pub fn set_history_persistence(&mut self, path: PathBuf) {
+        let history_file = HistoryFile::new(path.clone());
+        if path.exists() {
+            match history_file.read() {
+                Ok(commands) => {
+                    let history = mem::replace(&mut self.model.command_history, vec![]);
+                    for command in commands.into_iter().chain(history) {
+                        push_unique(&mut self.model.command_history, command);
+                    }
+                },
+                Err(error) => warn!("cannot read the history from `{}`: {}", path.to_string_lossy(), error),
+            }
+        }
+        self.model.history_file = Some(Rc::new(RefCell::new(history_file)));
+        // NOTE: the commands of the file that are now excluded are removed from it.
+        self.prune_history();
+    }
+
+    /// Set the prefixes of the commands which are not recorded in the history from the value of
+    /// the `history-exclude` setting.
+    /// The commands already in the history starting with these prefixes are removed.
+    pub fn set_history_exclude(&mut self, value: &Value) {
+        match *value {
+            Value::Str(ref prefixes) => {
+                self.model.history_exclude = split_list(prefixes);
+                self.prune_history();
+            },
+            _ => self.error(Error::Msg(format!("Expecting a string for setting {}", HISTORY_EXCLUDE_SETTING))),
+        }
+    }
+
+    /// Set a function deciding whether a command is recorded in the history: it is only recorded
+    /// when the function returns true.
+    /// The commands already in the history rejected by this function are removed.
+    pub fn set_history_filter(&mut self, filter: Box<Fn(&str) -> bool>) {
+        self.model.history_filter = Some(filter);
+        self.prune_history();
+    }
+
+    /// Set the maximum number of commands in the history from the value of the `history-max-size`
+    /// setting.
+    pub fn set_history_max_size(&mut self, value: &Value) {
+        match *value {
+            Value::Int(size) if size >= 0 => {
+                self.model.history_max_size = size as usize;
+                self.prune_history();
+            },
+            _ => self.error(Error::Msg(format!("Expecting a positive integer for setting {}",
+                HISTORY_MAX_SIZE_SETTING))),
+        }
+    }
+
+    /// Show the history of the commands in the completion view, the newest first.
+    /// Selecting a command puts it in the command entry, so that it can be edited before being
+    /// executed with Enter.
+    pub fn show_command_history_window(&mut self) {
+        if self.model.mode_string != COMMAND_MODE {
+            self.model.mode_before_history = Some(self.model.mode_string.clone());
+        }
+        let entries = self.model.command_history.iter().rev().cloned().collect();
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert(HISTORY_COMPLETER_IDENT, Box::new(HistoryCompleter::new(entries)));
+        self.completion_view.emit(AddCompleters(completers));
+        self.model.relm.stream().emit(ShowHistory);
+    }
+}
+
+/// Add the command at the end of the history, removing its previous occurrence so that the history
+/// has no duplicate.
+fn push_unique(history: &mut Vec<String>, command: String) {
+    history.retain(|entry| *entry != command);
+    history.push(command);
+}
+
+/// Remove the excluded commands and the oldest ones above the maximum size from the history.
+fn prune<F: Fn(&str) -> bool>(history: Vec<String>, max_size: usize, is_excluded: F) -> Vec<String> {
+    let mut history: Vec<_> = history.into_iter()
+        .filter(|command| !is_excluded(command))
+        .collect();
+    if history.len() > max_size {
+        let excess = history.len() - max_size;
+        history.drain(..excess);
+    }
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{File, remove_file};
+    use std::io::Write;
+    use std::path::Path;
+
+    use super::{HistoryFile, HistoryNavigation, prune, push_unique};
+
+    fn history() -> Vec<String> {
+        ["open a", "quit", "open b", "open", "help"].iter()
+            .map(|command| command.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_previous_skips_other_prefixes_and_same_text() {
+        let history = history();
+        let navigation = HistoryNavigation::new(&history, "open".to_string());
+        // "help" does not start with the prefix and "open" is the text of the entry.
+        assert_eq!(navigation.previous(&history, "open"), Some((2, "open b".to_string())));
+    }
+
+    #[test]
+    fn test_navigation_keeps_prefix() {
+        let history = history();
+        let mut navigation = HistoryNavigation::new(&history, "open ".to_string());
+        let (index, command) = navigation.previous(&history, "open ").expect("previous");
+        assert_eq!((index, command.as_str()), (2, "open b"));
+        navigation.index = index;
+        let (index, command) = navigation.previous(&history, &command).expect("previous");
+        assert_eq!((index, command.as_str()), (0, "open a"));
+        navigation.index = index;
+        // There is no older command with the prefix.
+        assert_eq!(navigation.previous(&history, &command), None);
+        let (index, command) = navigation.next(&history, &command);
+        assert_eq!((index, command.as_str()), (2, "open b"));
+        navigation.index = index;
+        // The original text is shown after the newest command with the prefix.
+        assert_eq!(navigation.next(&history, &command), (history.len(), "open ".to_string()));
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let history = vec![];
+        let navigation = HistoryNavigation::new(&history, "open".to_string());
+        assert_eq!(navigation.previous(&history, "open"), None);
+        assert_eq!(navigation.next(&history, "open"), (0, "open".to_string()));
+    }
+
+    #[test]
+    fn test_push_unique() {
+        let mut history = history();
+        push_unique(&mut history, "quit".to_string());
+        assert_eq!(history, ["open a", "open b", "open", "help", "quit"]);
+        push_unique(&mut history, "close".to_string());
+        assert_eq!(history.last().map(String::as_str), Some("close"));
+    }
+
+    #[test]
+    fn test_prune() {
+        let history = prune(history(), 3, |command| command.starts_with("open "));
+        // The excluded commands are removed before the oldest ones.
+        assert_eq!(history, ["quit", "open", "help"]);
+        assert_eq!(prune(history.clone(), 2, |_| false), ["open", "help"]);
+        assert_eq!(prune(history, 0, |_| false), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_history_file() {
+        let path = env::temp_dir().join("mg-unit-test-history-file");
+        {
+            let mut file = File::create(&path).expect("create history");
+            write!(file, "  open a \n\nquit\n   \n").expect("write history");
+        }
+        let mut history_file = HistoryFile::new(path.clone());
+        // The blank lines are skipped and the commands are trimmed.
+        let commands = history_file.read().expect("read history");
+        assert_eq!(commands, ["open a", "quit"]);
+        history_file.commands = history();
+        history_file.write().expect("write history");
+        assert_eq!(history_file.read().expect("read history"), history());
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        assert!(!Path::new(&temp_path).exists());
+        remove_file(path).expect("remove history");
+    }
+}
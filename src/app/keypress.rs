@@ -19,13 +19,15 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::char;
+use std::collections::VecDeque;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use gdk::EventKey;
-use gdk::enums::key::Escape;
-use gtk::Inhibit;
+use gdk::{EventKey, ModifierType};
+use gdk::enums::key::{End, Escape, Home, Return};
+use gtk::{self, Continue, Entry, Inhibit};
 use mg_settings::{
     self,
     EnumFromStr,
@@ -33,20 +35,55 @@ use mg_settings::{
     SettingCompletion,
     SpecialCommand,
 };
+use mg_settings::key::Key::{self, Char};
 
 use app::{Mg, Mode};
 use app::ActivationType::Current;
-use app::Msg::{self, EnterNormalModeAndReset};
+use app::Msg::{self, EnterNormalModeAndReset, FlushKeyPresses, InputMethodKeyPress, KeyPress};
+use app::status_bar::{
+    filter_input_method_key_press,
+    input_method_consumed_key,
+    is_composing,
+    reset_input_method_consumed_key,
+};
+use completion::completion_view::Msg::{SelectFirst, SelectLast};
+use key_converter::gdk_key_to_key;
+
+/// The maximum number of key presses kept while waiting for a mode change.
+const MAX_QUEUED_KEY_PRESSES: usize = 64;
+
+/// Two characters that exit the input mode when typed quickly one after the other.
+pub struct EscapeChord {
+    first: char,
+    keep_text: bool,
+    second: char,
+    window: Duration,
+}
 
 impl<COMM, SETT> Mg<COMM, SETT>
     where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
           SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
 {
     /// Handle the key press event for the command mode.
+    /// Home and End select the first and last completions when a completion is selected, and move
+    /// the cursor of the entry otherwise.
+    /// Control-Enter executes the selected completion.
     #[allow(non_upper_case_globals)]
     fn command_key_press(&mut self, key: &EventKey) -> Option<Msg<COMM, SETT>> {
         match key.get_keyval() {
             Escape => Some(EnterNormalModeAndReset),
+            End if self.model.completion_selected.get() => {
+                self.completion_view.emit(SelectLast);
+                None
+            },
+            Home if self.model.completion_selected.get() => {
+                self.completion_view.emit(SelectFirst);
+                None
+            },
+            Return if key.get_state().contains(ModifierType::CONTROL_MASK) => {
+                self.complete_accept();
+                None
+            },
             _ => self.handle_shortcut(key),
         }
     }
@@ -55,17 +92,19 @@ impl<COMM, SETT> Mg<COMM, SETT>
     fn command_key_release(&mut self, _key: &EventKey) -> Option<Msg<COMM, SETT>> {
         if !self.is_normal_command() && COMM::is_incremental(self.model.current_command_mode) {
             let command = self.model.status_bar_command.clone(); // TODO: remove this useless clone.
-            let msg = self.handle_special_command(Current, &command);
-            return msg;
+            self.handle_special_command(Current, &command);
         }
         None
     }
 
     /// Check if the key should be inhibitted for the command mode.
     #[allow(non_upper_case_globals)]
-    fn inhibit_command_key_press(current_mode: &Rc<Cell<Mode>>, key: &EventKey) -> Inhibit {
+    fn inhibit_command_key_press(current_mode: &Rc<Cell<Mode>>, completion_selected: &Rc<Cell<bool>>, key: &EventKey)
+        -> Inhibit
+    {
         match key.get_keyval() {
             Escape => Inhibit(false),
+            End | Home if completion_selected.get() => Inhibit(true),
             _ => Self::inhibit_handle_shortcut(current_mode, key),
         }
     }
@@ -80,32 +119,156 @@ impl<COMM, SETT> Mg<COMM, SETT>
     }
 
     /// Check if the key should be inhibitted.
-    pub fn inhibit_key_press(current_mode: &Rc<Cell<Mode>>, key: &EventKey) -> Inhibit {
+    /// The keys are never inhibitted while an input method is composing text, so that it receives
+    /// them.
+    pub fn inhibit_key_press(current_mode: &Rc<Cell<Mode>>, completion_selected: &Rc<Cell<bool>>, key: &EventKey)
+        -> Inhibit
+    {
+        // NOTE: a key consumed by the input method must not be handled again by the entry.
+        if input_method_consumed_key() {
+            return Inhibit(true);
+        }
+        if is_composing() {
+            return Inhibit(false);
+        }
+        Self::inhibit_mode_key_press(current_mode, completion_selected, key)
+    }
+
+    /// Check if the key should be inhibitted for the current mode.
+    fn inhibit_mode_key_press(current_mode: &Rc<Cell<Mode>>, completion_selected: &Rc<Cell<bool>>, key: &EventKey)
+        -> Inhibit
+    {
         match current_mode.get() {
             Mode::Normal => Self::inhibit_normal_key_press(current_mode, key),
-            Mode::Command => Self::inhibit_command_key_press(current_mode, key),
+            Mode::Command => Self::inhibit_command_key_press(current_mode, completion_selected, key),
             Mode::BlockingInput | Mode::Input => Self::inhibit_input_key_press(current_mode, key),
             _ => Self::inhibit_handle_shortcut(current_mode, key)
         }
     }
 
+    /// Exit the input mode after the escape chord was typed.
+    /// The input callback receives the text without the chord if it is kept, None otherwise.
+    fn escape_input_with_chord(&mut self) -> Msg<COMM, SETT> {
+        let answer =
+            match self.model.input_escape_chord {
+                Some(ref chord) if chord.keep_text => {
+                    let mut text = self.model.status_bar_command.clone();
+                    // NOTE: the second character might not be in the entry yet.
+                    if text.ends_with(chord.second) {
+                        text.pop();
+                    }
+                    if text.ends_with(chord.first) {
+                        text.pop();
+                    }
+                    Some(text)
+                },
+                _ => None,
+            };
+        if let Some(callback) = self.model.input_callback.take() {
+            callback.call_box((answer, self.model.shortcut_pressed));
+        }
+        EnterNormalModeAndReset
+    }
+
+    /// Check if the key completes the input escape chord.
+    fn input_escape_chord_typed(&mut self, key: &EventKey) -> bool {
+        let character =
+            match gdk_key_to_key(key) {
+                Some(Char(character)) => character,
+                _ => {
+                    self.model.last_input_key = None;
+                    return false;
+                },
+            };
+        let now = Instant::now();
+        let typed =
+            match (&self.model.input_escape_chord, &self.model.last_input_key) {
+                (&Some(ref chord), &Some((last_character, time))) =>
+                    last_character == chord.first && character == chord.second &&
+                        now.duration_since(time) <= chord.window,
+                _ => false,
+            };
+        self.model.last_input_key =
+            if typed {
+                None
+            }
+            else {
+                Some((character, now))
+            };
+        typed
+    }
+
+    /// Get the message for a key press.
+    /// The keys used by an input method to compose text are not handled as shortcuts: this is
+    /// checked when the key is pressed since the composition can be over when the message is
+    /// handled.
+    /// In command mode, the input method of the command entry receives the keys which would be
+    /// inhibitted before they are handled as shortcuts, so this must be called before
+    /// `inhibit_key_press()`.
+    /// The other keys are not given to it here since the entry does it when it receives them.
+    pub fn key_press_msg(current_mode: &Rc<Cell<Mode>>, command_entry: &Rc<RefCell<Option<Entry>>>,
+        completion_selected: &Rc<Cell<bool>>, key: &EventKey) -> Msg<COMM, SETT>
+    {
+        let entry = command_entry.borrow();
+        let consumed =
+            match *entry {
+                Some(ref entry) if current_mode.get() == Mode::Command && !is_composing() &&
+                    Self::inhibit_mode_key_press(current_mode, completion_selected, key).0 =>
+                    filter_input_method_key_press(entry, key),
+                _ => {
+                    reset_input_method_consumed_key();
+                    false
+                },
+            };
+        if consumed || is_composing() {
+            InputMethodKeyPress
+        }
+        else {
+            KeyPress(key.clone())
+        }
+    }
+
+    /// Get the choice of the current question typed with the character.
+    /// A choice typed in another case is accepted if the character is not itself a choice.
+    fn find_choice(&self, character: char) -> Option<char> {
+        if self.model.choices.contains(&character) {
+            return Some(character);
+        }
+        let lowercase: String = character.to_lowercase().collect();
+        self.model.choices.iter()
+            .find(|choice| choice.to_lowercase().collect::<String>() == lowercase)
+            .cloned()
+    }
+
     /// Handle the key press event for the input mode.
     #[allow(non_upper_case_globals)]
     fn input_key_press(&mut self, key: &EventKey) -> Option<Msg<COMM, SETT>> {
+        // NOTE: cancel the timeout of the dialog.
+        self.model.dialog_timeout_pending = false;
         match key.get_keyval() {
             Escape => {
                 if let Some(callback) = self.model.input_callback.take() {
-                    callback(None, self.model.shortcut_pressed);
+                    self.model.dialog_generation += 1;
+                    callback.call_box((None, self.model.shortcut_pressed));
                 }
                 Some(EnterNormalModeAndReset)
             },
             keyval => {
+                if self.input_escape_chord_typed(key) {
+                    return Some(self.escape_input_with_chord());
+                }
                 if self.handle_input_shortcut(key) {
                     return None;
                 }
+                else if keyval == Return && !self.model.choices.is_empty() {
+                    if let Some(choice) = self.model.default_choice {
+                        self.set_dialog_answer(&choice.to_string());
+                        return None;
+                    }
+                }
                 else if let Some(character) = char::from_u32(keyval) {
-                    if self.model.choices.contains(&character) {
-                        self.set_dialog_answer(&character.to_string());
+                    if let Some(choice) = self.find_choice(character) {
+                        self.set_dialog_answer(&choice.to_string());
                         return None;
                     }
                 }
@@ -114,11 +277,82 @@ impl<COMM, SETT> Mg<COMM, SETT>
         }
     }
 
+    /// Exit the input mode when the two keys are typed in the specified time window, like
+    /// Escape would.
+    /// When `keep_text` is true, the input callback receives the text without the chord instead of
+    /// None.
+    /// Only the character keys are supported.
+    pub fn set_input_escape_chord(&mut self, keys: &[Key], window: Duration, keep_text: bool) {
+        match (keys.first(), keys.get(1)) {
+            (Some(&Char(first)), Some(&Char(second))) if keys.len() == 2 => {
+                self.model.input_escape_chord = Some(EscapeChord {
+                    first,
+                    keep_text,
+                    second,
+                    window,
+                });
+            },
+            _ => warn!("The input escape chord must be two characters"),
+        }
+    }
+
+    /// Process the key presses that were queued while a mapping was executed.
+    pub fn flush_key_presses(&mut self) {
+        self.model.queue_key_presses = false;
+        // NOTE: stop processing when a key triggers a mapping since the next keys must wait for
+        // this mapping to be executed.
+        while !self.model.queue_key_presses {
+            match self.model.queued_key_presses.pop_front() {
+                Some(key) => self.process_key_press(&key),
+                None => break,
+            }
+        }
+    }
+
     /// Handle the key press event.
+    /// The key is queued if a mapping is being executed, so that it is handled in the mode set by
+    /// this mapping.
     pub fn key_press(&mut self, key: &EventKey) {
+        if self.model.queue_key_presses {
+            if self.model.queued_key_presses.len() >= MAX_QUEUED_KEY_PRESSES {
+                warn!("Too many queued key presses, dropping the oldest one");
+                self.model.queued_key_presses.pop_front();
+            }
+            self.model.queued_key_presses.push_back(key.clone());
+        }
+        else {
+            self.process_key_press(key);
+        }
+    }
+
+    /// Get the key presses waiting for the current mapping to be executed.
+    pub fn queued_key_presses(&self) -> &VecDeque<EventKey> {
+        &self.model.queued_key_presses
+    }
+
+    /// Queue the next key presses until all the pending messages are processed.
+    /// This is used after executing a mapping because this mapping can change the mode.
+    pub fn queue_next_key_presses(&mut self) {
+        if !self.model.queue_key_presses {
+            self.model.queue_key_presses = true;
+            let stream = self.model.relm.stream().clone();
+            // NOTE: the idle callback is called after the pending messages (including a possible
+            // SetMode) are processed.
+            gtk::idle_add(move || {
+                stream.emit(FlushKeyPresses);
+                Continue(false)
+            });
+        }
+    }
+
+    /// Process the key press event.
+    fn process_key_press(&mut self, key: &EventKey) {
         let msg =
             match self.model.current_mode.get() {
-                Mode::Normal => self.normal_key_press(key),
+                Mode::Normal => {
+                    self.dismiss_sticky_error();
+                    self.normal_key_press(key)
+                },
                 Mode::Command => self.command_key_press(key),
                 Mode::BlockingInput | Mode::Input => self.input_key_press(key),
                 _ => self.handle_shortcut(key)
@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use gtk::{
+    self,
+    ContainerExt,
+    GridExt,
+    GtkWindowExt,
+    LabelExt,
+    WidgetExt,
+    WindowPosition,
+    WindowType,
+};
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+
+use app::{Mg, BLOCKING_INPUT_MODE, INPUT_MODE};
+use app::shortcut::{describe_mapping, shortcut_to_string};
+use key_converter::key_to_string;
+
+/// The spacing, in pixels, around the mappings overlay and between its columns.
+const OVERLAY_SPACING: u32 = 10;
+/// The opacity of the mappings overlay.
+const OVERLAY_OPACITY: f64 = 0.85;
+/// The number of mappings in a column of the mappings overlay.
+const OVERLAY_ROWS: usize = 20;
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Hide the mappings overlay.
+    pub fn hide_mappings_overlay(&mut self) {
+        if let Some(overlay) = self.model.mappings_overlay.take() {
+            overlay.destroy();
+        }
+    }
+
+    /// Show a window listing the mappings of the current mode over the main window.
+    /// The mappings are shown with their description, when they have one.
+    /// In an input dialog, its shortcuts are listed too.
+    /// The window does not take the focus and is closed by the next key press.
+    pub fn show_mappings_overlay(&mut self) {
+        self.hide_mappings_overlay();
+        let command_descriptions = self.all_command_descriptions();
+        let mut mappings: Vec<_> = self.model.mappings.get(self.mapping_mode().as_str())
+            .map(|mappings| mappings.iter()
+                .map(|(keys, mapping)| {
+                    let text = describe_mapping(mapping, &command_descriptions)
+                        .unwrap_or_else(|| mapping.action.clone());
+                    (shortcut_to_string(keys, true), text)
+                })
+                .collect())
+            .unwrap_or_default();
+        if self.model.mode_string == INPUT_MODE || self.model.mode_string == BLOCKING_INPUT_MODE {
+            // NOTE: the shortcuts of the dialog being shown are listed with the mappings of its
+            // mode.
+            mappings.extend(self.model.shortcuts.iter()
+                .map(|(key, answer)| (key_to_string(key), format!("answer: {}", answer))));
+        }
+        mappings.sort();
+
+        let grid = gtk::Grid::new();
+        grid.set_border_width(OVERLAY_SPACING);
+        grid.set_column_spacing(OVERLAY_SPACING);
+        for (index, &(ref keys, ref action)) in mappings.iter().enumerate() {
+            let column = (index / OVERLAY_ROWS * 2) as i32;
+            let row = (index % OVERLAY_ROWS) as i32;
+            let keys_label = gtk::Label::new(Some(keys.as_str()));
+            keys_label.set_xalign(0.0);
+            grid.attach(&keys_label, column, row, 1, 1);
+            let action_label = gtk::Label::new(Some(action.as_str()));
+            action_label.set_xalign(0.0);
+            grid.attach(&action_label, column + 1, row, 1, 1);
+        }
+
+        let overlay = gtk::Window::new(WindowType::Popup);
+        overlay.set_accept_focus(false);
+        overlay.set_transient_for(&self.window);
+        overlay.set_position(WindowPosition::CenterOnParent);
+        overlay.set_opacity(OVERLAY_OPACITY);
+        overlay.add(&grid);
+        overlay.show_all();
+        self.model.mappings_overlay = Some(overlay);
+    }
+
+    /// Show the mappings of the new mode in the mappings overlay, if it is shown.
+    pub fn update_mappings_overlay(&mut self) {
+        if self.model.mappings_overlay.is_some() {
+            self.show_mappings_overlay();
+        }
+    }
+}
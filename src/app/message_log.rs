@@ -0,0 +1,332 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions, create_dir_all};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gdk::{SELECTION_CLIPBOARD, SELECTION_PRIMARY};
+use gtk::{self, Clipboard, ClipboardExt, Continue};
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use mg_settings::errors::Error;
+
+use app::Mg;
+use app::Msg::ShowMessages;
+use completion::{Completer, MessageCompleter, MESSAGES_COMPLETER_IDENT};
+use completion::completion_view::Msg::AddCompleters;
+
+/// The name of the command showing the message log.
+pub const MESSAGES_COMMAND: &str = "messages";
+
+/// The levels of the messages written to the message log file.
+const LOG_FILE_LEVELS: &[&str] = &["ERROR", "WARNING"];
+/// The number of messages kept in memory.
+const MAX_LOG_ENTRIES: usize = 200;
+
+/// A message shown in the status bar.
+#[derive(Clone)]
+pub struct LogEntry {
+    /// The severity of the message, like `ERROR` or `INFO`.
+    pub level: String,
+    /// The text of the message.
+    pub message: String,
+    /// The time at which the message was shown.
+    pub time: SystemTime,
+}
+
+impl LogEntry {
+    /// Get the time of the message formatted as `HH:MM:SS` (UTC).
+    pub fn time_string(&self) -> String {
+        let seconds = self.time.duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        format!("{:02}:{:02}:{:02}", seconds / 3600 % 24, seconds / 60 % 60, seconds % 60)
+    }
+
+    /// Get the time of the message formatted as an ISO 8601 timestamp (UTC), like
+    /// `2018-04-01T12:30:00Z`.
+    pub fn iso_time_string(&self) -> String {
+        iso_timestamp(self.time)
+    }
+}
+
+/// Format a time as an ISO 8601 timestamp (UTC).
+fn iso_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    // NOTE: convert the number of days since the epoch to a date in the proleptic Gregorian
+    // calendar (see http://howardhinnant.github.io/date_algorithms.html#civil_from_days).
+    let days = seconds / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, seconds / 3600 % 24, seconds / 60 % 60,
+        seconds % 60)
+}
+
+/// Add the entry at the end of the log, removing the oldest one when the log is full.
+fn push_entry(message_log: &mut VecDeque<LogEntry>, entry: LogEntry) {
+    if message_log.len() >= MAX_LOG_ENTRIES {
+        message_log.pop_front();
+    }
+    message_log.push_back(entry);
+}
+
+/// A file where the status bar messages are appended.
+pub struct MessageLogFile {
+    flush_scheduled: bool,
+    max_size: u64,
+    path: PathBuf,
+    pending: Vec<String>,
+    /// Whether the last write failed, to only warn once until a write succeeds.
+    write_failed: bool,
+}
+
+impl MessageLogFile {
+    /// Create a new message log file which is truncated to its newest lines when it exceeds
+    /// `max_size` bytes.
+    pub fn new(path: PathBuf, max_size: u64) -> Self {
+        MessageLogFile {
+            flush_scheduled: false,
+            max_size,
+            path,
+            pending: vec![],
+            write_failed: false,
+        }
+    }
+
+    /// Write the pending messages to the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            create_dir_all(parent)?;
+        }
+        {
+            let mut file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(&self.path)?;
+            for line in self.pending.drain(..) {
+                writeln!(file, "{}", line)?;
+            }
+        }
+        self.truncate()
+    }
+
+    /// Only keep the newest lines of the file if it exceeds the maximum size.
+    fn truncate(&self) -> io::Result<()> {
+        let size = fs::metadata(&self.path)?.len();
+        if size > self.max_size {
+            let mut content = vec![];
+            File::open(&self.path)?.read_to_end(&mut content)?;
+            // NOTE: keep half of the maximum size to avoid truncating the file on every write and
+            // start the tail at the beginning of a line.
+            let tail_start = content.len().saturating_sub((self.max_size / 2) as usize);
+            let start =
+                if tail_start == 0 || content[tail_start - 1] == b'\n' {
+                    tail_start
+                }
+                else {
+                    content[tail_start..].iter()
+                        .position(|&byte| byte == b'\n')
+                        .map_or(content.len(), |index| tail_start + index + 1)
+                };
+            fs::write(&self.path, &content[start..])?;
+        }
+        Ok(())
+    }
+}
+
+/// Flush the message log, logging the error if any.
+/// The error is not shown in the status bar since it would be logged again, and it is only logged
+/// once until a write succeeds.
+fn flush(log_file: &mut MessageLogFile) {
+    match log_file.flush() {
+        Ok(()) => log_file.write_failed = false,
+        Err(error) => {
+            if !log_file.write_failed {
+                warn!("cannot write the message log to `{}`: {}", log_file.path.to_string_lossy(), error);
+            }
+            log_file.write_failed = true;
+        },
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Copy the message of the status bar, or the last logged one if there is none, to the
+    /// clipboard and the primary selection.
+    /// An error is copied with its causes.
+    pub fn copy_message(&mut self) {
+        let text = {
+            let message = &self.model.message;
+            match self.model.error_details {
+                Some((ref error, ref details)) if error == message && !message.is_empty() => Some(details.clone()),
+                _ if !message.is_empty() => Some(message.clone()),
+                _ => self.model.message_log.back().map(|entry| entry.message.clone()),
+            }
+        };
+        match text {
+            Some(text) => {
+                Clipboard::get(&SELECTION_CLIPBOARD).set_text(&text);
+                Clipboard::get(&SELECTION_PRIMARY).set_text(&text);
+                self.info("message copied");
+            },
+            None => self.info("No message to copy"),
+        }
+    }
+
+    /// Write the pending messages to the message log file.
+    /// This is useful to call in crash handlers since the messages are normally written when the
+    /// application is idle.
+    pub fn flush_message_log(&self) {
+        if let Some(ref log_file) = self.model.message_log_file {
+            flush(&mut log_file.borrow_mut());
+        }
+    }
+
+    /// Handle the `messages` command, which lists the message log in the completion view, the
+    /// newest message first.
+    pub fn show_message_log(&mut self) {
+        let entries = self.model.message_log.iter().rev()
+            .map(|entry| (format!("{} {}", entry.time_string(), entry.level), entry.message.clone()))
+            .collect();
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert(MESSAGES_COMPLETER_IDENT, Box::new(MessageCompleter::new(entries)));
+        self.completion_view.emit(AddCompleters(completers));
+        self.model.relm.stream().emit(ShowMessages);
+    }
+
+    /// Add a message shown in the status bar to the message log.
+    pub fn log_message(&mut self, level: &str, message: &str) {
+        let time = SystemTime::now();
+        push_entry(&mut self.model.message_log, LogEntry {
+            level: level.to_string(),
+            message: message.to_string(),
+            time,
+        });
+        if !LOG_FILE_LEVELS.contains(&level) {
+            return;
+        }
+        if let Some(ref log_file) = self.model.message_log_file {
+            let mut log = log_file.borrow_mut();
+            log.pending.push(format!("[{}] {}: {}", iso_timestamp(time), level, message));
+            if !log.flush_scheduled {
+                log.flush_scheduled = true;
+                let log_file = log_file.clone();
+                // NOTE: write when idle to avoid blocking the UI.
+                gtk::idle_add(move || {
+                    let mut log = log_file.borrow_mut();
+                    log.flush_scheduled = false;
+                    flush(&mut log);
+                    Continue(false)
+                });
+            }
+        }
+    }
+
+    /// Get the last messages shown in the status bar, the oldest first.
+    pub fn message_log(&self) -> &VecDeque<LogEntry> {
+        &self.model.message_log
+    }
+
+    /// Append the errors and warnings shown in the status bar to the specified file, which is
+    /// truncated to its newest lines when it exceeds `max_size` bytes.
+    /// The parent directories of the file are created if needed.
+    /// The dialog inputs are never written.
+    pub fn set_message_log_file(&mut self, path: PathBuf, max_size: u64) {
+        if max_size == 0 {
+            self.error(Error::Msg("The maximum size of the message log file must be greater than 0".to_string()));
+            return;
+        }
+        self.flush_message_log();
+        self.model.message_log_file = Some(Rc::new(RefCell::new(MessageLogFile::new(path, max_size))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::env;
+    use std::fs::{self, remove_dir_all};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{LogEntry, MAX_LOG_ENTRIES, MessageLogFile, iso_timestamp, push_entry};
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            level: "INFO".to_string(),
+            message: message.to_string(),
+            time: UNIX_EPOCH + Duration::from_secs(1_522_585_800),
+        }
+    }
+
+    #[test]
+    fn test_iso_timestamp() {
+        assert_eq!(iso_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(iso_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(entry("").iso_time_string(), "2018-04-01T12:30:00Z");
+        assert_eq!(entry("").time_string(), "12:30:00");
+    }
+
+    #[test]
+    fn test_push_entry() {
+        let mut message_log = VecDeque::new();
+        for index in 0..MAX_LOG_ENTRIES + 2 {
+            push_entry(&mut message_log, entry(&index.to_string()));
+        }
+        assert_eq!(message_log.len(), MAX_LOG_ENTRIES);
+        assert_eq!(message_log.front().map(|entry| entry.message.as_str()), Some("2"));
+        assert_eq!(message_log.back().map(|entry| entry.message.clone()), Some((MAX_LOG_ENTRIES + 1).to_string()));
+    }
+
+    #[test]
+    fn test_message_log_file() {
+        let directory = env::temp_dir().join("mg-unit-test-message-log");
+        let _ = remove_dir_all(&directory);
+        let path = directory.join("log");
+        let mut log_file = MessageLogFile::new(path.clone(), 16);
+        log_file.pending.push("first".to_string());
+        log_file.pending.push("second".to_string());
+        // The directory of the file is created.
+        log_file.flush().expect("flush");
+        assert_eq!(fs::read_to_string(&path).expect("read log"), "first\nsecond\n");
+        log_file.pending.push("third".to_string());
+        log_file.flush().expect("flush");
+        // The file is truncated to about half of its maximum size, at the beginning of a line.
+        assert_eq!(fs::read_to_string(&path).expect("read log"), "third\n");
+        remove_dir_all(directory).expect("remove directory");
+    }
+}
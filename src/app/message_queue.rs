@@ -0,0 +1,377 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+use mg_settings::errors::Error;
+use relm::timeout;
+
+use app::Mg;
+use app::color::{color_background, color_orange, color_red};
+use app::Msg::{HideColoredMessage, HideInfo};
+use app::timeout::{duration_to_milliseconds, parse_timeout_setting};
+
+/// The name of the built-in setting for the number of seconds the messages are shown, 0 meaning
+/// that they are never hidden.
+pub const MESSAGE_TIMEOUT_SETTING: &str = "message-timeout";
+/// The name of the built-in setting making the errors stay until the next key press.
+pub const STICKY_ERRORS_SETTING: &str = "sticky-errors";
+
+/// The kind of a message shown for a limited time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessageKind {
+    /// An error message, shown in red.
+    Error,
+    /// An information message.
+    Info,
+    /// A warning message, shown in orange.
+    Warning,
+}
+
+impl MessageKind {
+    /// Get the priority of the kind: the messages with a higher priority are shown first.
+    fn priority(self) -> u8 {
+        match self {
+            MessageKind::Error => 2,
+            MessageKind::Warning => 1,
+            MessageKind::Info => 0,
+        }
+    }
+}
+
+/// A message waiting to be shown in the status bar.
+pub struct QueuedMessage {
+    /// The number of identical consecutive messages coalesced in this one.
+    count: usize,
+    /// The duration of the message or None if it is not hidden automatically.
+    duration: Option<Duration>,
+    kind: MessageKind,
+    text: String,
+}
+
+impl QueuedMessage {
+    /// Create a new message shown for the duration, or until it is replaced if the duration is
+    /// None.
+    pub fn new(kind: MessageKind, text: &str, duration: Option<Duration>) -> Self {
+        QueuedMessage {
+            count: 1,
+            duration,
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    /// Check if the message has the same kind and text, so that they can be coalesced.
+    fn is_same(&self, kind: MessageKind, text: &str) -> bool {
+        self.kind == kind && self.text == text
+    }
+
+    /// Get the text shown in the status bar, with the number of occurrences.
+    pub fn to_display_string(&self) -> String {
+        if self.count > 1 {
+            format!("{} (×{})", self.text, self.count)
+        }
+        else {
+            self.text.clone()
+        }
+    }
+}
+
+/// The messages shown for a limited time, one after the other.
+pub struct MessageQueue {
+    /// The message being shown, until its timeout is over.
+    current: Option<QueuedMessage>,
+    /// The generation of the current message, to ignore the timeouts of the previous ones.
+    generation: u64,
+    pending: VecDeque<QueuedMessage>,
+}
+
+impl MessageQueue {
+    #[allow(unknown_lints, new_without_default_derive)]
+    /// Create an empty message queue.
+    pub fn new() -> Self {
+        MessageQueue {
+            current: None,
+            generation: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the number of messages waiting to be shown.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Take the next message to show.
+    pub fn pop(&mut self) -> Option<QueuedMessage> {
+        self.pending.pop_front()
+    }
+
+    /// Add a message to the queue, after the messages of the same or a higher priority: the
+    /// errors are queued before the warnings, which are queued before the information messages.
+    /// A message identical to the previous one is coalesced with it.
+    pub fn push(&mut self, message: QueuedMessage) {
+        let priority = message.kind.priority();
+        let queue = &mut self.pending;
+        let position = queue.iter()
+            .position(|queued| queued.kind.priority() < priority)
+            .unwrap_or(queue.len());
+        if position > 0 && queue[position - 1].is_same(message.kind, &message.text) {
+            queue[position - 1].count += message.count;
+        }
+        else {
+            queue.insert(position, message);
+        }
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Hide the sticky error, if any, and show the next queued message.
+    pub fn dismiss_sticky_error(&mut self) {
+        if self.model.sticky_error_shown {
+            self.model.sticky_error_shown = false;
+            self.model.message = String::new();
+            self.reset_colors();
+            if self.model.message_queue.current.is_none() {
+                self.show_next_message();
+            }
+        }
+    }
+
+    /// Show an error which stays until the next key press, even without the `sticky-errors`
+    /// setting.
+    /// The next messages are queued until then.
+    pub fn error_sticky(&mut self, error: Error) {
+        self.show_error(error, true);
+    }
+
+    /// Hide the warning message and show the next queued message.
+    /// Nothing is done if the timeout is not the one of the current message.
+    pub fn hide_colored_message(&mut self, generation: u64) {
+        if let Some(text) = self.take_current_message(generation) {
+            if self.model.message == text {
+                self.model.message = String::new();
+                self.reset_colors();
+            }
+            self.show_next_message();
+        }
+    }
+
+    /// Hide the information message and show the next queued message.
+    /// Nothing is done if the timeout is not the one of the current message.
+    pub fn hide_info(&mut self, generation: u64) {
+        if let Some(text) = self.take_current_message(generation) {
+            if self.model.message == text {
+                self.model.message = String::new();
+            }
+            self.show_next_message();
+        }
+    }
+
+    /// Show an information message to the user for the specified duration, after the queued
+    /// messages.
+    pub fn info_with_duration(&mut self, message: &str, duration: Duration) {
+        info!("{}", message);
+        self.log_message("INFO", message);
+        if self.model.silent {
+            return;
+        }
+        self.queue_message(MessageKind::Info, message, Some(duration));
+    }
+
+    /// Get the number of messages waiting to be shown.
+    pub fn pending_message_count(&self) -> usize {
+        self.model.message_queue.pending_count()
+    }
+
+    /// Show an error before the queued messages.
+    /// The message being shown is interrupted and shown again after the error, which is then
+    /// hidden after the `message-timeout` duration.
+    /// While a sticky error is shown, the error is queued before the other messages.
+    pub fn queue_error(&mut self, text: &str, sticky: bool) {
+        if self.model.sticky_error_shown {
+            let duration = self.model.message_duration;
+            self.model.message_queue.push(QueuedMessage::new(MessageKind::Error, text, duration));
+            return;
+        }
+        if let Some(current) = self.model.message_queue.current.take() {
+            self.model.message_queue.pending.push_front(current);
+        }
+        let duration =
+            if sticky || self.model.message_queue.pending.is_empty() {
+                None
+            }
+            else {
+                self.model.message_duration
+            };
+        self.show_queued_message(QueuedMessage::new(MessageKind::Error, text, duration));
+        self.model.sticky_error_shown = sticky;
+    }
+
+    /// Show a message for a limited time.
+    /// If another message is being shown, the message is queued (see `MessageQueue::push()`).
+    /// A message identical to the previous one is coalesced with it.
+    /// A message without duration is not hidden automatically, but the next message replaces it.
+    pub fn queue_message(&mut self, kind: MessageKind, text: &str, duration: Option<Duration>) {
+        // NOTE: a sticky error is not replaced, even by a message coalesced with the current one.
+        let coalesce_current = !self.model.sticky_error_shown && self.model.message_queue.pending.is_empty() &&
+            self.model.message_queue.current.as_ref().map_or(false, |current| current.is_same(kind, text));
+        if coalesce_current {
+            if let Some(mut current) = self.model.message_queue.current.take() {
+                current.count += 1;
+                // NOTE: the timeout of the previous generation is ignored, so the message is shown
+                // for the full duration again.
+                self.show_queued_message(current);
+            }
+            return;
+        }
+        self.model.message_queue.push(QueuedMessage::new(kind, text, duration));
+        if self.model.message_queue.current.is_none() {
+            self.show_next_message();
+        }
+    }
+
+    /// Show the next queued message.
+    fn show_next_message(&mut self) {
+        // NOTE: a sticky error is not replaced: the next message is shown when it is dismissed.
+        if !self.model.sticky_error_shown {
+            if let Some(message) = self.model.message_queue.pop() {
+                self.show_queued_message(message);
+            }
+        }
+    }
+
+    /// Set the duration of the next messages from the value of the `message-timeout` setting.
+    pub fn set_message_timeout(&mut self, value: &Value) {
+        match parse_timeout_setting(MESSAGE_TIMEOUT_SETTING, value) {
+            Ok(duration) => self.model.message_duration = duration,
+            Err(error) => self.error(Error::Msg(error)),
+        }
+    }
+
+    /// Remove the current message if the timeout of this generation is the one of the current
+    /// message and return its text.
+    fn take_current_message(&mut self, generation: u64) -> Option<String> {
+        if generation != self.model.message_queue.generation {
+            return None;
+        }
+        self.model.message_queue.current.take()
+            .map(|message| message.to_display_string())
+    }
+
+    /// Set whether the errors stay until the next key press from the value of the `sticky-errors`
+    /// setting.
+    pub fn set_sticky_errors(&mut self, value: &Value) {
+        match *value {
+            Value::Bool(sticky) => self.model.sticky_errors = sticky,
+            _ => self.error(Error::Msg(format!("Expecting a boolean for setting {}", STICKY_ERRORS_SETTING))),
+        }
+    }
+
+    /// Show a message in the status bar and hide it after its duration.
+    fn show_queued_message(&mut self, message: QueuedMessage) {
+        self.model.message = message.to_display_string();
+        match message.kind {
+            MessageKind::Error =>
+                match self.model.error_color {
+                    Some(ref color) => color_background(self.status_bar.widget(), color),
+                    None => color_red(self.status_bar.widget()),
+                },
+            MessageKind::Info => self.reset_colors(),
+            MessageKind::Warning => color_orange(self.status_bar.widget()),
+        }
+        self.model.message_queue.generation += 1;
+        if let Some(duration) = message.duration {
+            let generation = self.model.message_queue.generation;
+            let milliseconds = duration_to_milliseconds(duration);
+            match message.kind {
+                MessageKind::Info => timeout(self.model.relm.stream(), milliseconds, move || HideInfo(generation)),
+                MessageKind::Error | MessageKind::Warning =>
+                    timeout(self.model.relm.stream(), milliseconds, move || HideColoredMessage(generation)),
+            }
+            self.model.message_queue.current = Some(message);
+        }
+        else {
+            // NOTE: the message is not hidden, so it does not hold back the next messages.
+            self.model.message_queue.current = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{MessageKind, MessageQueue, QueuedMessage};
+
+    fn message(kind: MessageKind, text: &str) -> QueuedMessage {
+        QueuedMessage::new(kind, text, Some(Duration::from_secs(5)))
+    }
+
+    fn pop(queue: &mut MessageQueue) -> Option<(MessageKind, String)> {
+        queue.pop().map(|message| (message.kind, message.to_display_string()))
+    }
+
+    #[test]
+    fn test_message_queue_order() {
+        let mut queue = MessageQueue::new();
+        assert_eq!(queue.pending_count(), 0);
+        queue.push(message(MessageKind::Info, "first"));
+        queue.push(message(MessageKind::Info, "second"));
+        queue.push(message(MessageKind::Warning, "warning"));
+        queue.push(message(MessageKind::Error, "error"));
+        assert_eq!(queue.pending_count(), 4);
+        // The errors jump the queue, then the warnings.
+        assert_eq!(pop(&mut queue), Some((MessageKind::Error, "error".to_string())));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Warning, "warning".to_string())));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Info, "first".to_string())));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Info, "second".to_string())));
+        assert_eq!(pop(&mut queue), None);
+        assert_eq!(queue.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_message_queue_errors_in_order() {
+        let mut queue = MessageQueue::new();
+        queue.push(message(MessageKind::Info, "info"));
+        queue.push(message(MessageKind::Error, "first error"));
+        queue.push(message(MessageKind::Error, "second error"));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Error, "first error".to_string())));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Error, "second error".to_string())));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Info, "info".to_string())));
+    }
+
+    #[test]
+    fn test_message_queue_coalesce() {
+        let mut queue = MessageQueue::new();
+        queue.push(message(MessageKind::Info, "saved"));
+        queue.push(message(MessageKind::Info, "saved"));
+        queue.push(message(MessageKind::Warning, "saved"));
+        assert_eq!(queue.pending_count(), 2);
+        assert_eq!(pop(&mut queue), Some((MessageKind::Warning, "saved".to_string())));
+        assert_eq!(pop(&mut queue), Some((MessageKind::Info, "saved (×2)".to_string())));
+    }
+}
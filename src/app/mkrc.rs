@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use mg_settings::errors::Error;
+use mg_settings::key::Key;
+
+use app::Mg;
+use app::settings::value_to_config;
+use key_converter::{keys_to_string, normalize_key, string_to_keys};
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Get the lines of a config file reproducing the current mappings and settings, and the
+    /// mappings whose keys cannot be written in the config syntax.
+    fn config_lines(&self) -> (Vec<String>, Vec<String>) {
+        let mut mapping_lines = vec![];
+        let mut unwritable_mappings = vec![];
+        for (mode_name, mappings) in &self.model.mappings {
+            let prefix = self.model.modes.values()
+                .find(|mode| mode.name == *mode_name)
+                .map(|mode| mode.prefix);
+            if let Some(prefix) = prefix {
+                for (keys, mapping) in mappings {
+                    let keys_string = keys_to_string(keys);
+                    if !is_writable(keys, &keys_string) {
+                        unwritable_mappings.push(format!("{}map {} {}", prefix, keys_string, mapping.action));
+                        continue;
+                    }
+                    mapping_lines.push(format!("{}map {} {}", prefix, keys_string, mapping.to_config()));
+                }
+            }
+        }
+        mapping_lines.sort();
+        unwritable_mappings.sort();
+        let mut setting_lines: Vec<_> = self.changed_settings().iter()
+            .map(|&(ref name, ref value)| format!("set {} = {}", name, value_to_config(value)))
+            .collect();
+        setting_lines.sort();
+        mapping_lines.extend(setting_lines);
+        (mapping_lines, unwritable_mappings)
+    }
+
+    /// Get the config file text reproducing the current mappings and the settings which do not
+    /// have their default value, e.g. to show it in the UI or include it in a bug report.
+    /// The mappings whose keys cannot be written in the config syntax are listed in comments at
+    /// the start of the text.
+    pub fn dump_config(&self) -> String {
+        let (lines, unwritable_mappings) = self.config_lines();
+        let mut config = String::new();
+        for mapping in unwritable_mappings {
+            config.push_str(&format!("# Cannot write the keys of the mapping: {}\n", mapping));
+        }
+        for line in lines {
+            config.push_str(&line);
+            config.push('\n');
+        }
+        config
+    }
+
+    /// Handle the `mkrc [file]` command, which writes the current mappings and settings to the
+    /// file (by default, the config file).
+    /// The file is only overwritten when using `mkrc!`.
+    pub fn mkrc_command(&mut self, force: bool, path: &str) {
+        let path =
+            if path.is_empty() {
+                self.model.settings_filename.clone()
+            }
+            else {
+                Some(PathBuf::from(path))
+            };
+        match path {
+            Some(ref path) if path.exists() && !force =>
+                self.error(Error::Msg(format!("File {} exists (add ! to overwrite)", path.to_string_lossy()))),
+            Some(path) => {
+                let (lines, unwritable_mappings) = self.config_lines();
+                match write_config(&path, &lines) {
+                    Ok(()) => {
+                        self.info(&format!("Config written to {}", path.to_string_lossy()));
+                        for mapping in unwritable_mappings {
+                            self.warning(&format!("Cannot write the keys of the mapping: {}", mapping));
+                        }
+                    },
+                    Err(error) =>
+                        self.error(Error::Msg(format!("Cannot write the config to {}: {}", path.to_string_lossy(),
+                            error))),
+                }
+            },
+            None => self.error(Error::Msg("Argument required".to_string())),
+        }
+    }
+}
+
+/// Check if the keys are parsed back from their string representation.
+fn is_writable(keys: &[Key], keys_string: &str) -> bool {
+    let keys: Vec<_> = keys.iter().cloned().map(normalize_key).collect();
+    string_to_keys(keys_string) == Some(keys)
+}
+
+/// Write the lines of the config to the file.
+fn write_config(path: &Path, lines: &[String]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
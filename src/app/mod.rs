@@ -19,30 +19,52 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+mod alias;
 mod app_completion;
+mod builtin_command;
+mod close;
 mod color;
 mod command;
 mod config;
+mod config_watch;
 pub mod dialog;
+mod error_details;
+mod help;
+mod history;
 mod keypress;
+mod mappings_overlay;
+mod message_log;
+mod message_queue;
+mod mkrc;
+mod path;
 pub mod settings;
+mod settings_file;
+mod settings_snapshot;
+mod shell;
 mod shortcut;
 pub mod status_bar;
+mod timeout;
+mod user_command;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::char;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
 use std::path::PathBuf;
+use std::process::Output;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use gdk::{EventKey, RGBA};
+use gdk::{EventButton, EventKey, ModifierType, RGBA, keyval_to_unicode};
 use gdk::enums::key::{Escape, colon};
 use gtk;
 use gtk::{
     BoxExt,
+    Cast,
+    ContainerExt,
     GtkWindowExt,
     Inhibit,
+    IsA,
     OrientableExt,
     PackType,
     WidgetExt,
@@ -50,54 +72,111 @@ use gtk::{
 use gtk::Orientation::Vertical;
 use mg_settings::{
     self,
+    Command,
     EnumFromStr,
     EnumMetaData,
     Parser,
     SettingCompletion,
     SpecialCommand,
 };
-use mg_settings::ParseResult;
 use mg_settings::errors;
 use mg_settings::key::Key;
-use relm::{Relm, Widget, timeout};
+use relm::{Channel, Relm, Widget};
 use relm_attributes::widget;
 
-use app::config::create_default_config;
-pub use app::config::parse_config;
-use app::dialog::Responder;
-use app::settings::DefaultConfig;
-use app::shortcut::shortcut_to_string;
+use app::command::unescape_lt;
+use app::config::{
+    ParsedConfig,
+    clone_sources,
+    create_default_config,
+    parse_config_with_warnings,
+};
+pub use app::config::{ConfigSource, builtin_application_commands, parse_config};
+use app::dialog::{FnOnceBox, Responder};
+use app::settings::{
+    DefaultConfig,
+    SettingValues,
+    SettingValuesFn,
+    application_settings,
+    no_setting_values,
+    value_to_string,
+};
+pub use app::shortcut::Mapping;
+use app::shortcut::{SHORTCUT_TIMEOUT, shortcut_to_string};
 use completion::{
     self,
     CompletionView,
     DEFAULT_COMPLETER_IDENT,
+    HISTORY_COMPLETER_IDENT,
+    MESSAGES_COMPLETER_IDENT,
     NO_COMPLETER_IDENT,
+    SETTING_VALUES_COMPLETER_IDENT,
 };
+use completion::completion_view::CompletionWrap;
 use completion::completion_view::Msg::{
     AddCompleters,
     Completer,
+    CompletionAccepted,
+    CompletionActivated,
     CompletionChange,
+    CompletionsDeleted,
+    Refresh,
+    SelectionChanged,
     Visible,
 };
-use self::color::{color_blue, color_orange, color_red};
+use key_converter::is_dead_or_compose_key;
+pub use self::color::parse_color;
+pub use self::path::expand_path;
+use self::color::color_blue;
+use self::config_watch::ConfigWatch;
 use self::dialog::DialogBuilder;
+use self::help::HELP_COMMAND;
+pub use self::help::command_descriptions;
+use self::history::{HistoryFile, HistoryNavigation, HISTORY_MAX_SIZE};
+use self::keypress::EscapeChord;
+use self::message_log::{MessageLogFile, MESSAGES_COMMAND};
+pub use self::message_log::LogEntry;
+pub use self::message_queue::MessageKind;
+use self::message_queue::MessageQueue;
+use self::settings_file::SettingsFile;
 use self::status_bar::StatusBar;
 use self::status_bar::Msg::{
     EntryActivate,
     EntryChanged,
     EntryShown,
     EntryText,
+    EntryVisibility,
     Identifier,
+    ItemPriority,
+    SelectAll,
+};
+use self::status_bar::{
+    BACKGROUND_ERRORS_ITEM_PRIORITY,
+    MESSAGE_ITEM_PRIORITY,
+    MODE_ITEM_PRIORITY,
+    SHORTCUT_ITEM_PRIORITY,
+    is_composing,
 };
 use self::status_bar::ItemMsg::Text;
 use self::Msg::*;
 pub use self::status_bar::StatusBarItem;
 use super::Modes;
 
-type Mappings = HashMap<&'static str, HashMap<Vec<Key>, String>>;
+type Mappings = HashMap<&'static str, HashMap<Vec<Key>, Mapping>>;
+type MouseMappings = HashMap<&'static str, HashMap<(u32, ModifierType), String>>;
 type ModesHash = HashMap<&'static str, super::Mode>;
 type Variables = Vec<(&'static str, Box<Fn() -> String>)>;
 
+type AppCommandFn<COMM, SETT> = Rc<Fn(&mut Mg<COMM, SETT>)>;
+
+type CloseHandlerFn = Box<Fn(CloseReason) -> CloseDecision>;
+
+type HistoryFilterFn = Box<Fn(&str) -> bool>;
+
+type CommandFilterFn<COMM> = Box<Fn(FilteredCommand<COMM>, &CommandSource) -> CommandFilterResult<COMM>>;
+
+type SettingValidatorFn<SETT> = Box<Fn(&<SETT as mg_settings::settings::Settings>::Variant) -> Result<(), String>>;
+
 /// A known mode or an unknown mode.
 #[derive(Clone, Copy, PartialEq)]
 pub enum Mode {
@@ -108,6 +187,260 @@ pub enum Mode {
     Unknown,
 }
 
+/// Specify how the command entry is pre-filled when entering a special command.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PrefillMode {
+    /// The entry is empty.
+    Empty,
+    /// The entry contains the last activated text for this special command and is selected so
+    /// that typing replaces it.
+    LastText,
+}
+
+/// Where a custom command comes from.
+#[derive(Clone, Debug)]
+pub enum CommandSource {
+    /// The command is in a config file.
+    Config {
+        /// The config file, if known. It is the included file for a command of an include.
+        file: Option<PathBuf>,
+        /// The number of the line of the command in the file, if known, starting at 1.
+        line: Option<usize>,
+    },
+    /// The command was executed by a mapping.
+    Mapping {
+        /// The keys of the mapping (empty for a mouse mapping).
+        keys: Vec<Key>,
+    },
+    /// The command was executed by the application with execute_command().
+    Programmatic,
+    /// The command was received from another process (like a socket of the application) and
+    /// executed with execute_remote_command().
+    Remote,
+    /// The command was typed by the user.
+    Typed,
+}
+
+/// The decision of the close handler when the window is closed or the `quit` command is executed.
+pub enum CloseDecision {
+    /// Close the application: `AppClose` is emitted for the application to close itself.
+    Close,
+    /// Keep the application open.
+    Veto,
+    /// Ask the user to confirm with a yes/no question and close the application on yes.
+    Ask { prompt: String },
+}
+
+/// Why the application is asked to close.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CloseReason {
+    /// `Mg::request_close()` was called.
+    Api,
+    /// The window was closed by the window manager.
+    DeleteEvent,
+    /// The `quit` command was executed.
+    QuitCommand,
+}
+
+/// The decision of the command filter about a command.
+pub enum CommandFilterResult<COMM> {
+    /// Execute the command.
+    Run,
+    /// Do not execute the command.
+    Skip,
+    /// Execute this command instead.
+    Replace(Command<COMM>),
+}
+
+/// A command given to the command filter.
+pub enum FilteredCommand<'a, COMM: 'a> {
+    /// A command handled by mg itself, with its text (e.g. `set width?`, `!ls` or a command
+    /// defined with `command`).
+    Builtin(&'a str),
+    /// A command parsed with the config syntax.
+    Parsed(&'a Command<COMM>),
+}
+
+/// The parameters of the `Mg` widget, given as its model parameter in the `view!`:
+/// `Mg<AppCommand, AppSettings>(MgBuilder::new(MODES).config(ConfigSource::new("config")))`.
+pub struct MgBuilder<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// The commands registered by the application, usable like the built-in application commands,
+    /// with their description.
+    app_commands: HashMap<&'static str, (&'static str, AppCommandFn<COMM, SETT>)>,
+    /// The string settings containing a color.
+    color_settings: HashSet<String>,
+    /// The function deciding whether each command is executed, including the commands of the
+    /// config.
+    command_filter: Option<CommandFilterFn<COMM>>,
+    /// The config files, executed in order.
+    config_sources: Vec<ConfigSource>,
+    /// The directories and files created before the config is executed, if they do not exist.
+    default_config: Vec<DefaultConfig>,
+    /// Whether the custom commands of the config are sent when the application is ready.
+    defer_custom_commands: bool,
+    /// The directory where the included config files are searched.
+    include_path: Option<PathBuf>,
+    /// The color setting used for the background of the error messages.
+    error_color_setting: Option<String>,
+    /// The string settings containing a comma-separated list.
+    list_settings: HashSet<String>,
+    /// The modes of the application, in addition to the built-in ones.
+    modes: Modes,
+    /// The minimum and maximum values of the numeric settings.
+    setting_bounds: HashMap<String, (f64, f64)>,
+    /// The function checking the setting values before they are set.
+    setting_validator: Option<SettingValidatorFn<SETT>>,
+    /// The function reading the values of the application settings.
+    setting_values: SettingValuesFn<SETT>,
+}
+
+impl<COMM, SETT> MgBuilder<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Create the parameters of the `Mg` widget with the modes of the application.
+    pub fn new(modes: Modes) -> Self {
+        MgBuilder {
+            app_commands: HashMap::new(),
+            color_settings: HashSet::new(),
+            command_filter: None,
+            config_sources: vec![],
+            default_config: vec![],
+            defer_custom_commands: false,
+            error_color_setting: None,
+            include_path: None,
+            list_settings: HashSet::new(),
+            modes,
+            setting_bounds: HashMap::new(),
+            setting_validator: None,
+            setting_values: no_setting_values,
+        }
+    }
+
+    /// Register an application command, which can be used in the mappings like the built-in
+    /// application commands (e.g. `entry-end`).
+    /// The description is shown in the completions and the help.
+    /// Panics if the name is the one of a built-in application command or is registered twice.
+    pub fn app_command<F>(mut self, name: &'static str, description: &'static str, handler: F) -> Self
+        where F: Fn(&mut Mg<COMM, SETT>) + 'static,
+    {
+        assert!(!builtin_application_commands().contains(&name),
+            "Cannot register the application command {}: it is a built-in command.", name);
+        let previous = self.app_commands.insert(name, (description, Rc::new(handler)));
+        assert!(previous.is_none(), "Duplicate application command {}.", name);
+        self
+    }
+
+    /// Set the function deciding whether each command is executed, skipped or replaced.
+    /// It is also called for the commands of the config executed at startup.
+    pub fn command_filter(mut self, filter: CommandFilterFn<COMM>) -> Self {
+        self.command_filter = Some(filter);
+        self
+    }
+
+    /// Add a config file, which overrides the settings and mappings of the config files added
+    /// before.
+    pub fn config(mut self, source: ConfigSource) -> Self {
+        self.config_sources.push(source);
+        self
+    }
+
+    /// Add config files, like `ConfigSource::single()`.
+    pub fn configs(mut self, sources: Vec<ConfigSource>) -> Self {
+        self.config_sources.extend(sources);
+        self
+    }
+
+    /// Add a directory or a file to create if it does not exist, before the config is executed.
+    pub fn default_config(mut self, default_config: DefaultConfig) -> Self {
+        self.default_config.push(default_config);
+        self
+    }
+
+    /// Defer the custom commands of the config until the application calls `Mg::ready()` (or emits
+    /// `Ready`), so that it can finish its initialization before handling them.
+    /// The mappings and the settings of the config still apply immediately, and the deferred
+    /// commands are also sent on the first key press, in case the application never tells it is
+    /// ready.
+    pub fn defer_custom_commands_until_ready(mut self) -> Self {
+        self.defer_custom_commands = true;
+        self
+    }
+
+    /// Set the directory where the included config files are searched.
+    pub fn include_path<P: Into<PathBuf>>(mut self, include_path: P) -> Self {
+        self.include_path = Some(include_path.into());
+        self
+    }
+
+    /// Set a function to check the setting values before they are set, including the ones from
+    /// the config.
+    pub fn setting_validator(mut self, validator: SettingValidatorFn<SETT>) -> Self {
+        self.setting_validator = Some(validator);
+        self
+    }
+}
+
+/// The options of the `Mg` widget which need to read the values of the application settings.
+impl<COMM, SETT> MgBuilder<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + SettingValues + 'static,
+{
+    /// Specify that a string setting contains a color.
+    pub fn color_setting(mut self, name: &str) -> Self {
+        self.color_settings.insert(name.to_string());
+        self.setting_values()
+    }
+
+    /// Use the color setting for the background of the error messages.
+    pub fn error_color_setting(mut self, name: &str) -> Self {
+        self.error_color_setting = Some(name.to_string());
+        self.color_setting(name)
+    }
+
+    /// Specify that a string setting contains a comma-separated list.
+    pub fn list_setting(mut self, name: &str) -> Self {
+        self.list_settings.insert(name.to_string());
+        self.setting_values()
+    }
+
+    /// Set the minimum and maximum values of a numeric setting.
+    /// Since they are known before the config is executed, they also apply to its settings.
+    pub fn setting_bounds(mut self, name: &str, min: f64, max: f64) -> Self {
+        self.setting_bounds.insert(name.to_string(), (min, max));
+        self.setting_values()
+    }
+
+    /// Read the values of the application settings with `SettingValues`.
+    /// They are needed to show them (`set name?`, `set all`, the settings snapshot), to toggle or
+    /// adjust them, to check their bounds, to save them with the settings persistence and to
+    /// reset them when the config is reloaded.
+    pub fn setting_values(mut self) -> Self {
+        self.setting_values = SETT::get_value;
+        self
+    }
+}
+
+/// Create the parameters of the `Mg` widget from its model parameter before `MgBuilder`: the
+/// modes, the config file, the include path and the default config.
+/// This allows to keep the old parameters with `MgBuilder::from((MODES, config, None, vec![]))`.
+impl<COMM, SETT> From<(Modes, io::Result<PathBuf>, Option<PathBuf>, Vec<DefaultConfig>)> for MgBuilder<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    fn from((modes, settings_filename, include_path, default_config):
+            (Modes, io::Result<PathBuf>, Option<PathBuf>, Vec<DefaultConfig>)) -> Self
+    {
+        let mut builder = MgBuilder::new(modes).configs(ConfigSource::single(settings_filename));
+        builder.default_config = default_config;
+        builder.include_path = include_path;
+        builder
+    }
+}
+
 /// A command from a map command.
 #[derive(Debug)]
 pub enum ShortcutCommand {
@@ -115,13 +448,23 @@ pub enum ShortcutCommand {
     Complete(String),
     /// An incomplete command where the user needs to complete it and press Enter.
     Incomplete(String),
+    /// A complete command executed without showing the entry and the non-error messages.
+    Silent(String),
 }
 
 const BLOCKING_INPUT_MODE: &str = "blocking-input";
+const COMMAND_HISTORY_WINDOW: &str = "command-history-window";
 pub const COMMAND_MODE: &str = "command";
+const COMPLETE_ACCEPT_COMMAND: &str = "complete-accept";
+const COMPLETE_ACCEPT_NEXT_COMMAND: &str = "complete-accept-next";
+const COMPLETE_FIRST_COMMAND: &str = "complete-first";
+const COMPLETE_LAST_COMMAND: &str = "complete-last";
 const COMPLETE_NEXT_COMMAND: &str = "complete-next";
 const COMPLETE_PREVIOUS_COMMAND: &str = "complete-previous";
+const COMPLETION_DELETE_MARKED_COMMAND: &str = "completion-delete-marked";
+const COMPLETION_TOGGLE_MARK_COMMAND: &str = "completion-toggle-mark";
 const COPY: &str = "entry-copy";
+const COPY_MESSAGE: &str = "copy-message";
 const CUT: &str = "entry-cut";
 const ENTRY_DELETE_NEXT_CHAR: &str = "entry-delete-next-char";
 const ENTRY_DELETE_NEXT_WORD: &str = "entry-delete-next-word";
@@ -132,11 +475,16 @@ const ENTRY_NEXT_WORD: &str = "entry-next-word";
 const ENTRY_PREVIOUS_CHAR: &str = "entry-previous-char";
 const ENTRY_PREVIOUS_WORD: &str = "entry-previous-word";
 const ENTRY_SMART_HOME: &str = "entry-smart-home";
-const INFO_MESSAGE_DURATION: u32 = 5;
+const HISTORY_NEXT: &str = "history-next";
+const HISTORY_PREVIOUS: &str = "history-previous";
+const INFO_MESSAGE_DURATION: u64 = 5;
 const INPUT_MODE: &str = "input";
 const NORMAL_MODE: &str = "normal";
 const PASTE: &str = "entry-paste";
 const PASTE_SELECTION: &str = "entry-paste-selection";
+const REPEAT_LAST_ENTRY_COMMAND: &str = "repeat-last-entry-command";
+const SHOW_ERROR_DETAILS: &str = "show-error-details";
+const SHOW_MAPPINGS: &str = "show-mappings";
 
 #[derive(PartialEq)]
 pub enum ActivationType {
@@ -148,30 +496,123 @@ pub struct Model<COMM, SETT>
     where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
           SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
 {
+    aliases: HashMap<String, String>,
     answer: Option<String>,
+    app_commands: HashMap<&'static str, (&'static str, AppCommandFn<COMM, SETT>)>,
+    chained_command: Option<String>,
+    background_errors: Vec<String>,
+    background_errors_badge: String,
     choices: Vec<char>,
+    close_handler: Option<CloseHandlerFn>,
+    close_reason: Option<CloseReason>,
+    color_settings: HashSet<String>,
+    command_categories: HashMap<String, String>,
+    command_failed: bool,
+    command_filter: Option<CommandFilterFn<COMM>>,
+    command_history: Vec<String>,
+    command_source: CommandSource,
+    // NOTE: the command entry is shared with the key press signal handler to give the keys to its
+    // input method before handling them as shortcuts.
+    command_entry: Rc<RefCell<Option<gtk::Entry>>>,
+    command_text: Option<String>,
+    complete_common_prefix: bool,
+    complete_next_accepts: bool,
     completer: String,
+    // NOTE: shared with the key press signal handler.
+    completion_selected: Rc<Cell<bool>>,
     completion_shown: bool,
+    completion_wrap: CompletionWrap,
+    config_sources: Vec<ConfigSource>,
+    config_aliases: HashSet<String>,
+    config_dialog_shortcuts: HashSet<Key>,
+    config_keycode_mappings: HashSet<(&'static str, Vec<Key>)>,
+    config_mappings: HashSet<(&'static str, Vec<Key>)>,
+    config_settings: HashSet<String>,
+    config_user_commands: HashSet<String>,
+    config_watch: ConfigWatch,
     current_command_mode: char,
     current_mode: Rc<Cell<Mode>>,
     current_shortcut: Vec<Key>,
+    custom_command_id: u64,
+    custom_command_start: Option<(u64, String, Instant)>,
+    default_choice: Option<char>,
+    dialog_generation: u64,
+    dialog_timeout_pending: bool,
+    dialog_shortcuts: HashMap<Key, String>,
+    default_config_created: Vec<PathBuf>,
+    deferred_commands: Option<Vec<(CommandSource, COMM, String)>>,
     entry_shown: bool,
+    error_color: Option<RGBA>,
+    error_color_setting: Option<String>,
+    error_details: Option<(String, String)>,
+    error_details_window: Option<gtk::Window>,
     foreground_color: RGBA,
+    history_exclude: Vec<String>,
+    history_file: Option<Rc<RefCell<HistoryFile>>>,
+    history_filter: Option<HistoryFilterFn>,
+    history_max_size: usize,
+    history_navigation: Option<HistoryNavigation>,
+    identifier_prefill: HashMap<char, PrefillMode>,
+    include_path: Option<PathBuf>,
+    initial_commands_executed: bool,
     initial_errors: Vec<errors::Error>,
-    initial_parse_result: Option<ParseResult<COMM>>,
-    input_callback: Option<Box<Fn(Option<String>, bool)>>,
+    initial_parse_results: Vec<ParsedConfig<COMM>>,
+    initial_warnings: Vec<String>,
+    input_callback: Option<Box<FnOnceBox<(Option<String>, bool), ()>>>,
+    input_escape_chord: Option<EscapeChord>,
+    keycode_mappings: HashSet<(&'static str, Vec<Key>)>,
+    last_input_key: Option<(char, Instant)>,
+    last_shell_output: Option<String>,
+    last_special_commands: HashMap<char, String>,
+    list_settings: HashSet<String>,
+    local_settings: HashMap<String, HashMap<String, mg_settings::Value>>,
+    map_by_keycode: bool,
+    // NOTE: shared with the button-press-event handler.
+    mapped_buttons: Rc<RefCell<HashSet<(u32, ModifierType)>>>,
     mappings: Mappings,
+    mappings_overlay: Option<gtk::Window>,
     message: String,
+    message_duration: Option<Duration>,
+    message_log: VecDeque<LogEntry>,
+    message_log_file: Option<Rc<RefCell<MessageLogFile>>>,
+    message_queue: MessageQueue,
+    mode_before_history: Option<String>,
     mode_label: String,
     mode_string: String,
     modes: ModesHash,
+    mouse_mappings: MouseMappings,
+    password_input: bool,
+    queue_key_presses: bool,
+    queued_dialogs: VecDeque<DialogBuilder>,
+    queued_key_presses: VecDeque<EventKey>,
     relm: Relm<Mg<COMM, SETT>>,
+    report_unmapped_keys: bool,
     settings: SETT,
+    setting_bounds: HashMap<String, (f64, f64)>,
+    setting_categories: HashMap<String, String>,
+    settings_file: Option<Rc<RefCell<SettingsFile>>>,
+    settings_filename: Option<PathBuf>,
     settings_parser: Box<Parser<COMM>>,
+    setting_validator: Option<SettingValidatorFn<SETT>>,
+    setting_values: SettingValuesFn<SETT>,
     shortcuts: HashMap<Key, String>,
+    shortcut_generation: u64,
     shortcut_pressed: bool,
+    shortcut_timeout: Option<Duration>,
+    shell_channels: HashMap<u64, Channel<(String, Result<Output, String>)>>,
+    shell_command_id: u64,
+    shell_commands_enabled: bool,
     show_count: bool,
+    silent: bool,
+    slow_command_threshold: Option<Duration>,
     status_bar_command: String,
+    sticky_error_shown: bool,
+    sticky_errors: bool,
+    stop_command_chain_on_error: bool,
+    undefined_env_variables: HashSet<String>,
+    user_command_depth: usize,
+    user_commands: HashMap<String, String>,
+    user_modes: Modes,
     variables: HashMap<String, Box<Fn() -> String>>,
 }
 
@@ -182,41 +623,115 @@ pub enum Msg<COMM, SETT>
           SETT: Default + mg_settings::settings::Settings + EnumMetaData + SettingCompletion + 'static,
 {
     Alert(String),
-    AppClose,
+    Alias(String, String),
+    AppClose(CloseReason),
+    BackgroundError(errors::Error),
     BlockingCustomDialog(Box<Responder>, DialogBuilder),
     BlockingInput(Box<Responder>, String, String),
+    BlockingInputWithDefault(Box<Responder>, String, String),
+    BlockingPasswordInput(Box<Responder>, String),
     BlockingQuestion(Box<Responder>, String, Vec<char>),
     BlockingYesNoQuestion(Box<Responder>, String),
+    ButtonPress(EventButton),
+    CloseConfirmed(bool),
+    CloseHandler(CloseHandlerFn),
+    CloseRequested(CloseReason),
     CloseWin,
+    ColorSetting(&'static str),
+    CommandCategory(&'static str, &'static str),
+    CommandContext(CommandSource),
+    CommandExecuted { name: String, origin: CommandSource },
+    CommandFilter(CommandFilterFn<COMM>),
+    CommandResult(u64, Result<Option<String>, String>),
+    CompleteNextAccepts(bool),
     Completers(HashMap<&'static str, Box<completion::Completer>>),
+    CompletionItemsDeleted(Vec<String>),
+    CompletionSelectionChanged(bool),
+    CompletionViewAccept(String),
+    CompletionViewActivate(String),
     CompletionViewChange(String),
+    CompletionViewDeleted(String, Vec<String>),
+    ConfigFileChanged,
+    Confirm(Box<Responder>, String, bool),
+    ConfirmWithTimeout(Box<Responder>, String, bool, Duration),
     CustomCommand(COMM),
+    CustomCommandId(u64),
     CustomDialog(DialogBuilder),
     DarkTheme(bool),
+    DefaultConfigCreated(Vec<PathBuf>),
     DeleteCompletionItem,
+    DialogAnswer(String, Option<String>),
+    DialogShortcut(Key, String),
+    DialogTimeout(u64, String),
     EnterCommandMode,
     EnterNormalMode,
     EnterNormalModeAndReset,
     Error(errors::Error),
-    HideColoredMessage(String),
-    HideInfo(String),
+    ErrorColorSetting(&'static str),
+    ErrorSticky(errors::Error),
+    ExecuteCommand(String),
+    ExecuteRemoteCommand(String),
+    FlushMessageLog,
+    FlushKeyPresses,
+    HideColoredMessage(u64),
+    HideInfo(u64),
+    HistoryFilter(HistoryFilterFn),
+    HistoryPersistence(PathBuf),
+    IdentifierPrefill(char, PrefillMode),
+    IdentifierVisible(bool),
     Info(String),
+    InfoWithDuration(String, Duration),
     InitAfter,
+    InitialCommandsExecuted,
+    InputEscapeChord(Vec<Key>, Duration, bool),
+    InputMethodKeyPress,
     Input(Box<Responder>, String, String),
+    InputWithCompletion(Box<Responder>, String, String),
+    InputWithDefault(Box<Responder>, String, String),
     KeyPress(EventKey),
+    LabeledQuestion(Box<Responder>, String, Vec<(char, String)>),
+    ListSetting(&'static str),
+    ListSettingChanged(String, Vec<String>),
+    LocalSettingChanged(String, SETT::Variant),
     KeyRelease(EventKey),
+    MapMouseButton(&'static str, u32, ModifierType, String),
     Message(String),
     ModeChanged(String),
+    PasswordInput(Box<Responder>, String),
     Question(Box<Responder>, String, &'static [char]),
+    QuestionAsync(String, String, Vec<char>),
+    Ready,
+    RefreshCompletions,
+    RemoveDialogShortcut(Key),
     ResetInput,
+    SetMessageLogFile(PathBuf, u64),
     SetMode(&'static str),
     SetSetting(SETT::Variant),
+    SettingBounds(&'static str, f64, f64),
+    SettingCategory(&'static str, &'static str),
     SettingChanged(SETT::Variant),
+    SettingTransition(Option<SETT::Variant>, SETT::Variant),
+    SettingValidator(SettingValidatorFn<SETT>),
+    SettingsPersistence(PathBuf),
+    ShowBackgroundErrors,
+    ShellCommandCompleted { status: Option<i32>, stdout: String, stderr: String },
+    ShellCommandFinished(u64, String, Result<Output, String>),
+    ShellCommands(bool),
+    ShortcutTimeout(u64),
+    ShowHelp(String),
+    ShowHistory,
+    ShowMessages,
+    ShowSettingValues,
+    SlowCommandThreshold(Option<Duration>),
     StatusBarEntryActivate(Option<String>),
     StatusBarEntryChanged(Option<String>),
+    StatusBarItemPriority(gtk::Label, i32),
+    StopCommandChainOnError(bool),
     Title(String),
+    UserCommand(String, String),
     Variables(Variables),
     Warning(String),
+    WatchConfig(bool),
     YesNoQuestion(Box<Responder>, String),
 }
 
@@ -230,77 +745,154 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
     fn after_children_added(&mut self) {
         // NOTE: This code is not in init_view() because the SettingChanged signal would be sent
         // before the user's code connected to this event.
-        let parse_result = self.model.initial_parse_result.take().expect("initial parse result");
-        self.execute_commands(parse_result, false);
+        let parse_results: Vec<_> = self.model.initial_parse_results.drain(..).collect();
+        // NOTE: complete the bounds, the colors and the list elements given by the builder.
+        self.update_setting_completer();
+        // NOTE: complete the application commands given by the builder.
+        if !self.model.app_commands.is_empty() {
+            self.update_command_completer();
+            self.update_help_completer();
+        }
+        for parsed_config in parse_results {
+            // NOTE: the errors of the config files are shown with the initial errors since they
+            // are not parse errors anymore once they are prefixed by the file name.
+            self.model.initial_errors.extend(parsed_config.errors);
+            for (alias, expansion) in parsed_config.aliases {
+                self.add_config_alias(&alias, &expansion);
+            }
+            for (name, expansion) in parsed_config.user_commands {
+                self.add_config_user_command(&name, &expansion);
+            }
+            for (key, answer) in parsed_config.dialog_shortcuts {
+                self.add_config_dialog_shortcut(key, &answer);
+            }
+            for (mode_prefix, keys) in parsed_config.keycode_mappings {
+                self.add_config_keycode_mapping(&mode_prefix, keys);
+            }
+            let errors = self.execute_config_commands(parsed_config.commands);
+            self.model.initial_errors.extend(errors);
+        }
+        self.model.command_source = CommandSource::Typed;
+        if !self.model.default_config_created.is_empty() {
+            let created = self.model.default_config_created.drain(..).collect();
+            self.model.relm.stream().emit(DefaultConfigCreated(created));
+        }
+        let warnings: Vec<_> = self.model.initial_warnings.drain(..).collect();
+        for warning in warnings {
+            self.warning(&warning);
+        }
         let errors: Vec<_> = self.model.initial_errors.drain(..).collect();
         for error in errors {
             self.error(error);
         }
+        self.model.initial_commands_executed = true;
+        self.model.relm.stream().emit(InitialCommandsExecuted);
     }
 
     /// Show an alert message to the user.
     fn alert(&mut self, message: &str) {
+        self.log_message("ALERT", message);
         self.model.message = message.to_string();
         color_blue(self.status_bar.widget());
     }
 
+    /// Show an error caused by a background activity (and not by the command the user just ran).
+    /// While the user is typing in the entry or answering a dialog, the error is only counted in
+    /// the status bar and logged: it is shown when the user returns to the normal mode.
+    pub fn background_error(&mut self, error: errors::Error) {
+        if self.model.entry_shown || !self.model.choices.is_empty() {
+            let message = error.to_string();
+            error!("{}", message);
+            self.log_message("ERROR", &message);
+            self.model.background_errors.push(message);
+            self.model.background_errors_badge = format!("{}⚠", self.model.background_errors.len());
+        }
+        else {
+            self.error(error);
+        }
+    }
+
     /// Show an error to the user.
+    /// With the `sticky-errors` setting, the error stays until the next key press.
     fn error(&mut self, error: errors::Error) {
+        let sticky = self.model.sticky_errors;
+        self.show_error(error, sticky);
+    }
+
+    /// Show an error to the user before the queued messages.
+    /// A sticky error stays until the next key press.
+    fn show_error(&mut self, error: errors::Error, sticky: bool) {
+        self.model.command_failed = true;
         let mut message = String::new();
         let error_str = error.to_string();
         message.push_str(&error_str);
         error!("{}", message);
-
-        self.model.message = error_str;
+        self.log_message("ERROR", &message);
+        self.model.error_details = Some((error_str.clone(), message));
         self.model.entry_shown = false;
-        color_red(self.status_bar.widget());
-    }
-
-    /// Hide the information message.
-    fn hide_colored_message(&mut self, message: &str) {
-        if self.model.message == message {
-            self.model.message = String::new();
-            self.reset_colors();
-        }
+        self.queue_error(&error_str, sticky);
     }
 
-    /// Hide the information message.
-    fn hide_info(&mut self, message: &str) {
-        if self.model.message == message {
-            self.model.message = String::new();
+    /// Show the background errors that happened while the user was typing.
+    fn show_background_errors(&mut self) {
+        if let Some(last_error) = self.model.background_errors.pop() {
+            let message =
+                if self.model.background_errors.is_empty() {
+                    last_error
+                }
+                else {
+                    format!("{} (and {} other background errors)", last_error, self.model.background_errors.len())
+                };
+            self.model.background_errors.clear();
+            self.model.background_errors_badge = String::new();
+            self.model.entry_shown = false;
+            let sticky = self.model.sticky_errors;
+            self.queue_error(&message, sticky);
         }
     }
 
-    /// Show an information message to the user for 5 seconds.
+    /// Show an information message to the user for the duration of the `message-timeout`
+    /// setting, after the queued messages.
     fn info(&mut self, message: &str) {
         info!("{}", message);
-        let message = message.to_string();
-        self.model.message = message.clone();
-        self.reset_colors();
-
-        timeout(self.model.relm.stream(), INFO_MESSAGE_DURATION, move || HideInfo(message.clone()));
+        self.log_message("INFO", message);
+        if self.model.silent {
+            return;
+        }
+        let duration = self.model.message_duration;
+        self.queue_message(MessageKind::Info, message, duration);
     }
 
     /// Show a message to the user.
     fn message(&mut self, message: &str) {
+        self.log_message("MESSAGE", message);
+        if self.model.silent {
+            return;
+        }
         self.reset_colors();
         self.model.message = message.to_string();
     }
 
-    /// Show a warning message to the user for 5 seconds.
+    /// Show a warning message to the user for the duration of the `message-timeout` setting,
+    /// before the queued information messages.
     fn warning(&mut self, message: &str) {
         warn!("{}", message);
-        let message = message.to_string();
-        self.model.message = message.clone();
-        color_orange(self.status_bar.widget());
-
-        timeout(self.model.relm.stream(), INFO_MESSAGE_DURATION, move || HideColoredMessage(message.clone()));
+        self.log_message("WARNING", message);
+        if self.model.silent {
+            return;
+        }
+        let duration = self.model.message_duration;
+        self.queue_message(MessageKind::Warning, message, duration);
     }
 
     /// Hide the command entry and the completion view.
     fn hide_entry_and_completion(&mut self) {
         self.model.completion_shown = false;
         self.model.entry_shown = false;
+        if self.model.password_input {
+            self.model.password_input = false;
+            self.status_bar.emit(EntryVisibility(true));
+        }
     }
 
     /// Check if the key should be inhibitted for a normal mode.
@@ -309,8 +901,8 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
         match key.get_keyval() {
             colon | Escape => Inhibit(true),
             keyval => {
-                let character = keyval as u8 as char;
-                if COMM::is_identifier(character) {
+                let is_identifier = keyval_to_unicode(keyval).map(COMM::is_identifier).unwrap_or(false);
+                if is_identifier {
                     Inhibit(true)
                 }
                 else {
@@ -321,66 +913,188 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
     }
 
     fn init_view(&mut self) {
+        let priorities = [
+            (&self.message, MESSAGE_ITEM_PRIORITY),
+            (&self.mode, MODE_ITEM_PRIORITY),
+            (&self.background_errors, BACKGROUND_ERRORS_ITEM_PRIORITY),
+            (&self.shortcut, SHORTCUT_ITEM_PRIORITY),
+        ];
+        for &(item, priority) in &priorities {
+            self.status_bar.emit(ItemPriority(item.widget().clone(), priority));
+        }
+        *self.model.command_entry.borrow_mut() = self.status_bar.widget().get_children().into_iter()
+            .filter_map(|child| child.downcast::<gtk::Entry>().ok())
+            .next();
         self.model.foreground_color = self.get_foreground_color();
         self.model.relm.stream().emit(InitAfter);
     }
 
     /// Input the specified command.
-    fn input_command(&mut self, mut command: String) {
+    /// The environment variables are expanded before the `<variable>` placeholders, so that a `$`
+    /// in the value of a placeholder (like an URL) is kept as is.
+    fn input_command(&mut self, command: String) {
         self.set_mode(COMMAND_MODE);
         self.show_entry();
+        let mut command = self.expand_env(&command);
         for (variable, function) in &self.model.variables {
             command = command.replace(&format!("<{}>", variable), &function());
         }
+        let mut command = unescape_lt(&command);
         if !command.contains(' ') {
             command.push(' ');
         }
         self.model.status_bar_command = command;
     }
 
-    fn model(relm: &Relm<Self>, (user_modes, settings_filename, include_path, default_config):
-             (Modes, io::Result<PathBuf>, Option<PathBuf>, Vec<DefaultConfig>)) -> Model<COMM, SETT>
-    {
+    fn model(relm: &Relm<Self>, builder: MgBuilder<COMM, SETT>) -> Model<COMM, SETT> {
+        let MgBuilder {
+            app_commands,
+            color_settings,
+            command_filter,
+            config_sources,
+            default_config,
+            defer_custom_commands,
+            error_color_setting,
+            include_path,
+            list_settings,
+            modes: user_modes,
+            setting_bounds,
+            setting_validator,
+            setting_values,
+        } = builder;
+        let app_command_names: Vec<_> = app_commands.keys().cloned().collect();
         let mut initial_errors = vec![];
-        if let Err(error) = create_default_config(default_config) {
-            initial_errors.push(error.into());
-        }
-        let (settings_parser, initial_parse_result, modes) =
-            match settings_filename {
-                Ok(settings_filename) => {
-                    let (parser, parse_result, modes) = parse_config(settings_filename, user_modes, include_path);
-                    (Box::new(parser), Some(parse_result), modes)
-                },
-                Err(error) => {
-                    initial_errors.push(error.into());
-                    (Box::new(Parser::<COMM>::new()), None, HashMap::new())
-                },
-            };
+        let (default_config_created, errors) = create_default_config(default_config);
+        initial_errors.extend(errors.into_iter().map(Into::into));
+        // NOTE: the last config file has the highest precedence, so it is the user config where
+        // the settings are written.
+        let settings_filename = config_sources.iter().rev()
+            .filter_map(|source| source.path.as_ref().ok())
+            .next()
+            .cloned();
+        let reload_sources = clone_sources(&config_sources);
+        let (settings_parser, initial_parse_results, modes, initial_warnings) =
+            parse_config_with_warnings(config_sources, user_modes, include_path.clone(), &app_command_names);
         Model {
+            aliases: HashMap::new(),
             answer: None,
+            app_commands,
+            chained_command: None,
+            background_errors: vec![],
+            background_errors_badge: String::new(),
             choices: vec![],
+            close_handler: None,
+            close_reason: None,
+            color_settings,
+            command_categories: HashMap::new(),
+            command_failed: false,
+            command_filter,
+            command_history: vec![],
+            command_source: CommandSource::Typed,
+            command_entry: Rc::new(RefCell::new(None)),
+            command_text: None,
+            complete_common_prefix: false,
+            complete_next_accepts: false,
             completer: DEFAULT_COMPLETER_IDENT.to_string(),
+            completion_selected: Rc::new(Cell::new(false)),
             completion_shown: false,
+            completion_wrap: CompletionWrap::Unselect,
+            config_sources: reload_sources,
+            config_aliases: HashSet::new(),
+            config_dialog_shortcuts: HashSet::new(),
+            config_keycode_mappings: HashSet::new(),
+            config_mappings: HashSet::new(),
+            config_settings: HashSet::new(),
+            config_user_commands: HashSet::new(),
+            config_watch: ConfigWatch::default(),
             current_command_mode: ':',
             current_mode: Rc::new(Cell::new(Mode::Normal)),
             current_shortcut: vec![],
+            custom_command_id: 0,
+            custom_command_start: None,
+            default_choice: None,
+            dialog_generation: 0,
+            dialog_timeout_pending: false,
+            dialog_shortcuts: HashMap::new(),
+            default_config_created,
+            deferred_commands:
+                if defer_custom_commands {
+                    Some(vec![])
+                }
+                else {
+                    None
+                },
             entry_shown: false,
+            error_color: None,
+            error_color_setting,
+            error_details: None,
+            error_details_window: None,
             foreground_color: RGBA::white(),
+            history_exclude: vec![],
+            history_file: None,
+            history_filter: None,
+            history_max_size: HISTORY_MAX_SIZE,
+            history_navigation: None,
+            identifier_prefill: HashMap::new(),
+            include_path,
+            initial_commands_executed: false,
             initial_errors,
-            initial_parse_result,
+            initial_parse_results,
+            initial_warnings,
             input_callback: None,
+            input_escape_chord: None,
+            keycode_mappings: HashSet::new(),
+            last_input_key: None,
+            last_shell_output: None,
+            last_special_commands: HashMap::new(),
+            list_settings,
+            local_settings: HashMap::new(),
+            map_by_keycode: false,
+            mapped_buttons: Rc::new(RefCell::new(HashSet::new())),
             mappings: HashMap::new(),
+            mappings_overlay: None,
             message: String::new(),
+            message_duration: Some(Duration::from_secs(INFO_MESSAGE_DURATION)),
+            message_log: VecDeque::new(),
+            message_log_file: None,
+            message_queue: MessageQueue::new(),
+            mode_before_history: None,
             mode_label: String::new(),
             mode_string: NORMAL_MODE.to_string(),
             modes,
+            mouse_mappings: HashMap::new(),
+            password_input: false,
+            queue_key_presses: false,
+            queued_dialogs: VecDeque::new(),
+            queued_key_presses: VecDeque::new(),
             relm: relm.clone(),
+            report_unmapped_keys: false,
             settings: SETT::default(),
-            settings_parser,
+            setting_bounds,
+            setting_categories: HashMap::new(),
+            settings_file: None,
+            settings_filename,
+            settings_parser: Box::new(settings_parser),
+            setting_validator,
+            setting_values,
             shortcuts: HashMap::new(),
+            shortcut_generation: 0,
             shortcut_pressed: false,
+            shortcut_timeout: Some(Duration::from_secs(SHORTCUT_TIMEOUT)),
+            shell_channels: HashMap::new(),
+            shell_command_id: 0,
+            shell_commands_enabled: false,
             show_count: true,
+            silent: false,
+            slow_command_threshold: None,
             status_bar_command: String::new(),
+            sticky_error_shown: false,
+            sticky_errors: false,
+            stop_command_chain_on_error: true,
+            undefined_env_variables: HashSet::new(),
+            user_command_depth: 0,
+            user_commands: HashMap::new(),
+            user_modes,
             variables: HashMap::new(),
         }
     }
@@ -395,15 +1109,23 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
                 self.clear_shortcut();
                 self.handle_shortcut(key)
             },
+            // NOTE: the dead keys are left to the input method composing the character.
+            keyval if is_dead_or_compose_key(keyval) => None,
             keyval => {
-                let character = keyval as u8 as char;
-                if COMM::is_identifier(character) {
+                let identifier = keyval_to_unicode(keyval).filter(|&character| COMM::is_identifier(character));
+                if let Some(character) = identifier {
                     self.model.completer = NO_COMPLETER_IDENT.to_string();
                     self.set_current_identifier(character);
                     self.set_mode(COMMAND_MODE);
                     self.reset();
                     self.clear_shortcut();
                     self.show_entry();
+                    if self.model.identifier_prefill.get(&character) == Some(&PrefillMode::LastText) {
+                        if let Some(text) = self.model.last_special_commands.get(&character).cloned() {
+                            self.model.status_bar_command = text;
+                            self.status_bar.emit(SelectAll);
+                        }
+                    }
                     None
                 }
                 else {
@@ -419,6 +1141,8 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
         self.hide_entry_and_completion();
         self.model.message = String::new();
         self.clear_shortcut();
+        self.dismiss_sticky_error();
+        self.model.history_navigation = None;
     }
 
     /// Reset the input after closing a input dialog.
@@ -430,8 +1154,14 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
 
     fn return_to_normal_mode(&mut self) {
         self.hide_entry_and_completion();
+        // NOTE: an input dialog can have its own completer.
+        self.set_completer(DEFAULT_COMPLETER_IDENT);
         self.set_mode(NORMAL_MODE);
         self.set_current_identifier(':');
+        if !self.model.background_errors.is_empty() {
+            // NOTE: emit a message so that the errors are shown after the status bar is reset.
+            self.model.relm.stream().emit(ShowBackgroundErrors);
+        }
     }
 
     /// Set the current (special) command identifier.
@@ -446,6 +1176,7 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
     /// Set the current mode.
     fn set_mode(&mut self, mode: &str) {
         self.model.mode_string = mode.to_string();
+        self.update_mapped_buttons();
         let current_mode =
             match mode {
                 BLOCKING_INPUT_MODE => Mode::BlockingInput,
@@ -462,12 +1193,17 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
         }
         self.model.current_mode.set(current_mode);
         self.model.relm.stream().emit(ModeChanged(mode.to_string()));
+        if current_mode == Mode::Normal || current_mode == Mode::Command {
+            // NOTE: always show the identifier in these modes so that the : prompt is never blank.
+            self.set_identifier_visible(true);
+        }
 
         for mode in self.model.modes.values() {
             if mode.name == self.model.mode_string {
                 self.model.show_count = mode.show_count;
             }
         }
+        self.update_mappings_overlay();
     }
 
     fn show_entry(&mut self) {
@@ -478,21 +1214,72 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
         match event {
             Alert(msg) => self.alert(&msg),
             // To be listened to by the user.
-            AppClose => (),
+            Alias(alias, expansion) => self.add_alias(&alias, &expansion),
+            // NOTE: the application closes itself when it receives this message.
+            AppClose(_) => {
+                self.cancel_blocking_dialog();
+                self.flush_message_log();
+            },
+            BackgroundError(error) => self.background_error(error),
             BlockingCustomDialog(responder, builder) =>
                 self.blocking_custom_dialog(responder, builder),
             BlockingInput(responder, question, default_answer) =>
                 self.blocking_input(responder, question, default_answer),
+            BlockingInputWithDefault(responder, question, default_answer) =>
+                self.blocking_input_with_default(responder, question, default_answer),
+            BlockingPasswordInput(responder, question) => self.blocking_password_input(responder, question),
             BlockingQuestion(responder, question, choices) => self.blocking_question(responder, question, choices),
             BlockingYesNoQuestion(responder, question) => self.blocking_yes_no_question(responder, question),
+            ButtonPress(event) => {
+                if let Some(msg) = self.handle_button_press(&event) {
+                    self.model.relm.stream().emit(msg);
+                }
+            },
+            CloseConfirmed(confirmed) => self.close_confirmed(confirmed),
+            CloseHandler(handler) => self.set_close_handler(handler),
+            CloseRequested(reason) => self.handle_close_request(reason),
             CloseWin => self.window.destroy(),
+            ColorSetting(name) => self.set_color_setting(name),
+            CommandCategory(name, category) => self.set_command_category(name, category),
+            // To be listened to by the user.
+            CommandContext(_) => (),
+            // To be listened to by the user.
+            CommandExecuted { .. } => (),
+            CommandFilter(filter) => self.set_command_filter(filter),
+            CommandResult(id, result) => self.command_result(id, result),
+            CompleteNextAccepts(accepts) => self.model.complete_next_accepts = accepts,
             Completers(completers) => self.completion_view.emit(AddCompleters(completers)),
+            // To be listened to by the user.
+            CompletionItemsDeleted(_) => (),
+            CompletionSelectionChanged(selected) => self.model.completion_selected.set(selected),
+            CompletionViewAccept(completion) => {
+                self.set_input(&completion);
+                self.update_completions();
+            },
+            CompletionViewActivate(completion) => {
+                self.set_input(&completion);
+                self.command_activate(Some(completion));
+            },
             CompletionViewChange(completion) => self.set_input(&completion),
+            CompletionViewDeleted(completer, items) => {
+                if completer == HISTORY_COMPLETER_IDENT {
+                    self.delete_history_items(&items);
+                }
+                self.model.relm.stream().emit(CompletionItemsDeleted(items));
+            },
+            ConfigFileChanged => self.reload_config(),
+            Confirm(responder, question, default) => self.confirm(responder, question, default),
+            ConfirmWithTimeout(responder, question, default, timeout) =>
+                self.confirm_with_timeout(responder, question, default, timeout),
             // To be listened to by the user.
-            CustomCommand(_) => (),
+            CustomCommand(_) | CustomCommandId(_) => (),
             CustomDialog(builder) => self.show_dialog(builder),
             DarkTheme(dark) => self.set_dark_theme(dark),
             DeleteCompletionItem => self.delete_current_completion_item(),
+            // To be listened to by the user.
+            DialogAnswer(_, _) => (),
+            DialogShortcut(key, answer) => self.add_dialog_shortcut(key, &answer),
+            DialogTimeout(generation, answer) => self.dialog_timeout(generation, &answer),
             EnterCommandMode => {
                 self.set_completer(DEFAULT_COMPLETER_IDENT);
                 self.set_current_identifier(':');
@@ -509,32 +1296,128 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
                 self.return_to_normal_mode();
                 self.reset();
                 self.clear_shortcut();
+                self.restore_mode_before_history();
+                self.show_queued_dialog();
             },
             Info(msg) => self.info(&msg),
+            InfoWithDuration(msg, duration) => self.info_with_duration(&msg, duration),
             InitAfter => self.after_children_added(),
+            ExecuteCommand(command) => self.execute_command(&command),
+            ExecuteRemoteCommand(command) => self.execute_remote_command(&command),
+            // To be listened to by the user.
+            InitialCommandsExecuted => (),
             Input(responder, input, default_answer) => self.input(responder, input, default_answer),
+            InputWithCompletion(responder, input, completer) =>
+                self.input_with_completion(responder, input, &completer),
+            InputWithDefault(responder, input, default_answer) =>
+                self.input_with_default(responder, input, default_answer),
+            LabeledQuestion(responder, question, choices) => self.labeled_question(responder, question, choices),
+            InputEscapeChord(keys, window, keep_text) => self.set_input_escape_chord(&keys, window, keep_text),
+            // NOTE: the key was used by the input method.
+            InputMethodKeyPress => (),
             Message(msg) => self.message(&msg),
-            KeyPress(key) => self.key_press(&key),
+            KeyPress(key) => {
+                // NOTE: Flush the deferred commands on the first user interaction in case the
+                // application never tells it is ready.
+                self.ready();
+                // NOTE: the key press still executes its mapping after closing the overlay.
+                self.hide_mappings_overlay();
+                if !self.hide_error_details_on_escape(&key) {
+                    self.key_press(&key)
+                }
+            },
             KeyRelease(key) => self.key_release(&key),
+            ListSetting(name) => self.set_list_setting(name),
+            // To be listened to by the user.
+            ListSettingChanged(_, _) => (),
+            MapMouseButton(mode, button, modifiers, action) => self.map_mouse_button(mode, button, modifiers, action),
             Error(error) => self.error(error),
-            HideColoredMessage(message) => self.hide_colored_message(&message),
-            HideInfo(message) => self.hide_info(&message),
+            ErrorColorSetting(name) => self.set_error_color_setting(name),
+            ErrorSticky(error) => self.error_sticky(error),
+            FlushKeyPresses => self.flush_key_presses(),
+            FlushMessageLog => self.flush_message_log(),
+            HideColoredMessage(generation) => self.hide_colored_message(generation),
+            HideInfo(generation) => self.hide_info(generation),
+            HistoryFilter(filter) => self.set_history_filter(filter),
+            HistoryPersistence(path) => self.set_history_persistence(path),
+            IdentifierPrefill(identifier, mode) => self.set_identifier_prefill(identifier, mode),
+            IdentifierVisible(visible) => self.set_identifier_visible(visible),
             // To be listened by the user.
-            ModeChanged(_) | SettingChanged(_) => (),
+            DefaultConfigCreated(_) | LocalSettingChanged(_, _) | ModeChanged(_) | SettingChanged(_) |
+                SettingTransition(_, _) => (),
+            PasswordInput(responder, question) => self.password_input(responder, question),
             Question(responder, question, choices) => self.question(responder, question, choices),
-            ResetInput => self.reset_input(),
+            QuestionAsync(id, question, choices) => self.question_async(&id, question, &choices),
+            Ready => self.ready(),
+            RefreshCompletions => self.completion_view.emit(Refresh),
+            RemoveDialogShortcut(key) => self.remove_dialog_shortcut(&key),
+            ResetInput => {
+                self.reset_input();
+                self.show_queued_dialog();
+            },
+            SetMessageLogFile(path, max_size) => self.set_message_log_file(path, max_size),
             SetMode(mode) => self.set_mode(mode),
             SetSetting(setting) => self.set_setting(setting),
+            SettingBounds(name, min, max) => self.set_setting_bounds(name, min, max),
+            SettingCategory(name, category) => self.set_setting_category(name, category),
+            SettingValidator(validator) => self.set_setting_validator(validator),
+            SettingsPersistence(path) => self.set_settings_persistence(path),
+            ShowBackgroundErrors => self.show_background_errors(),
+            // To be listened to by the user.
+            ShellCommandCompleted { .. } => (),
+            ShellCommandFinished(id, command, output) => self.shell_command_finished(id, &command, output),
+            ShellCommands(enabled) => self.set_shell_commands_enabled(enabled),
+            ShortcutTimeout(generation) => self.shortcut_timeout(generation),
+            ShowHelp(category) => {
+                self.set_completer(HELP_COMMAND);
+                self.set_current_identifier(':');
+                self.set_mode(COMMAND_MODE);
+                self.model.completion_shown = true;
+                self.show_entry();
+                self.model.status_bar_command = format!("{} {}", HELP_COMMAND, category);
+            },
+            ShowHistory => {
+                self.set_completer(HISTORY_COMPLETER_IDENT);
+                self.set_current_identifier(':');
+                self.set_mode(COMMAND_MODE);
+                self.model.completion_shown = true;
+                self.show_entry();
+                self.model.status_bar_command = String::new();
+            },
+            ShowMessages => {
+                self.set_completer(MESSAGES_COMPLETER_IDENT);
+                self.set_current_identifier(':');
+                self.set_mode(COMMAND_MODE);
+                self.model.completion_shown = true;
+                self.show_entry();
+                self.model.status_bar_command = format!("{} ", MESSAGES_COMMAND);
+            },
+            ShowSettingValues => {
+                self.set_completer(SETTING_VALUES_COMPLETER_IDENT);
+                self.set_current_identifier(':');
+                self.set_mode(COMMAND_MODE);
+                self.model.completion_shown = true;
+                self.show_entry();
+                self.model.status_bar_command = "set ".to_string();
+            },
+            SlowCommandThreshold(threshold) => self.set_slow_command_threshold(threshold),
             StatusBarEntryActivate(input) => self.command_activate(input),
             StatusBarEntryChanged(input) => {
                 // NOTE: Lock to prevent moving the cursor of the command entry.
                 let _lock = self.status_bar.stream().lock();
                 self.model.status_bar_command = input.unwrap_or_default();
-                self.update_completions()
+                // NOTE: the completions are not updated with a half-composed text.
+                if !is_composing() {
+                    self.update_completions();
+                }
             },
+            StatusBarItemPriority(label, priority) => self.status_bar.emit(ItemPriority(label, priority)),
+            StopCommandChainOnError(stop) => self.set_stop_command_chain_on_error(stop),
             Title(title) => self.set_title(&title),
+            UserCommand(name, expansion) => self.add_user_command(&name, &expansion),
             Variables(variables) => self.set_variables(variables),
             Warning(message) => self.warning(&message),
+            WatchConfig(watch) => self.watch_config(watch),
             YesNoQuestion(responder, question) => self.yes_no_question(responder, question),
         }
     }
@@ -549,6 +1432,7 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
         #[name="window"]
         gtk::Window {
             #[container]
+            #[name="container"]
             gtk::Box {
                 orientation: Vertical,
                 #[container="status-bar-item"]
@@ -574,6 +1458,10 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
                             pack_type: PackType::Start,
                         },
                     },
+                    #[name="background_errors"]
+                    StatusBarItem {
+                        Text: self.model.background_errors_badge.clone(),
+                    },
                     #[name="shortcut"]
                     StatusBarItem {
                         Text: shortcut_to_string(&self.model.current_shortcut, self.model.show_count),
@@ -589,14 +1477,24 @@ impl<COMM, SETT> Widget for Mg<COMM, SETT>
                     CompletionView(Self::default_completers()) {
                         Completer: self.model.completer.clone(),
                         Visible: self.model.completion_shown,
+                        CompletionAccepted(ref completion) => CompletionViewAccept(completion.clone()),
+                        CompletionActivated(ref completion) => CompletionViewActivate(completion.clone()),
                         CompletionChange(ref completion) => CompletionViewChange(completion.clone()),
+                        CompletionsDeleted(ref completer, ref items) =>
+                            CompletionViewDeleted(completer.clone(), items.clone()),
+                        SelectionChanged(selected) => CompletionSelectionChanged(selected),
                     },
                 },
             },
-            key_press_event(_, key) with(current_mode) =>
-                (KeyPress(key.clone()), Self::inhibit_key_press(&current_mode, key)),
+            key_press_event(_, key) with(current_mode, command_entry, completion_selected) =>
+                (Self::key_press_msg(&current_mode, &command_entry, &completion_selected, key),
+                 Self::inhibit_key_press(&current_mode, &completion_selected, key)),
             key_release_event(_, key) => (KeyRelease(key.clone()), Inhibit(false)),
-            delete_event(_, _) => (AppClose, Inhibit(true)),
+            // NOTE: the window only receives the button events not handled by the child widgets.
+            // The mapped buttons are not propagated further.
+            button_press_event(_, event) with(mapped_buttons) =>
+                (ButtonPress(event.clone()), Self::inhibit_button_press(&mapped_buttons, event)),
+            delete_event(_, _) => (CloseRequested(CloseReason::DeleteEvent), Inhibit(true)),
         },
     }
 }
@@ -605,14 +1503,62 @@ impl<COMM, SETT> Mg<COMM, SETT>
     where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
           SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
 {
+    /// Get the box containing the central widget and the status bar.
+    /// Widgets can be added to it to place them between the central widget and the status bar.
+    pub fn container(&self) -> &gtk::Box {
+        &self.container
+    }
+
     fn is_normal_command(&self) -> bool {
         self.model.current_command_mode == ':'
     }
 
+    /// Tell that the application is ready to receive the custom commands.
+    /// The custom commands from the config file that were deferred are sent in order.
+    pub fn ready(&mut self) {
+        if let Some(commands) = self.model.deferred_commands.take() {
+            for (source, command, name) in commands {
+                self.send_custom_command(source.clone(), command, &name);
+                self.model.relm.stream().emit(CommandExecuted { name, origin: source });
+            }
+        }
+    }
+
     /// Set a setting value.
+    /// The value is not set if validate_setting() rejects it.
     fn set_setting(&mut self, setting: SETT::Variant) {
+        if self.validate_setting(&setting) {
+            self.apply_setting(setting);
+        }
+    }
+
+    /// Set a setting value without validating it.
+    /// Every setting change goes through this function, which emits SettingChanged and
+    /// SettingTransition.
+    /// The previous value of SettingTransition is read from the settings, and it is None for the
+    /// settings set by the config at startup and when the setting values cannot be read (see
+    /// `MgBuilder::setting_values()`).
+    fn apply_setting(&mut self, setting: SETT::Variant) {
+        let old_values = application_settings(&self.model.settings, self.model.setting_values);
         self.model.settings.set_value(setting.clone());
-        self.model.relm.stream().emit(SettingChanged(setting));
+        let old_setting =
+            if self.model.initial_commands_executed && !old_values.is_empty() {
+                let changed = old_values.into_iter()
+                    .find(|&(ref name, ref old_value, _)| {
+                        self.get_setting_value(name)
+                            .map_or(false, |value| value_to_string(&value) != value_to_string(old_value))
+                    });
+                match changed {
+                    Some((name, old_value, _)) => SETT::to_variant(&name, old_value).ok(),
+                    // NOTE: the value did not change, so the previous value is the new one.
+                    None => Some(setting.clone()),
+                }
+            }
+            else {
+                None
+            };
+        self.model.relm.stream().emit(SettingChanged(setting.clone()));
+        self.model.relm.stream().emit(SettingTransition(old_setting, setting));
     }
 
     /// Set the window title.
@@ -620,6 +1566,25 @@ impl<COMM, SETT> Mg<COMM, SETT>
         self.window.set_title(title);
     }
 
+    /// Set how the command entry is pre-filled for the special command with the specified
+    /// identifier.
+    pub fn set_identifier_prefill(&mut self, identifier: char, mode: PrefillMode) {
+        self.model.identifier_prefill.insert(identifier, mode);
+    }
+
+    /// Set whether the identifier (or the dialog message) is shown at the left of the command
+    /// entry.
+    /// It is shown again when returning to the normal or command mode.
+    pub fn set_identifier_visible(&self, visible: bool) {
+        self.status_bar.emit(status_bar::Msg::IdentifierVisible(visible));
+    }
+
+    /// Set a custom title bar (i.e. a `gtk::HeaderBar`) for the window.
+    /// The key events are connected to the window, so the shortcuts keep working.
+    pub fn set_titlebar<W: IsA<gtk::Widget>>(&self, titlebar: &W) {
+        self.window.set_titlebar(titlebar);
+    }
+
     /// Set the variables that will be available in the settings.
     /// A variable can be used in mappings.
     /// The placeholder will be replaced by the value returned by the function.
@@ -628,4 +1593,9 @@ impl<COMM, SETT> Mg<COMM, SETT>
             .map(|(string, func)| (string.to_string(), func))
             .collect();
     }
+
+    /// Get the application window.
+    pub fn window(&self) -> &gtk::Window {
+        &self.window
+    }
 }
@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::env;
+use std::ffi::{CStr, CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use libc;
+
+/// Expand the `~` and `~user` prefixes of a path to the home directory.
+/// The path is returned unchanged when it does not start with `~` or when the home directory
+/// cannot be found.
+pub fn expand_path(path: &str) -> PathBuf {
+    if !path.starts_with('~') {
+        return PathBuf::from(path);
+    }
+    let (user, rest) =
+        match path.find('/') {
+            Some(index) => (&path[1..index], &path[index + 1..]),
+            None => (&path[1..], ""),
+        };
+    let home =
+        if user.is_empty() {
+            env::var_os("HOME").map(PathBuf::from)
+        }
+        else {
+            user_home_dir(user)
+        };
+    match home {
+        Some(home) => {
+            if rest.is_empty() {
+                home
+            }
+            else {
+                home.join(rest)
+            }
+        },
+        None => PathBuf::from(path),
+    }
+}
+
+/// Expand the `~` prefix of a path which may not be valid unicode.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    path.to_str()
+        .map(expand_path)
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Get the home directory of a user from the user database, which is not always a local file.
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    let user = CString::new(user).ok()?;
+    // NOTE: the entry returned by getpwnam() is overwritten by the next call, so the directory is
+    // copied right away.
+    unsafe {
+        let entry = libc::getpwnam(user.as_ptr());
+        if entry.is_null() || (*entry).pw_dir.is_null() {
+            return None;
+        }
+        let directory = CStr::from_ptr((*entry).pw_dir);
+        Some(PathBuf::from(OsStr::from_bytes(directory.to_bytes())))
+    }
+}
@@ -20,19 +20,102 @@
  */
 
 use std::collections::HashMap;
+use std::env;
 use std::io;
-use std::path::PathBuf;
+use std::mem;
+use std::path::{Path, PathBuf};
 
-use mg_settings::{EnumMetaData, MetaData, SettingCompletion, Value};
+use mg_settings::{EnumFromStr, EnumMetaData, MetaData, SettingCompletion, SpecialCommand, Value};
+use mg_settings::Command::Set;
 use mg_settings::errors::{Error, Result, SettingError};
 use mg_settings::settings;
 
+use app::{CommandSource, Mg, SettingValidatorFn, INPUT_MODE};
+use app::app_completion::{COMPLETE_COMMON_PREFIX_SETTING, COMPLETION_WRAP_SETTING};
+use app::color::{NAMED_COLORS, parse_color};
+use app::history::{HISTORY_EXCLUDE_SETTING, HISTORY_MAX_SIZE_SETTING};
+use app::message_queue::{MESSAGE_TIMEOUT_SETTING, STICKY_ERRORS_SETTING};
+use app::shell::SHELL_COMMANDS_SETTING;
+use app::shortcut::{MAP_BY_KEYCODE_SETTING, REPORT_UNMAPPED_KEYS_SETTING, SHORTCUT_TIMEOUT_SETTING, quote};
+use app::Msg::{ListSettingChanged, LocalSettingChanged, ShowSettingValues};
+use completion::{Completer, SettingCompleter, SettingValueCompleter, SETTING_VALUES_COMPLETER_IDENT};
+use completion::completion_view::Msg::AddCompleters;
+
 /// A directory or a file to create if it does not exist.
 pub enum DefaultConfig {
     /// A config directory.
     Dir(io::Result<PathBuf>),
     /// A config file with its content.
     File(io::Result<PathBuf>, &'static str),
+    /// A tree of config files, given by their path relative to the root directory and their
+    /// content.
+    /// The intermediate directories are created and the existing files are not overwritten.
+    Tree(io::Result<PathBuf>, &'static [(&'static str, &'static str)]),
+}
+
+impl DefaultConfig {
+    /// Create the cache directory of the application in `$XDG_CACHE_HOME` (`~/.cache` by default).
+    pub fn xdg_cache_dir(app_name: &str) -> Self {
+        DefaultConfig::Dir(xdg_dir("XDG_CACHE_HOME", ".cache", app_name))
+    }
+
+    /// Create the config directory of the application in `$XDG_CONFIG_HOME` (`~/.config` by
+    /// default).
+    pub fn xdg_config_dir(app_name: &str) -> Self {
+        DefaultConfig::Dir(xdg_dir("XDG_CONFIG_HOME", ".config", app_name))
+    }
+
+    /// Create a file in the config directory of the application.
+    /// The config directory must be created before with `xdg_config_dir()`.
+    pub fn xdg_config_file(app_name: &str, file_name: &str, content: &'static str) -> Self {
+        let path = xdg_dir("XDG_CONFIG_HOME", ".config", app_name)
+            .map(|directory| directory.join(file_name));
+        DefaultConfig::File(path, content)
+    }
+
+    /// Create the data directory of the application in `$XDG_DATA_HOME` (`~/.local/share` by
+    /// default).
+    pub fn xdg_data_dir(app_name: &str) -> Self {
+        DefaultConfig::Dir(xdg_dir("XDG_DATA_HOME", ".local/share", app_name))
+    }
+}
+
+/// Get the XDG base directory specified by the environment variable, or the default directory
+/// relative to the home directory.
+pub fn xdg_base_dir(variable: &str, default_dir: &str) -> io::Result<PathBuf> {
+    match env::var_os(variable) {
+        // NOTE: the specification says that relative paths must be ignored.
+        Some(ref directory) if !directory.is_empty() && Path::new(directory).is_absolute() =>
+            Ok(PathBuf::from(directory)),
+        _ => {
+            let home = env::var_os("HOME")
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot find the home directory"))?;
+            Ok(Path::new(&home).join(default_dir))
+        },
+    }
+}
+
+/// Get the directory of the application in the XDG base directory specified by the environment
+/// variable, or in the default directory relative to the home directory.
+fn xdg_dir(variable: &str, default_dir: &str, app_name: &str) -> io::Result<PathBuf> {
+    Ok(xdg_base_dir(variable, default_dir)?.join(app_name))
+}
+
+/// Trait to read the values of the settings by name.
+/// It is needed because the settings derive only allows to set the values.
+/// Implementing it is optional: mg reads the values of the application settings after
+/// `MgBuilder::setting_values()` is called.
+pub trait SettingValues {
+    /// Get the current value of the setting `name`, or None if there is no such setting.
+    fn get_value(&self, name: &str) -> Option<Value>;
+}
+
+/// The function reading the value of an application setting by name.
+pub type SettingValuesFn<SETT> = fn(&SETT, &str) -> Option<Value>;
+
+/// Read no setting values, for the applications which do not implement `SettingValues`.
+pub fn no_setting_values<SETT>(_settings: &SETT, _name: &str) -> Option<Value> {
+    None
 }
 
 #[doc(hidden)]
@@ -65,3 +148,685 @@ impl SettingCompletion for NoSettings {
         HashMap::new()
     }
 }
+
+impl SettingValues for NoSettings {
+    fn get_value(&self, _name: &str) -> Option<Value> {
+        None
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+      SETT: Default + EnumMetaData + settings::Settings + SettingCompletion + 'static,
+{
+    /// Get the current value of an application setting by name.
+    /// Returns None when the application did not call `MgBuilder::setting_values()`.
+    pub(crate) fn get_setting_value(&self, name: &str) -> Option<Value> {
+        (self.model.setting_values)(&self.model.settings, name)
+    }
+
+    /// Append an element to a list setting or remove one from it.
+    fn adjust_list_setting(&mut self, name: &str, operator: char, element: &str) {
+        let mut elements = self.list_setting_elements(name);
+        if operator == '+' {
+            if element.is_empty() {
+                self.error(Error::Msg(format!("Expecting an element to append to setting {}", name)));
+                return;
+            }
+            elements.push(element.to_string());
+        }
+        else {
+            let index = elements.iter().position(|current_element| current_element == element);
+            match index {
+                Some(index) => {
+                    elements.remove(index);
+                },
+                None => {
+                    self.error(Error::Msg(format!("{} is not in setting {}", element, name)));
+                    return;
+                },
+            }
+        }
+        self.set_setting_by_name(name.to_string(), Value::Str(join_list(&elements)));
+    }
+
+    /// Adjust the value of a numeric setting by adding (or subtracting) the amount.
+    /// For a list setting, append (or remove) the element instead.
+    pub fn adjust_setting(&mut self, name: &str, operator: char, amount: &str) {
+        if !SETT::get_metadata().contains_key(name) {
+            self.error(Error::Setting(SettingError::UnknownSetting(name.to_string())));
+            return;
+        }
+        if self.model.list_settings.contains(name) {
+            self.adjust_list_setting(name, operator, amount);
+            return;
+        }
+        let value = adjusted_value(name, self.get_setting_value(name), operator, amount);
+        match value {
+            Ok(value) => self.set_setting_by_name(name.to_string(), value),
+            Err(message) => self.error(Error::Msg(message)),
+        }
+    }
+
+    /// Handle the `setlocal mode name = value` command, which overrides the value of a setting in
+    /// a mode.
+    pub fn setlocal_command(&mut self, arguments: &str) {
+        match arguments.split_whitespace().next() {
+            Some(mode) => {
+                let arguments = arguments[mode.len()..].trim();
+                self.set_local_setting_from_command(mode, arguments);
+            },
+            None => self.error(Error::Msg("Argument required".to_string())),
+        }
+    }
+
+    /// Show the settings which do not have their default value, with their value, in the
+    /// completion view.
+    pub fn list_settings(&mut self) {
+        let values = self.changed_settings().into_iter()
+            .map(|(name, value)| (name, value_to_string(&value)))
+            .collect();
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert(SETTING_VALUES_COMPLETER_IDENT, Box::new(SettingValueCompleter::new(values)));
+        self.completion_view.emit(AddCompleters(completers));
+        self.model.relm.stream().emit(ShowSettingValues);
+    }
+
+    /// Check that the value of a numeric setting is in its bounds.
+    fn check_bounds(&self, name: &str, value: &Value) -> ::std::result::Result<(), String> {
+        check_value_bounds(name, value, self.model.setting_bounds.get(name))
+    }
+
+    /// Check that the value of a color setting is a valid color.
+    fn check_color(&self, name: &str, value: &Value) -> ::std::result::Result<(), String> {
+        if self.model.color_settings.contains(name) {
+            if let Value::Str(ref color) = *value {
+                if parse_color(color).is_none() {
+                    return Err(format!("Invalid color for setting {}: {}", name, color));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the elements of a list setting.
+    fn list_setting_elements(&self, name: &str) -> Vec<String> {
+        match self.get_setting_value(name) {
+            Some(Value::Str(ref value)) => split_list(value),
+            _ => vec![],
+        }
+    }
+
+    /// Set the value of a setting in a mode.
+    /// The global value of the setting is unchanged.
+    pub fn set_local_setting(&mut self, mode: &str, name: String, value: Value) {
+        match SETT::to_variant(&name, clone_value(&value)) {
+            Ok(setting) => {
+                if !self.validate_setting(&setting) {
+                    return;
+                }
+                self.model.local_settings.entry(mode.to_string())
+                    .or_insert_with(HashMap::new)
+                    .insert(name, value);
+                self.model.relm.stream().emit(LocalSettingChanged(mode.to_string(), setting));
+            },
+            Err(error) => {
+                self.error(Error::Msg("Error setting value".to_string()));
+                error!("{}", error);
+            },
+        }
+    }
+
+    /// Set the local value of a setting from the arguments of the setlocal command, following the
+    /// mode (i.e. `name = value`).
+    fn set_local_setting_from_command(&mut self, mode: &str, arguments: &str) {
+        let mode_exists = mode == INPUT_MODE ||
+            self.model.modes.values().any(|current_mode| current_mode.name == mode);
+        if !mode_exists {
+            self.error(Error::Msg(format!("Unknown mode: {}", mode)));
+            return;
+        }
+        let parse_result = self.model.settings_parser.parse_line(&format!("set {}", arguments), None);
+        for command in parse_result.commands {
+            if let Set(name, value) = command {
+                self.set_local_setting(mode, name, value);
+            }
+        }
+        for error in parse_result.errors {
+            self.show_parse_error(error);
+        }
+    }
+
+    /// Set a function to check the setting values before they are set.
+    /// When it returns an error, the message is shown and the setting keeps its old value.
+    pub fn set_setting_validator(&mut self, validator: SettingValidatorFn<SETT>) {
+        self.model.setting_validator = Some(validator);
+    }
+
+    /// Get the value of a setting in the current mode.
+    /// Return None for an unknown setting.
+    pub fn setting(&self, name: &str) -> Option<SETT::Variant> {
+        self.setting_for_mode(&self.model.mode_string, name)
+    }
+
+    /// Get the value of a setting in a mode: its local value if it was set with setlocal, its
+    /// global value otherwise.
+    /// Return None for an unknown setting.
+    pub fn setting_for_mode(&self, mode: &str, name: &str) -> Option<SETT::Variant> {
+        self.model.local_settings.get(mode)
+            .and_then(|settings| settings.get(name))
+            .map(clone_value)
+            .or_else(|| self.get_setting_value(name))
+            .and_then(|value| SETT::to_variant(name, value).ok())
+    }
+
+    /// Specify that a string setting contains a comma-separated list.
+    /// Its elements can only be read after `MgBuilder::setting_values()`.
+    pub fn set_list_setting(&mut self, name: &str) {
+        self.model.list_settings.insert(name.to_string());
+        self.update_setting_completer();
+    }
+
+    /// Specify that a string setting contains a color.
+    /// The colors are only checked after `MgBuilder::setting_values()`.
+    pub fn set_color_setting(&mut self, name: &str) {
+        self.model.color_settings.insert(name.to_string());
+        self.update_setting_completer();
+    }
+
+    /// Use the color setting for the background of the error messages.
+    pub fn set_error_color_setting(&mut self, name: &str) {
+        self.set_color_setting(name);
+        self.model.error_color_setting = Some(name.to_string());
+    }
+
+    /// Set the minimum and maximum values of a numeric setting.
+    /// They only apply to the values set afterwards: use `MgBuilder::setting_bounds()` for them to
+    /// apply to the config.
+    /// The bounds are only checked after `MgBuilder::setting_values()`.
+    pub fn set_setting_bounds(&mut self, name: &str, min: f64, max: f64) {
+        self.model.setting_bounds.insert(name.to_string(), (min, max));
+        self.update_setting_completer();
+    }
+
+    /// Set the value of a setting by name.
+    /// The old value is kept if the new value is rejected by validate_setting().
+    /// SettingChanged and SettingTransition are emitted by apply_setting().
+    pub fn set_setting_by_name(&mut self, name: String, value: Value) {
+        if name == MESSAGE_TIMEOUT_SETTING {
+            self.set_message_timeout(&value);
+            return;
+        }
+        if name == STICKY_ERRORS_SETTING {
+            self.set_sticky_errors(&value);
+            return;
+        }
+        if name == HISTORY_EXCLUDE_SETTING {
+            self.set_history_exclude(&value);
+            return;
+        }
+        if name == HISTORY_MAX_SIZE_SETTING {
+            self.set_history_max_size(&value);
+            return;
+        }
+        if name == MAP_BY_KEYCODE_SETTING {
+            self.set_map_by_keycode(&value);
+            return;
+        }
+        if name == COMPLETE_COMMON_PREFIX_SETTING {
+            self.set_complete_common_prefix(&value);
+            return;
+        }
+        if name == COMPLETION_WRAP_SETTING {
+            self.set_completion_wrap(&value);
+            return;
+        }
+        if name == SHELL_COMMANDS_SETTING {
+            self.set_shell_commands(&value);
+            return;
+        }
+        if name == SHORTCUT_TIMEOUT_SETTING {
+            self.set_shortcut_timeout(&value);
+            return;
+        }
+        if name == REPORT_UNMAPPED_KEYS_SETTING {
+            self.set_report_unmapped_keys(&value);
+            return;
+        }
+        match SETT::to_variant(&name, clone_value(&value)) {
+            Ok(setting) => {
+                if !self.validate_setting(&setting) {
+                    return;
+                }
+                if self.model.error_color_setting.as_ref() == Some(&name) {
+                    if let Value::Str(ref color) = value {
+                        self.model.error_color = parse_color(color);
+                    }
+                }
+                self.persist_setting(&name, &value);
+                if let CommandSource::Config { .. } = self.model.command_source {
+                    self.model.config_settings.insert(name.clone());
+                }
+                self.apply_setting(setting);
+                if self.model.list_settings.contains(&name) {
+                    let elements = self.list_setting_elements(&name);
+                    self.model.relm.stream().emit(ListSettingChanged(name, elements));
+                    // NOTE: recreate the setting completer to complete the new elements.
+                    self.update_setting_completer();
+                }
+            },
+            Err(error) => {
+                self.error(Error::Msg("Error setting value".to_string()));
+                error!("{}", error);
+            },
+        }
+    }
+
+    /// Show the current value of a setting, which can be a built-in one.
+    pub fn show_setting(&mut self, name: &str) {
+        match self.setting_value(name) {
+            Some(value) => self.info(&format!("{}={}", name, value)),
+            None => self.error(Error::Setting(SettingError::UnknownSetting(name.to_string()))),
+        }
+    }
+
+    /// Recreate the setting completer to take into account the bounds, the color settings and the
+    /// elements of the list settings.
+    pub fn update_setting_completer(&self) {
+        let colors: Vec<_> = NAMED_COLORS.iter().map(|&(name, _)| name.to_string()).collect();
+        let list_elements = self.model.list_settings.iter()
+            .map(|name| (name.clone(), self.list_setting_elements(name)))
+            .collect();
+        let completer = SettingCompleter::<SETT>::new()
+            .bounds(&self.model.setting_bounds)
+            .list_elements(list_elements)
+            .values(&self.model.color_settings, &colors);
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert("set", Box::new(completer));
+        self.completion_view.emit(AddCompleters(completers));
+    }
+
+    /// Check a setting value against its bounds, the color settings and the validator of the
+    /// application.
+    fn check_setting(&self, setting: &SETT::Variant) -> ::std::result::Result<(), String> {
+        if let Some((name, value)) = variant_name_value::<SETT>(setting, self.model.setting_values) {
+            self.check_bounds(&name, &value)?;
+            self.check_color(&name, &value)?;
+        }
+        match self.model.setting_validator {
+            Some(ref validator) => validator(setting),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the setting value and show the error if it is rejected.
+    /// Every setting value goes through this function, whether it comes from the config, a
+    /// command or the application.
+    /// The errors from the config are shown with the other errors once it has been executed.
+    pub fn validate_setting(&mut self, setting: &SETT::Variant) -> bool {
+        match self.check_setting(setting) {
+            Ok(()) => true,
+            Err(message) => {
+                if self.model.initial_commands_executed {
+                    self.error(Error::Msg(message));
+                }
+                else {
+                    self.model.initial_errors.push(Error::Msg(message));
+                }
+                false
+            },
+        }
+    }
+
+    /// Invert the value of a boolean setting.
+    pub fn toggle_setting(&mut self, name: &str) {
+        match self.get_setting_value(name) {
+            Some(Value::Bool(value)) => self.set_setting_by_name(name.to_string(), Value::Bool(!value)),
+            Some(value) =>
+                self.error(Error::Msg(format!("Cannot toggle setting {} of type {}", name, value_type(&value)))),
+            None => self.error(Error::Setting(SettingError::UnknownSetting(name.to_string()))),
+        }
+    }
+}
+
+/// Get the settings of the application with their current value, sorted by name, and whether
+/// they have their default value.
+pub(crate) fn application_settings<SETT>(settings: &SETT, get_value: SettingValuesFn<SETT>) -> Vec<(String, Value, bool)>
+    where SETT: Default + EnumMetaData,
+{
+    let default_settings = SETT::default();
+    let mut values: Vec<_> = SETT::get_metadata().keys()
+        .filter_map(|name| {
+            let value = get_value(settings, name)?;
+            let is_default = get_value(&default_settings, name)
+                .map_or(false, |default_value| value_to_string(&default_value) == value_to_string(&value));
+            Some((name.clone(), value, is_default))
+        })
+        .collect();
+    values.sort_by(|&(ref name1, _, _), &(ref name2, _, _)| name1.cmp(name2));
+    values
+}
+
+/// Get the name and the value of a setting variant.
+/// The variant is first applied to settings where every value differs from the default one, so
+/// that its setting is found even when its value is the default one.
+fn variant_name_value<SETT>(setting: &SETT::Variant, get_value: SettingValuesFn<SETT>) -> Option<(String, Value)>
+    where SETT: Default + EnumMetaData + settings::Settings + SettingCompletion,
+{
+    let names: Vec<_> = SETT::get_metadata().keys().cloned().collect();
+    let value_completions = SETT::get_value_completions();
+    let mut other_settings = SETT::default();
+    for name in &names {
+        let other_setting = get_value(&other_settings, name)
+            .map(|value| other_value(&value, value_completions.get(name)))
+            .and_then(|value| SETT::to_variant(name, value).ok());
+        if let Some(other_setting) = other_setting {
+            other_settings.set_value(other_setting);
+        }
+    }
+    // NOTE: when the value of the variant is the other value, it differs from the default one.
+    changed_setting(&names, other_settings, setting, get_value)
+        .or_else(|| changed_setting(&names, SETT::default(), setting, get_value))
+}
+
+/// Apply the setting variant and get the name and the new value of the setting it changed.
+fn changed_setting<SETT>(names: &[String], mut settings: SETT, setting: &SETT::Variant,
+    get_value: SettingValuesFn<SETT>) -> Option<(String, Value)>
+    where SETT: settings::Settings,
+{
+    let old_values: Vec<_> = names.iter()
+        .map(|name| get_value(&settings, name).as_ref().map(value_to_string))
+        .collect();
+    settings.set_value(setting.clone());
+    names.iter().zip(old_values)
+        .filter_map(|(name, old_value)| {
+            let value = get_value(&settings, name)?;
+            if Some(value_to_string(&value)) != old_value {
+                Some((name.clone(), value))
+            }
+            else {
+                None
+            }
+        })
+        .next()
+}
+
+/// Add (or subtract) the amount to the value of a numeric setting.
+fn adjusted_value(name: &str, value: Option<Value>, operator: char, amount: &str)
+    -> ::std::result::Result<Value, String>
+{
+    match value {
+        Some(Value::Int(value)) => {
+            match amount.parse::<i64>() {
+                Ok(amount) if operator == '+' => Ok(Value::Int(value.saturating_add(amount))),
+                Ok(amount) => Ok(Value::Int(value.saturating_sub(amount))),
+                Err(_) => Err(format!("Expecting an integer to adjust setting {}, found {}", name, amount)),
+            }
+        },
+        Some(Value::Float(value)) => {
+            match amount.parse::<f64>() {
+                Ok(amount) if operator == '+' => Ok(Value::Float(value + amount)),
+                Ok(amount) => Ok(Value::Float(value - amount)),
+                Err(_) => Err(format!("Expecting a number to adjust setting {}, found {}", name, amount)),
+            }
+        },
+        Some(value) => Err(format!("Cannot use {}= on setting {} of type {}", operator, name, value_type(&value))),
+        None => Err(format!("Cannot read the value of setting {}", name)),
+    }
+}
+
+/// Check that the value of a numeric setting is in the bounds, if any.
+fn check_value_bounds(name: &str, value: &Value, bounds: Option<&(f64, f64)>) -> ::std::result::Result<(), String> {
+    if let Some(&(min, max)) = bounds {
+        let number =
+            match *value {
+                Value::Float(value) => value,
+                Value::Int(value) => value as f64,
+                _ => return Ok(()),
+            };
+        if number < min || number > max {
+            return Err(format!("{} must be between {} and {}", name, min, max));
+        }
+    }
+    Ok(())
+}
+
+/// Get a value of the same type that differs from the value.
+/// The string value of an enum is replaced by another of its completions.
+fn other_value(value: &Value, completions: Option<&Vec<String>>) -> Value {
+    match *value {
+        Value::Bool(value) => Value::Bool(!value),
+        Value::Float(value) => Value::Float(value + 1.0),
+        Value::Int(value) => Value::Int(value.wrapping_add(1)),
+        Value::Str(ref value) => {
+            let other = completions
+                .and_then(|completions| completions.iter().find(|completion| *completion != value))
+                .cloned()
+                .unwrap_or_else(|| format!("{}_", value));
+            Value::Str(other)
+        },
+    }
+}
+
+/// Get the name of the type of a value.
+fn value_type(value: &Value) -> &'static str {
+    match *value {
+        Value::Bool(_) => "bool",
+        Value::Float(_) => "float",
+        Value::Int(_) => "int",
+        Value::Str(_) => "string",
+    }
+}
+
+/// Copy a value, since `Value` does not implement `Clone`.
+pub fn clone_value(value: &Value) -> Value {
+    match *value {
+        Value::Bool(value) => Value::Bool(value),
+        Value::Float(value) => Value::Float(value),
+        Value::Int(value) => Value::Int(value),
+        Value::Str(ref value) => Value::Str(value.clone()),
+    }
+}
+
+/// Convert a value to the string used to write it in the config file.
+pub fn value_to_string(value: &Value) -> String {
+    match *value {
+        Value::Bool(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        Value::Int(value) => value.to_string(),
+        Value::Str(ref value) => value.clone(),
+    }
+}
+
+/// Convert a value to the string used to write it in the config file, quoting the strings.
+/// The quotes and the backslashes of the strings are escaped.
+pub fn value_to_config(value: &Value) -> String {
+    match *value {
+        Value::Str(ref value) => quote(value),
+        ref value => value_to_string(value),
+    }
+}
+
+/// Join the elements of a list setting into its value.
+/// The commas and the backslashes in the elements are escaped with a backslash.
+pub(crate) fn join_list(elements: &[String]) -> String {
+    elements.iter()
+        .map(|element| element.replace('\\', "\\\\").replace(',', "\\,"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Split the value of a list setting into its elements.
+/// A comma or a backslash escaped with a backslash is part of the element.
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    let mut elements = vec![];
+    let mut element = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' if chars.peek() == Some(&',') || chars.peek() == Some(&'\\') => {
+                if let Some(escaped) = chars.next() {
+                    element.push(escaped);
+                }
+            },
+            ',' => elements.push(mem::replace(&mut element, String::new())),
+            _ => element.push(character),
+        }
+    }
+    elements.push(element);
+    elements.iter()
+        .map(|element| element.trim())
+        .filter(|element| !element.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mg_settings::Value;
+
+    use super::{
+        SettingValues,
+        adjusted_value,
+        application_settings,
+        check_value_bounds,
+        join_list,
+        other_value,
+        split_list,
+        value_to_config,
+    };
+
+    fn strings(elements: &[&str]) -> Vec<String> {
+        elements.iter().map(|element| element.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_list() {
+        assert_eq!(split_list("a, b,c"), strings(&["a", "b", "c"]));
+        assert_eq!(split_list(",a,,b,"), strings(&["a", "b"]));
+        assert_eq!(split_list(""), strings(&[]));
+        assert_eq!(split_list("a\\,b,c"), strings(&["a,b", "c"]));
+        assert_eq!(split_list("a\\\\,b"), strings(&["a\\", "b"]));
+        assert_eq!(split_list("C:\\dir,b"), strings(&["C:\\dir", "b"]));
+    }
+
+    #[test]
+    fn test_join_list() {
+        assert_eq!(join_list(&strings(&["a", "b"])), "a,b");
+        assert_eq!(join_list(&strings(&["a,b", "c\\"])), "a\\,b,c\\\\");
+    }
+
+    #[test]
+    fn test_list_round_trip() {
+        let elements = strings(&["https://example.com/?q=a,b", "back\\slash", "back\\,both", "plain"]);
+        assert_eq!(split_list(&join_list(&elements)), elements);
+    }
+
+    #[derive(Settings)]
+    pub struct AppSettings {
+        enabled: bool,
+        step: i64,
+        title: String,
+    }
+
+    impl Default for AppSettings {
+        fn default() -> Self {
+            AppSettings {
+                enabled: true,
+                step: 5,
+                title: String::new(),
+            }
+        }
+    }
+
+    impl SettingValues for AppSettings {
+        fn get_value(&self, name: &str) -> Option<Value> {
+            let value =
+                match name {
+                    "enabled" => Value::Bool(self.enabled),
+                    "step" => Value::Int(self.step),
+                    "title" => Value::Str(self.title.clone()),
+                    _ => return None,
+                };
+            Some(value)
+        }
+    }
+
+    fn values(settings: &AppSettings) -> Vec<(String, String, bool)> {
+        application_settings(settings, AppSettings::get_value).into_iter()
+            .map(|(name, value, is_default)| {
+                let value =
+                    match value {
+                        Value::Bool(value) => value.to_string(),
+                        Value::Float(value) => value.to_string(),
+                        Value::Int(value) => value.to_string(),
+                        Value::Str(value) => value,
+                    };
+                (name, value, is_default)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_default_values() {
+        assert_eq!(values(&AppSettings::default()), vec![
+            ("enabled".to_string(), "true".to_string(), true),
+            ("step".to_string(), "5".to_string(), true),
+            ("title".to_string(), "".to_string(), true),
+        ]);
+    }
+
+    #[test]
+    fn test_changed_values() {
+        let settings = AppSettings {
+            enabled: false,
+            step: 5,
+            title: "mg".to_string(),
+        };
+        assert_eq!(values(&settings), vec![
+            ("enabled".to_string(), "false".to_string(), false),
+            ("step".to_string(), "5".to_string(), true),
+            ("title".to_string(), "mg".to_string(), false),
+        ]);
+    }
+
+    #[test]
+    fn test_adjusted_value() {
+        assert_eq!(adjusted_value("step", Some(Value::Int(5)), '+', "2"), Ok(Value::Int(7)));
+        assert_eq!(adjusted_value("step", Some(Value::Int(7)), '-', "3"), Ok(Value::Int(4)));
+        assert_eq!(adjusted_value("step", Some(Value::Int(i64::max_value())), '+', "1"),
+            Ok(Value::Int(i64::max_value())));
+        assert_eq!(adjusted_value("zoom", Some(Value::Float(1.0)), '-', "0.5"), Ok(Value::Float(0.5)));
+        assert_eq!(adjusted_value("step", Some(Value::Int(5)), '+', "0.5"),
+            Err("Expecting an integer to adjust setting step, found 0.5".to_string()));
+        assert_eq!(adjusted_value("enabled", Some(Value::Bool(true)), '+', "1"),
+            Err("Cannot use += on setting enabled of type bool".to_string()));
+        assert_eq!(adjusted_value("step", None, '+', "1"), Err("Cannot read the value of setting step".to_string()));
+    }
+
+    #[test]
+    fn test_check_value_bounds() {
+        let bounds = (1.0, 100.0);
+        assert_eq!(check_value_bounds("step", &Value::Int(50), Some(&bounds)), Ok(()));
+        assert_eq!(check_value_bounds("step", &Value::Float(100.0), Some(&bounds)), Ok(()));
+        assert_eq!(check_value_bounds("step", &Value::Int(0), Some(&bounds)),
+            Err("step must be between 1 and 100".to_string()));
+        assert_eq!(check_value_bounds("step", &Value::Int(500), None), Ok(()));
+        assert_eq!(check_value_bounds("title", &Value::Str("mg".to_string()), Some(&bounds)), Ok(()));
+    }
+
+    #[test]
+    fn test_other_value() {
+        assert_eq!(other_value(&Value::Bool(true), None), Value::Bool(false));
+        assert_eq!(other_value(&Value::Int(i64::max_value()), None), Value::Int(i64::min_value()));
+        let completions = strings(&["dark", "light"]);
+        assert_eq!(other_value(&Value::Str("dark".to_string()), Some(&completions)), Value::Str("light".to_string()));
+        assert_eq!(other_value(&Value::Str("mg".to_string()), None), Value::Str("mg_".to_string()));
+    }
+
+    #[test]
+    fn test_value_to_config() {
+        assert_eq!(value_to_config(&Value::Int(5)), "5");
+        assert_eq!(value_to_config(&Value::Str(r#"Say "hi" \ now"#.to_string())), r#""Say \"hi\" \\ now""#);
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk::{self, Continue};
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+
+use app::{CommandSource, Mg};
+use app::settings::{application_settings, clone_value, value_to_config, value_to_string};
+
+/// The delay, in milliseconds, before writing the changed settings, so that many changes in a
+/// short time only write the file once.
+const WRITE_DELAY: u32 = 500;
+
+/// A file where the settings changed by the user are saved.
+pub struct SettingsFile {
+    config_values: HashMap<String, Value>,
+    path: PathBuf,
+    values: HashMap<String, Value>,
+    write_scheduled: bool,
+}
+
+impl SettingsFile {
+    /// Create a new settings file.
+    /// The settings that have their value from the config are not saved.
+    pub fn new(path: PathBuf, config_values: HashMap<String, Value>) -> Self {
+        SettingsFile {
+            config_values,
+            path,
+            values: HashMap::new(),
+            write_scheduled: false,
+        }
+    }
+
+    /// Record the new value of a setting.
+    fn set_value(&mut self, name: &str, value: &Value) {
+        let config_value = self.config_values.get(name).map(value_to_string);
+        if config_value.as_ref() == Some(&value_to_string(value)) {
+            self.values.remove(name);
+        }
+        else {
+            self.values.insert(name.to_string(), clone_value(value));
+        }
+    }
+
+    /// Write the settings to the file.
+    /// The settings are written to a temporary file which is then renamed so that the file is
+    /// never partially written.
+    pub fn write(&self) -> io::Result<()> {
+        let mut values: Vec<_> = self.values.iter().collect();
+        values.sort_by(|&(name1, _), &(name2, _)| name1.cmp(name2));
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        {
+            let mut file = File::create(&temp_path)?;
+            for (name, value) in values {
+                writeln!(file, "set {} = {}", name, value_to_config(value))?;
+            }
+        }
+        fs::rename(temp_path, &self.path)
+    }
+}
+
+/// Write the settings file, logging the error if any.
+fn write(settings_file: &SettingsFile) {
+    if let Err(error) = settings_file.write() {
+        warn!("cannot write the settings to `{}`: {}", settings_file.path.to_string_lossy(), error);
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Save the new value of a setting to the settings file, if any.
+    pub fn persist_setting(&self, name: &str, value: &Value) {
+        if let Some(ref settings_file) = self.model.settings_file {
+            let mut file = settings_file.borrow_mut();
+            file.set_value(name, value);
+            if !file.write_scheduled {
+                file.write_scheduled = true;
+                let settings_file = settings_file.clone();
+                gtk::timeout_add(WRITE_DELAY, move || {
+                    let mut file = settings_file.borrow_mut();
+                    file.write_scheduled = false;
+                    write(&file);
+                    Continue(false)
+                });
+            }
+        }
+    }
+
+    /// Save the settings changed by the user to the specified file, and restore the settings
+    /// that were saved in this file.
+    /// The saved settings take precedence over the config.
+    pub fn set_settings_persistence(&mut self, path: PathBuf) {
+        let config_values = application_settings(&self.model.settings, self.model.setting_values).into_iter()
+            .map(|(name, value, _)| (name, value))
+            .collect();
+        let settings_file = SettingsFile::new(path.clone(), config_values);
+        let settings_file = Rc::new(RefCell::new(settings_file));
+        if path.exists() {
+            match File::open(&path) {
+                Ok(file) => {
+                    let parse_result = self.model.settings_parser.parse(BufReader::new(file), None);
+                    self.model.settings_file = Some(settings_file);
+                    self.model.command_source = CommandSource::Config { file: Some(path.clone()), line: None };
+                    self.execute_commands(parse_result, false);
+                    self.model.command_source = CommandSource::Typed;
+                },
+                Err(error) => {
+                    warn!("cannot read the settings from `{}`: {}", path.to_string_lossy(), error);
+                    self.model.settings_file = Some(settings_file);
+                },
+            }
+        }
+        else {
+            self.model.settings_file = Some(settings_file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs::{self, remove_file};
+    use std::path::Path;
+
+    use mg_settings::Value;
+
+    use super::SettingsFile;
+
+    #[test]
+    fn test_write_changed_settings() {
+        let path = env::temp_dir().join("mg-unit-test-settings-file");
+        let mut config_values = HashMap::new();
+        config_values.insert("enabled".to_string(), Value::Bool(true));
+        let mut settings_file = SettingsFile::new(path.clone(), config_values);
+        settings_file.set_value("title", &Value::Str("Say \"hi\"".to_string()));
+        settings_file.set_value("step", &Value::Int(7));
+        // The value from the config is not saved.
+        settings_file.set_value("enabled", &Value::Bool(true));
+        settings_file.write().expect("write settings");
+        assert_eq!(fs::read_to_string(&path).expect("read settings"),
+            "set step = 7\nset title = \"Say \\\"hi\\\"\"\n");
+        // Setting back the value of the config removes it from the file.
+        settings_file.set_value("enabled", &Value::Bool(false));
+        settings_file.set_value("enabled", &Value::Bool(true));
+        settings_file.set_value("step", &Value::Int(3));
+        settings_file.write().expect("write settings");
+        assert_eq!(fs::read_to_string(&path).expect("read settings"),
+            "set step = 3\nset title = \"Say \\\"hi\\\"\"\n");
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        assert!(!Path::new(&temp_path).exists());
+        remove_file(path).expect("remove settings");
+    }
+}
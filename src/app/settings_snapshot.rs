@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+
+use app::{Mg, INFO_MESSAGE_DURATION};
+use app::Msg::ShowSettingValues;
+use app::app_completion::{COMPLETE_COMMON_PREFIX_SETTING, COMPLETION_WRAP_SETTING};
+use app::history::{HISTORY_EXCLUDE_SETTING, HISTORY_MAX_SIZE, HISTORY_MAX_SIZE_SETTING};
+use app::message_queue::{MESSAGE_TIMEOUT_SETTING, STICKY_ERRORS_SETTING};
+use app::settings::{application_settings, join_list, value_to_string};
+use app::shell::SHELL_COMMANDS_SETTING;
+use app::shortcut::{
+    MAP_BY_KEYCODE_SETTING,
+    REPORT_UNMAPPED_KEYS_SETTING,
+    SHORTCUT_TIMEOUT,
+    SHORTCUT_TIMEOUT_SETTING,
+};
+use completion::{AllSettingsCompleter, Completer, SETTING_VALUES_COMPLETER_IDENT};
+use completion::completion_view::CompletionWrap;
+use completion::completion_view::Msg::AddCompleters;
+
+/// The marker added to the value of a setting which has its default value.
+const DEFAULT_MARKER: &str = "(default)";
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Get the name, current value, default value and description of the built-in settings.
+    fn builtin_settings(&self) -> Vec<(&'static str, Value, Value, &'static str)> {
+        let wrap_value = |wrap| {
+            let name =
+                match wrap {
+                    CompletionWrap::Stop => "stop",
+                    CompletionWrap::Unselect => "unselect",
+                    CompletionWrap::Wrap => "wrap",
+                };
+            Value::Str(name.to_string())
+        };
+        vec![
+            (COMPLETE_COMMON_PREFIX_SETTING, Value::Bool(self.model.complete_common_prefix), Value::Bool(false),
+                "Insert the longest common prefix of the completions first"),
+            (COMPLETION_WRAP_SETTING, wrap_value(self.model.completion_wrap), wrap_value(CompletionWrap::Unselect),
+                "What to do when moving past the last completion (stop, unselect or wrap)"),
+            (HISTORY_EXCLUDE_SETTING, Value::Str(join_list(&self.model.history_exclude)), Value::Str(String::new()),
+                "The prefixes of the commands not recorded in the history"),
+            (HISTORY_MAX_SIZE_SETTING, Value::Int(self.model.history_max_size as i64),
+                Value::Int(HISTORY_MAX_SIZE as i64), "The maximum number of commands kept in the history"),
+            (MAP_BY_KEYCODE_SETTING, Value::Bool(self.model.map_by_keycode), Value::Bool(false),
+                "Match the mappings by physical key too"),
+            (MESSAGE_TIMEOUT_SETTING, duration_value(self.model.message_duration),
+                Value::Int(INFO_MESSAGE_DURATION as i64), "The number of seconds a message is shown (0 to keep it)"),
+            (REPORT_UNMAPPED_KEYS_SETTING, Value::Bool(self.model.report_unmapped_keys), Value::Bool(false),
+                "Report the key sequences that do not match any mapping"),
+            (SHELL_COMMANDS_SETTING, Value::Bool(self.model.shell_commands_enabled), Value::Bool(false),
+                "Allow running shell commands with !command"),
+            (SHORTCUT_TIMEOUT_SETTING, duration_value(self.model.shortcut_timeout),
+                Value::Int(SHORTCUT_TIMEOUT as i64), "The number of seconds to wait for the next key of a mapping"),
+            (STICKY_ERRORS_SETTING, Value::Bool(self.model.sticky_errors), Value::Bool(false),
+                "Keep the errors in the status bar until the next key press"),
+        ]
+    }
+
+    /// Get every setting, including the built-in ones, with its current value and whether it has
+    /// its default value, sorted by name.
+    fn all_settings(&self) -> Vec<(String, Value, bool)> {
+        let builtin_settings = self.builtin_settings().into_iter()
+            .map(|(name, value, default_value, _)| {
+                let is_default = value_to_string(&value) == value_to_string(&default_value);
+                (name.to_string(), value, is_default)
+            });
+        let mut settings: Vec<_> = builtin_settings.chain(application_settings(&self.model.settings, self.model.setting_values))
+            .collect();
+        settings.sort_by(|&(ref name1, _, _), &(ref name2, _, _)| name1.cmp(name2));
+        settings
+    }
+
+    /// Get the settings, including the built-in ones, which do not have their default value, with
+    /// their current value, sorted by name.
+    pub fn changed_settings(&self) -> Vec<(String, Value)> {
+        self.all_settings().into_iter()
+            .filter(|&(_, _, is_default)| !is_default)
+            .map(|(name, value, _)| (name, value))
+            .collect()
+    }
+
+    /// Get the current value of a setting by name, e.g. for diagnostics.
+    /// Returns None for an unknown setting.
+    pub fn setting_value(&self, name: &str) -> Option<String> {
+        let builtin = self.builtin_settings().into_iter()
+            .find(|&(builtin, _, _, _)| builtin == name);
+        if let Some((_, value, _, _)) = builtin {
+            return Some(value_to_string(&value));
+        }
+        self.get_setting_value(name)
+            .map(|value| value_to_string(&value))
+    }
+
+    /// Get every setting, including the built-in ones, with its current value, sorted by name.
+    /// The settings having their default value are marked with `(default)`, so that the changed
+    /// settings are easy to spot in a bug report.
+    pub fn settings_snapshot(&self) -> Vec<(String, String)> {
+        self.all_settings().into_iter()
+            .map(|(name, value, is_default)| {
+                let value = value_to_string(&value);
+                if is_default {
+                    (name, format!("{} {}", value, DEFAULT_MARKER))
+                }
+                else {
+                    (name, value)
+                }
+            })
+            .collect()
+    }
+
+    /// Handle the `set all` command: list every setting, including the ones having their default
+    /// value, with its current value and its description in the completion view.
+    /// The built-in settings are prefixed by `built-in:` in the description.
+    pub fn list_all_settings(&mut self) {
+        let metadata = SETT::get_metadata();
+        let builtin_help: HashMap<_, _> = self.builtin_settings().into_iter()
+            .map(|(name, _, _, help)| (name, help))
+            .collect();
+        let settings = self.settings_snapshot().into_iter()
+            .map(|(name, value)| {
+                let help =
+                    if let Some(help) = builtin_help.get(name.as_str()) {
+                        format!("built-in: {}", help)
+                    }
+                    else {
+                        metadata.get(&name)
+                            .map(|metadata| metadata.help_text.clone())
+                            .unwrap_or_default()
+                    };
+                (name, value, help)
+            })
+            .collect();
+        let completer = AllSettingsCompleter::new(settings)
+            .categories(&self.model.setting_categories);
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert(SETTING_VALUES_COMPLETER_IDENT, Box::new(completer));
+        self.completion_view.emit(AddCompleters(completers));
+        self.model.relm.stream().emit(ShowSettingValues);
+    }
+}
+
+/// Convert a duration setting to its value in seconds, 0 meaning no duration.
+fn duration_value(duration: Option<Duration>) -> Value {
+    Value::Int(duration.map_or(0, |duration| duration.as_secs() as i64))
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::process::{Command, Output};
+use std::thread;
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+use mg_settings::errors::Error;
+use relm::Channel;
+
+use app::Mg;
+use app::Msg::{ShellCommandCompleted, ShellCommandFinished};
+
+/// The maximum number of characters of the output shown in the status bar.
+const MAX_STATUS_OUTPUT_LEN: usize = 80;
+/// The name of the built-in setting allowing to run shell commands with `!command`.
+pub const SHELL_COMMANDS_SETTING: &str = "shell-commands";
+
+/// Get the first line of the output, truncated to fit in the status bar.
+fn status_output(output: &str) -> String {
+    let first_line = output.lines().next().unwrap_or_default();
+    if first_line.chars().count() > MAX_STATUS_OUTPUT_LEN {
+        let mut truncated: String = first_line.chars().take(MAX_STATUS_OUTPUT_LEN - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+    else {
+        first_line.to_string()
+    }
+}
+
+/// Get the message telling how a shell command failed, with the first line of its error output.
+/// The exit status is None when the command was killed by a signal.
+fn failure_message(shell_command: &str, status: Option<i32>, stderr: &str) -> String {
+    let message =
+        match status {
+            Some(code) => format!("{}: exited with status {}", shell_command, code),
+            None => format!("{}: killed by a signal", shell_command),
+        };
+    let error_line = status_output(stderr);
+    if error_line.is_empty() {
+        message
+    }
+    else {
+        format!("{}: {}", message, error_line)
+    }
+}
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Get the standard output of the last shell command that finished.
+    pub fn last_shell_output(&self) -> Option<String> {
+        self.model.last_shell_output.clone()
+    }
+
+    /// Handle the `!command args` command, which runs the command in a shell.
+    /// The command runs in the background. When it finishes, the first line of its output or its
+    /// exit status is shown.
+    pub fn shell_command(&mut self, shell_command: &str) {
+        if !self.model.shell_commands_enabled {
+            self.error(Error::Msg(format!("Shell commands are disabled (set {} = true to enable them)",
+                SHELL_COMMANDS_SETTING)));
+        }
+        else if shell_command.is_empty() {
+            self.error(Error::Msg("Argument required".to_string()));
+        }
+        else {
+            self.run_shell_command(shell_command.to_string());
+        }
+    }
+
+    /// Run the command in a shell in another thread.
+    /// Its output is sent back through a channel, which wakes up the main loop when the command
+    /// finishes.
+    fn run_shell_command(&mut self, shell_command: String) {
+        self.info(&format!("running: {}", shell_command));
+        self.model.shell_command_id += 1;
+        let id = self.model.shell_command_id;
+        let stream = self.model.relm.stream().clone();
+        let (channel, sender) = Channel::new(move |(shell_command, output)| {
+            stream.emit(ShellCommandFinished(id, shell_command, output));
+        });
+        // NOTE: the channel is kept until the command finishes, since the callback is not called
+        // anymore once it is dropped.
+        self.model.shell_channels.insert(id, channel);
+        thread::spawn(move || {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(&shell_command)
+                .output()
+                .map_err(|error| error.to_string());
+            // NOTE: the receiver is gone if the window was closed, so the error is ignored.
+            let _ = sender.send((shell_command, output));
+        });
+    }
+
+    /// Set whether the `!command` can be used to run shell commands.
+    pub fn set_shell_commands_enabled(&mut self, enabled: bool) {
+        self.model.shell_commands_enabled = enabled;
+    }
+
+    /// Set whether the `!command` can be used to run shell commands from the value of the
+    /// `shell-commands` setting.
+    pub fn set_shell_commands(&mut self, value: &Value) {
+        match *value {
+            Value::Bool(enabled) => self.set_shell_commands_enabled(enabled),
+            _ => self.error(Error::Msg(format!("Expecting a boolean for setting {}", SHELL_COMMANDS_SETTING))),
+        }
+    }
+
+    /// Show the output or the exit status of a shell command and send it to the application.
+    pub fn shell_command_finished(&mut self, id: u64, shell_command: &str, output: Result<Output, String>) {
+        self.model.shell_channels.remove(&id);
+        let output =
+            match output {
+                Ok(output) => output,
+                Err(error) => {
+                    self.error(Error::Msg(format!("cannot run {}: {}", shell_command, error)));
+                    return;
+                },
+            };
+        // NOTE: the output of a command is not necessarily valid UTF-8.
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        if output.status.success() {
+            let first_line = status_output(&stdout);
+            if first_line.is_empty() {
+                self.info(&format!("{}: done", shell_command));
+            }
+            else {
+                self.info(&first_line);
+            }
+        }
+        else {
+            self.error(Error::Msg(failure_message(shell_command, output.status.code(), &stderr)));
+        }
+        self.model.last_shell_output = Some(stdout.clone());
+        self.model.relm.stream().emit(ShellCommandCompleted {
+            status: output.status.code(),
+            stdout,
+            stderr,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_STATUS_OUTPUT_LEN, failure_message, status_output};
+
+    #[test]
+    fn test_status_output_first_line() {
+        assert_eq!(status_output("first\nsecond\n"), "first");
+        assert_eq!(status_output(""), "");
+    }
+
+    #[test]
+    fn test_status_output_truncated() {
+        let output = "0".repeat(100);
+        let status = status_output(&output);
+        assert_eq!(status.chars().count(), MAX_STATUS_OUTPUT_LEN);
+        assert!(status.ends_with("0…"));
+        let output = "é".repeat(MAX_STATUS_OUTPUT_LEN);
+        assert_eq!(status_output(&output), output);
+    }
+
+    #[test]
+    fn test_failure_message() {
+        assert_eq!(failure_message("false", Some(1), ""), "false: exited with status 1");
+        assert_eq!(failure_message("exit 3", Some(3), "failure\nmore\n"), "exit 3: exited with status 3: failure");
+        assert_eq!(failure_message("sleep 10", None, ""), "sleep 10: killed by a signal");
+    }
+}
@@ -19,31 +19,148 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::time::Duration;
 
-use gdk::{EventKey, ModifierType, keyval_to_unicode};
+use gdk::{EventButton, EventKey, ModifierType, keyval_to_unicode};
 use gdk::enums::key::{Escape, Tab, ISO_Left_Tab};
-use gtk::{Inhibit, LabelExt};
-use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use gtk::{self, Continue, Inhibit, LabelExt, WidgetExt};
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand, Value};
+use mg_settings::errors::Error;
 use mg_settings::key::Key::{self, Char};
 
 use app::{
+    CommandSource,
+    Mappings,
     Mg,
     Mode,
+    ModesHash,
     Msg,
     BLOCKING_INPUT_MODE,
     COMMAND_MODE,
     INPUT_MODE,
 };
-use app::ShortcutCommand::{Complete, Incomplete};
-use key_converter::gdk_key_to_key;
+use app::Msg::ShortcutTimeout;
+use app::ShortcutCommand::{Complete, Incomplete, Silent};
+use app::timeout::{duration_to_milliseconds, parse_timeout_setting};
+use key_converter::{
+    gdk_key_to_key,
+    gdk_keycode_to_key,
+    has_super_modifier,
+    is_dead_or_compose_key,
+    keys_to_string,
+    normalize_key,
+};
+
+/// The name of the command declaring that a mapping is also matched by the physical keys.
+pub const KEYCODE_MAP_COMMAND: &str = "keycode-map";
+/// The name of the built-in setting matching all the mappings by the physical keys too.
+pub const MAP_BY_KEYCODE_SETTING: &str = "map-by-keycode";
+/// The name of the built-in setting showing the keys discarded because they match no mapping.
+pub const REPORT_UNMAPPED_KEYS_SETTING: &str = "report-unmapped-keys";
+/// The default number of seconds after which a partial shortcut is cleared.
+pub const SHORTCUT_TIMEOUT: u64 = 3;
+/// The name of the built-in setting for the number of seconds after which a partial shortcut is
+/// cleared, 0 meaning that it is never cleared.
+pub const SHORTCUT_TIMEOUT_SETTING: &str = "shortcut-timeout";
+
+/// The separator between the action of a mapping and its description in the config, which is
+/// followed by the description between quotes.
+pub const DESCRIPTION_SEPARATOR: &str = " -- ";
+
+/// The action of a mapping with its optional description.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    /// The action executed when the keys are pressed.
+    pub action: String,
+    /// The description of the mapping, written after the action and `--` between quotes in the
+    /// config.
+    pub description: Option<String>,
+}
+
+impl Mapping {
+    /// Create a mapping from an action followed by an optional description between quotes after
+    /// `--`, like `:goto-definition<Enter> -- "Go to definition"`.
+    /// A trailing quoted string without the separator is part of the action, like in
+    /// `open "foo"`.
+    pub fn new(action: &str) -> Self {
+        let trimmed = action.trim_right();
+        // NOTE: the separator can also appear in the action or in the description, so the
+        // description starts at the first separator followed by a single quoted string.
+        for (index, _) in trimmed.match_indices(DESCRIPTION_SEPARATOR) {
+            let command = trimmed[..index].trim_right();
+            let description = unquote(&trimmed[index + DESCRIPTION_SEPARATOR.len()..]);
+            if let Some(description) = description {
+                if !command.is_empty() {
+                    return Mapping {
+                        action: command.to_string(),
+                        description: Some(description),
+                    };
+                }
+            }
+        }
+        Mapping {
+            action: action.to_string(),
+            description: None,
+        }
+    }
+
+    /// Get the text of the mapping in the config syntax, i.e. the action followed by the quoted
+    /// description, if any.
+    pub fn to_config(&self) -> String {
+        match self.description {
+            Some(ref description) => format!("{}{}{}", self.action, DESCRIPTION_SEPARATOR, quote(description)),
+            None => self.action.clone(),
+        }
+    }
+}
+
+/// Quote a string, escaping the quotes and the backslashes it contains with a backslash.
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Get the content of a string quoted with quote().
+/// Return None if the text is not a quoted string.
+pub fn unquote(text: &str) -> Option<String> {
+    if text.len() < 2 || !text.starts_with('"') || !text.ends_with('"') {
+        return None;
+    }
+    let mut result = String::new();
+    let mut chars = text[1..text.len() - 1].chars();
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' => result.push(chars.next()?),
+            '"' => return None,
+            _ => result.push(character),
+        }
+    }
+    Some(result)
+}
+
+/// Get the description of a mapping, or the one of its command when it executes a single
+/// command.
+/// The descriptions of the commands are given by command_descriptions(), so that they are only
+/// computed once when describing many mappings.
+pub fn describe_mapping(mapping: &Mapping, command_descriptions: &HashMap<String, String>) -> Option<String> {
+    if mapping.description.is_some() {
+        return mapping.description.clone();
+    }
+    let command = mapping.action.trim_left_matches(':').trim_right_matches("<Enter>").trim();
+    if command.is_empty() || command.contains(char::is_whitespace) || command.contains(';') {
+        return None;
+    }
+    command_descriptions.get(command)
+        .filter(|help| !help.is_empty())
+        .cloned()
+}
 
 /// Convert a shortcut of keys to a `String`.
 pub fn shortcut_to_string(keys: &[Key], show_count: bool) -> String {
     if show_count {
-        let strings: Vec<_> = keys.iter().map(ToString::to_string).collect();
-        strings.join("")
+        keys_to_string(keys)
     }
     else {
         String::new()
@@ -54,16 +171,63 @@ impl<COMM, SETT> Mg<COMM, SETT>
     where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
           SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
 {
+    /// Match the mapping of these keys in the mode having this prefix by the physical keys too,
+    /// i.e. by the keys of the first group of the keyboard layout.
+    pub fn add_keycode_mapping(&mut self, mode_prefix: &str, keys: Vec<Key>) {
+        self.insert_keycode_mapping(mode_prefix, keys);
+    }
+
+    /// Add a keycode mapping defined in the config, to remove it when the config is reloaded.
+    /// A mapping which was already added at runtime is not removed.
+    pub fn add_config_keycode_mapping(&mut self, mode_prefix: &str, keys: Vec<Key>) {
+        if let Some(mapping) = self.insert_keycode_mapping(mode_prefix, keys) {
+            self.model.config_keycode_mappings.insert(mapping);
+        }
+    }
+
+    /// Insert the keycode mapping and return it if it was not already there.
+    fn insert_keycode_mapping(&mut self, mode_prefix: &str, keys: Vec<Key>) -> Option<(&'static str, Vec<Key>)> {
+        let mode_name = self.model.modes.get(mode_prefix).map(|mode| mode.name);
+        match mode_name {
+            Some(mode_name) => {
+                let keys: Vec<_> = keys.into_iter().map(normalize_key).collect();
+                if self.model.keycode_mappings.insert((mode_name, keys.clone())) {
+                    Some((mode_name, keys))
+                }
+                else {
+                    None
+                }
+            },
+            None => {
+                self.error(Error::Msg(format!("{}: Unknown mode prefix {}", KEYCODE_MAP_COMMAND, mode_prefix)));
+                None
+            },
+        }
+    }
+
     /// Add the key to the current shortcut.
+    /// The shortcut is cleared if no other key is pressed before the `shortcut-timeout`.
     pub fn add_to_shortcut(&mut self, key: Key) {
         self.model.current_shortcut.push(key);
         self.update_shortcut_label();
+        self.model.shortcut_generation += 1;
+        if let Some(duration) = self.model.shortcut_timeout {
+            let stream = self.model.relm.stream().clone();
+            let generation = self.model.shortcut_generation;
+            let milliseconds = duration_to_milliseconds(duration);
+            gtk::timeout_add(milliseconds, move || {
+                stream.emit(ShortcutTimeout(generation));
+                Continue(false)
+            });
+        }
     }
 
     /// Clear the current shortcut buffer.
     pub fn clear_shortcut(&mut self) {
         self.model.current_shortcut.clear();
         self.update_shortcut_label();
+        // NOTE: cancel the timeout of the shortcut.
+        self.model.shortcut_generation += 1;
     }
 
     /// Handle a shortcut in input mode.
@@ -84,6 +248,10 @@ impl<COMM, SETT> Mg<COMM, SETT>
     /// Check if the key should be inhibitted for the shortcut.
     pub fn inhibit_handle_shortcut(current_mode: &Rc<Cell<Mode>>, key: &EventKey) -> Inhibit {
         let keyval = key.get_keyval();
+        // NOTE: the dead keys and the compose key go to the input method.
+        if is_dead_or_compose_key(keyval) {
+            return Inhibit(false);
+        }
         let alt_pressed = key.get_state().contains(ModifierType::MOD1_MASK);
         let control_pressed = key.get_state().contains(ModifierType::CONTROL_MASK);
         let shift_pressed = key.get_state().contains(ModifierType::SHIFT_MASK);
@@ -100,54 +268,183 @@ impl<COMM, SETT> Mg<COMM, SETT>
     /// Handle a possible input of a shortcut.
     pub fn handle_shortcut(&mut self, key: &EventKey) -> Option<Msg<COMM, SETT>> {
         let keyval = key.get_keyval();
+        if is_dead_or_compose_key(keyval) {
+            return None;
+        }
         let alt_pressed = key.get_state().contains(ModifierType::MOD1_MASK);
         let control_pressed = key.get_state().contains(ModifierType::CONTROL_MASK);
         let shift_pressed = key.get_state().contains(ModifierType::SHIFT_MASK);
         if !self.model.entry_shown || alt_pressed || control_pressed || shift_pressed || keyval == Tab ||
             keyval == ISO_Left_Tab
         {
-            if let Some(key) = gdk_key_to_key(key) {
-                self.add_to_shortcut(key);
+            if let Some(converted_key) = gdk_key_to_key(key) {
+                // NOTE: the shortcut is cleared as soon as it matches no mapping, so the previous
+                // keys, if any, matched the prefix of a mapping.
+                let matched_prefix = !self.shortcut_without_prefix().is_empty();
+                self.add_to_shortcut(converted_key);
+                // NOTE: the keyval is tried first, then the physical key.
+                if self.no_possible_shortcut() {
+                    self.use_keycode_for_last_key(key);
+                }
                 let action = {
-                    let mut current_mode = self.model.mode_string.clone();
-                    // The input modes have the same mappings as the command mode.
-                    if current_mode == INPUT_MODE || current_mode == BLOCKING_INPUT_MODE {
-                        current_mode = COMMAND_MODE.to_string();
-                    }
+                    let current_mode = self.mapping_mode();
                     self.model.mappings.get(&current_mode.as_ref())
-                        .and_then(|mappings| mappings.get(self.shortcut_without_prefix()).cloned())
+                        .and_then(|mappings| mappings.get(self.shortcut_without_prefix()))
+                        .map(|mapping| mapping.action.clone())
                 };
                 if let Some(action) = action {
                     let prefix = self.shortcut_prefix();
-                    // FIXME: this is copied a couple of lines below.
-                    if !self.model.entry_shown {
-                        // TODO: document why we need this.
-                        self.reset();
-                    }
-                    self.clear_shortcut();
-                    match self.action_to_command(&action) {
-                        Complete(command) => {
-                            return self.handle_command(Some(command), false, prefix);
-                        },
-                        Incomplete(command) => {
-                            self.input_command(command);
-                            self.show_completion();
-                        },
-                    }
+                    return self.execute_action(&action, prefix);
                 }
                 else if self.no_possible_shortcut() {
+                    let discarded_keys = self.shortcut_without_prefix().to_vec();
                     let current_mode = self.model.current_mode.get();
                     if current_mode != Mode::Input && !self.model.entry_shown {
                         // TODO: document why we need this.
                         self.reset();
                     }
                     self.clear_shortcut();
+                    // NOTE: a key is only reported after a key matching the prefix of a mapping,
+                    // since a single unmapped key can be meant for the main widget.
+                    if self.model.report_unmapped_keys && matched_prefix {
+                        self.report_unmapped_keys(&discarded_keys);
+                    }
                 }
             }
+            else if self.model.report_unmapped_keys && has_super_modifier(key.get_state()) {
+                self.info("unsupported key: the super modifier cannot be mapped");
+                self.window.error_bell();
+            }
         }
         None
     }
 
+    /// Execute the action of a mapping.
+    fn execute_action(&mut self, action: &str, prefix: Option<u32>) -> Option<Msg<COMM, SETT>> {
+        let keys = self.model.current_shortcut.clone();
+        // FIXME: this is copied a couple of lines below.
+        if !self.model.entry_shown {
+            // TODO: document why we need this.
+            self.reset();
+        }
+        self.clear_shortcut();
+        match self.action_to_command(action) {
+            Complete(command) => {
+                let command = self.expand_env(&command);
+                self.queue_next_key_presses();
+                self.model.command_source = CommandSource::Mapping { keys };
+                let msg = self.handle_command(Some(command), false, prefix);
+                self.model.command_source = CommandSource::Typed;
+                msg
+            },
+            Incomplete(command) => {
+                self.input_command(command);
+                self.show_completion();
+                None
+            },
+            Silent(command) => {
+                let command = self.expand_env(&command);
+                self.queue_next_key_presses();
+                self.model.command_source = CommandSource::Mapping { keys };
+                // NOTE: only the messages shown while the command is executed are suppressed; the
+                // errors are always shown.
+                self.model.silent = true;
+                let msg = self.handle_command(Some(command), false, prefix);
+                self.model.silent = false;
+                self.model.command_source = CommandSource::Typed;
+                msg
+            },
+        }
+    }
+
+    /// Handle a possible mouse button mapping.
+    pub fn handle_button_press(&mut self, event: &EventButton) -> Option<Msg<COMM, SETT>> {
+        let modifiers = event.get_state() & mouse_modifiers();
+        let action = self.model.mouse_mappings.get(self.mapping_mode().as_str())
+            .and_then(|mappings| mappings.get(&(event.get_button(), modifiers)).cloned());
+        action.and_then(|action| self.execute_action(&action, None))
+    }
+
+    /// Get the description of the mapping of the keys in the mode.
+    /// A mapping without description executing a single command gets the description of this
+    /// command.
+    pub fn mapping_description(&self, mode: &str, keys: &[Key]) -> Option<String> {
+        let keys: Vec<_> = keys.iter().cloned().map(normalize_key).collect();
+        self.model.mappings.get(mode)
+            .and_then(|mappings| mappings.get(&keys))
+            .and_then(|mapping| describe_mapping(mapping, &self.all_command_descriptions()))
+    }
+
+    /// Get a snapshot of the mappings of every mode, keyed by the mode name, with their keys and
+    /// action.
+    pub fn all_mappings(&self) -> HashMap<String, Vec<(Vec<Key>, String)>> {
+        self.model.mappings.keys()
+            .map(|&mode| (mode.to_string(), self.mappings(mode)))
+            .collect()
+    }
+
+    /// Get the keys of the mappings of the mode whose action contains `action`, e.g. to show
+    /// which keys run `:quit` in a help screen.
+    /// The mode is specified by its name or its prefix.
+    pub fn find_mapping(&self, mode: &str, action: &str) -> Vec<Vec<Key>> {
+        find_mapping(&self.mappings(mode), action)
+    }
+
+    /// Get a snapshot of the mappings of the mode, with their keys and action.
+    /// The mode is specified by its name or its prefix.
+    pub fn mappings(&self, mode: &str) -> Vec<(Vec<Key>, String)> {
+        mode_mappings(&self.model.mappings, &self.model.modes, mode)
+    }
+
+    /// Get the mode from which the mappings are taken.
+    pub fn mapping_mode(&self) -> String {
+        let current_mode = self.model.mode_string.clone();
+        // The input modes have the same mappings as the command mode.
+        if current_mode == INPUT_MODE || current_mode == BLOCKING_INPUT_MODE {
+            COMMAND_MODE.to_string()
+        }
+        else {
+            current_mode
+        }
+    }
+
+    /// Add a mapping for a mouse button, with the specified modifiers, in the specified mode.
+    pub fn map_mouse_button(&mut self, mode: &'static str, button: u32, modifiers: ModifierType, action: String) {
+        self.model.mouse_mappings.entry(mode)
+            .or_insert_with(HashMap::new)
+            .insert((button, modifiers & mouse_modifiers()), action);
+        self.update_mapped_buttons();
+    }
+
+    /// Check if the button press event should be inhibitted because it triggers a mouse mapping.
+    pub fn inhibit_button_press(mapped_buttons: &Rc<RefCell<HashSet<(u32, ModifierType)>>>, event: &EventButton)
+        -> Inhibit
+    {
+        let modifiers = event.get_state() & mouse_modifiers();
+        Inhibit(mapped_buttons.borrow().contains(&(event.get_button(), modifiers)))
+    }
+
+    /// Update the buttons having a mouse mapping in the current mode, which are shared with the
+    /// button-press-event handler.
+    pub fn update_mapped_buttons(&self) {
+        let buttons = self.model.mouse_mappings.get(self.mapping_mode().as_str())
+            .map(|mappings| mappings.keys().cloned().collect())
+            .unwrap_or_default();
+        *self.model.mapped_buttons.borrow_mut() = buttons;
+    }
+
+    /// Check if the mappings starting with the current shortcut can be matched by the physical
+    /// keys.
+    fn can_match_by_keycode(&self) -> bool {
+        if self.model.map_by_keycode {
+            return true;
+        }
+        let mode = self.mapping_mode();
+        let shortcut = self.shortcut_without_prefix();
+        self.model.keycode_mappings.iter()
+            .any(|&(mode_name, ref keys)| mode_name == mode && keys.starts_with(shortcut))
+    }
+
     /// Check if there are no possible shortcuts.
     fn no_possible_shortcut(&self) -> bool {
         if let Some(mappings) = self.model.mappings.get(&self.model.mode_string.as_ref()) {
@@ -196,6 +493,69 @@ impl<COMM, SETT> Mg<COMM, SETT>
         &self.model.current_shortcut[start..]
     }
 
+    /// Set whether all the mappings are matched by the physical keys too from the value of the
+    /// `map-by-keycode` setting.
+    pub fn set_map_by_keycode(&mut self, value: &Value) {
+        match *value {
+            Value::Bool(map_by_keycode) => self.model.map_by_keycode = map_by_keycode,
+            _ => self.error(Error::Msg(format!("Expecting a boolean for setting {}", MAP_BY_KEYCODE_SETTING))),
+        }
+    }
+
+    /// Show the keys discarded because they match no mapping and ring the error bell, which can
+    /// be a visual bell depending on the GTK+ settings.
+    fn report_unmapped_keys(&mut self, keys: &[Key]) {
+        self.info(&format!("no mapping: {}", keys_to_string(keys)));
+        self.window.error_bell();
+    }
+
+    /// Set whether the keys matching no mapping, and the unsupported keys pressed with the super
+    /// modifier, are reported from the value of the `report-unmapped-keys` setting.
+    pub fn set_report_unmapped_keys(&mut self, value: &Value) {
+        match *value {
+            Value::Bool(report) => self.model.report_unmapped_keys = report,
+            _ => self.error(Error::Msg(format!("Expecting a boolean for setting {}", REPORT_UNMAPPED_KEYS_SETTING))),
+        }
+    }
+
+    /// Set the duration after which a partial shortcut is cleared from the value of the
+    /// `shortcut-timeout` setting.
+    pub fn set_shortcut_timeout(&mut self, value: &Value) {
+        match parse_shortcut_timeout(value) {
+            Ok(timeout) => self.model.shortcut_timeout = timeout,
+            Err(message) => self.error(Error::Msg(message)),
+        }
+    }
+
+    /// Clear the partial shortcut when its timeout expires.
+    /// The timeout is ignored when a key was added to the shortcut or the shortcut was cleared
+    /// since.
+    pub fn shortcut_timeout(&mut self, generation: u64) {
+        if generation == self.model.shortcut_generation {
+            self.clear_shortcut();
+        }
+    }
+
+    /// Replace the last key of the shortcut by the physical key when it can match a mapping.
+    fn use_keycode_for_last_key(&mut self, key: &EventKey) {
+        let physical_key =
+            match gdk_keycode_to_key(key) {
+                Some(physical_key) => physical_key,
+                None => return,
+            };
+        if let Some(last_key) = self.model.current_shortcut.pop() {
+            if last_key != physical_key {
+                self.model.current_shortcut.push(physical_key);
+                if !self.no_possible_shortcut() && self.can_match_by_keycode() {
+                    self.update_shortcut_label();
+                    return;
+                }
+                self.model.current_shortcut.pop();
+            }
+            self.model.current_shortcut.push(last_key);
+        }
+    }
+
     // TODO: remove this when updating the model in methods outside the trait will update the view.
     /// Update the shortcut label.
     fn update_shortcut_label(&self) {
@@ -203,6 +563,11 @@ impl<COMM, SETT> Mg<COMM, SETT>
     }
 }
 
+/// The modifiers that are considered for the mouse mappings.
+fn mouse_modifiers() -> ModifierType {
+    ModifierType::CONTROL_MASK | ModifierType::MOD1_MASK | ModifierType::SHIFT_MASK
+}
+
 fn is_digit(key: &&Key) -> bool {
     !is_not_digit(key)
 }
@@ -215,3 +580,98 @@ fn is_not_digit(key: &Key) -> bool {
         true
     }
 }
+
+/// Convert the value of the `shortcut-timeout` setting.
+/// None means that a partial shortcut is never cleared.
+fn parse_shortcut_timeout(value: &Value) -> Result<Option<Duration>, String> {
+    parse_timeout_setting(SHORTCUT_TIMEOUT_SETTING, value)
+}
+
+/// Get the keys of the mappings whose action contains `action`.
+fn find_mapping(mappings: &[(Vec<Key>, String)], action: &str) -> Vec<Vec<Key>> {
+    mappings.iter()
+        .filter(|&&(_, ref mapping_action)| mapping_action.contains(action))
+        .map(|&(ref keys, _)| keys.clone())
+        .collect()
+}
+
+/// Get the mappings of the mode, sorted by keys, with their action.
+/// The mode is specified by its name or its prefix.
+fn mode_mappings(mappings: &Mappings, modes: &ModesHash, mode: &str) -> Vec<(Vec<Key>, String)> {
+    let mode_name = modes.get(mode)
+        .map(|mode| mode.name)
+        .unwrap_or(mode);
+    let mut mode_mappings: Vec<_> = mappings.get(mode_name)
+        .map(|mappings| mappings.iter()
+             .map(|(keys, mapping)| (keys.clone(), mapping.action.clone()))
+             .collect())
+        .unwrap_or_default();
+    mode_mappings.sort_by_key(|&(ref keys, _)| keys_to_string(keys));
+    mode_mappings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use mg_settings::Value;
+    use mg_settings::key::Key::Char;
+
+    use Mode;
+    use app::{Mappings, ModesHash};
+    use super::{Mapping, find_mapping, mode_mappings, parse_shortcut_timeout};
+
+    fn mappings() -> (Mappings, ModesHash) {
+        let mut normal_mappings = HashMap::new();
+        normal_mappings.insert(vec![Char('q')], Mapping::new("quit"));
+        normal_mappings.insert(vec![Char('g'), Char('o')], Mapping::new("open -- \"Open a page\""));
+        normal_mappings.insert(vec![Char('Q')], Mapping::new(":quit<Enter>"));
+        let mut insert_mappings = HashMap::new();
+        insert_mappings.insert(vec![Char('x')], Mapping::new("normal-mode"));
+        let mut mappings = HashMap::new();
+        mappings.insert("normal", normal_mappings);
+        mappings.insert("insert", insert_mappings);
+        let mut modes = HashMap::new();
+        modes.insert("i", Mode { name: "insert", prefix: "i", show_count: false });
+        (mappings, modes)
+    }
+
+    #[test]
+    fn test_mode_mappings() {
+        let (mappings, modes) = mappings();
+        assert_eq!(mode_mappings(&mappings, &modes, "normal"), vec![
+            (vec![Char('Q')], ":quit<Enter>".to_string()),
+            (vec![Char('g'), Char('o')], "open".to_string()),
+            (vec![Char('q')], "quit".to_string()),
+        ]);
+        let insert_mappings = vec![(vec![Char('x')], "normal-mode".to_string())];
+        assert_eq!(mode_mappings(&mappings, &modes, "insert"), insert_mappings);
+        assert_eq!(mode_mappings(&mappings, &modes, "i"), insert_mappings);
+        assert_eq!(mode_mappings(&mappings, &modes, "visual"), vec![]);
+    }
+
+    #[test]
+    fn test_find_mapping() {
+        let (mappings, modes) = mappings();
+        let normal_mappings = mode_mappings(&mappings, &modes, "normal");
+        assert_eq!(find_mapping(&normal_mappings, "quit"), vec![vec![Char('Q')], vec![Char('q')]]);
+        assert_eq!(find_mapping(&normal_mappings, "open"), vec![vec![Char('g'), Char('o')]]);
+        assert_eq!(find_mapping(&normal_mappings, "Open a page"), Vec::<Vec<_>>::new());
+    }
+
+    #[test]
+    fn test_parse_shortcut_timeout() {
+        assert_eq!(parse_shortcut_timeout(&Value::Int(0)), Ok(None));
+        assert_eq!(parse_shortcut_timeout(&Value::Int(3)), Ok(Some(Duration::from_secs(3))));
+    }
+
+    #[test]
+    fn test_parse_invalid_shortcut_timeout() {
+        let error = Err("Expecting an integer between 0 and 86400 for setting shortcut-timeout".to_string());
+        assert_eq!(parse_shortcut_timeout(&Value::Int(-1)), error);
+        assert_eq!(parse_shortcut_timeout(&Value::Int(5_000_000)), error);
+        assert_eq!(parse_shortcut_timeout(&Value::Float(1.5)), error);
+        assert_eq!(parse_shortcut_timeout(&Value::Str("1".to_string())), error);
+    }
+}
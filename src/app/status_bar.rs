@@ -19,10 +19,17 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use gdk::{RGBA, SELECTION_PRIMARY};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gdk::{EventKey, ModifierType, RGBA, SELECTION_PRIMARY};
+use glib::Cast;
 use gtk;
 use gtk::{
+    Allocation,
     BoxExt,
+    Entry,
     Clipboard,
     ClipboardExt,
     CssProvider,
@@ -30,6 +37,9 @@ use gtk::{
     EditableExt,
     EditableSignals,
     EntryExt,
+    ContainerExt,
+    Continue,
+    Label,
     LabelExt,
     OrientableExt,
     PackType,
@@ -46,8 +56,33 @@ use relm_attributes::widget;
 use self::Msg::*;
 use self::ItemMsg::{Color, Text};
 
+/// The default priority of a status bar item.
+pub const DEFAULT_ITEM_PRIORITY: i32 = 0;
+/// The priority of the mode item.
+pub const MODE_ITEM_PRIORITY: i32 = 10;
+/// The priority of the shortcut item.
+pub const SHORTCUT_ITEM_PRIORITY: i32 = 20;
+/// The priority of the background errors item.
+pub const BACKGROUND_ERRORS_ITEM_PRIORITY: i32 = 25;
+/// The priority of the message item.
+pub const MESSAGE_ITEM_PRIORITY: i32 = 30;
+
+/// The horizontal padding around an item.
+const ITEM_PADDING: i32 = 3;
+
+/// The priorities of the status bar items, by label.
+type ItemPriorities = Rc<RefCell<HashMap<Label, i32>>>;
+
+thread_local! {
+    // NOTE: this is updated synchronously because it is needed in the key press signal handler.
+    static PREEDIT_ACTIVE: Cell<bool> = Cell::new(false);
+    static INPUT_METHOD_CONSUMED_KEY: Cell<bool> = Cell::new(false);
+}
+
 #[derive(Msg)]
 pub enum Msg {
+    /// Activate the command entry, like when Enter is pressed.
+    ActivateEntry,
     Copy,
     Cut,
     DeleteNextChar,
@@ -58,26 +93,50 @@ pub enum Msg {
     EntryChanged(Option<String>),
     EntryText(String),
     EntryShown(bool),
+    EntryVisibility(bool),
     Identifier(String),
+    IdentifierVisible(bool),
+    /// Set the priority of a status bar item.
+    /// When there is not enough space, the items with the lowest priority are hidden first.
+    ItemPriority(Label, i32),
     NextChar,
     NextWord,
     Paste,
     PasteSelection,
     PreviousChar,
     PreviousWord,
+    SelectAll,
     ShowIdentifier,
     SmartHome,
 }
 
 pub struct Model {
+    identifier_hidden: bool,
     identifier_label: &'static str,
     identifier_visible: bool,
+    // NOTE: shared with the size-allocate handler.
+    item_priorities: ItemPriorities,
     relm: Relm<StatusBar>,
 }
 
 #[widget]
 impl Widget for StatusBar {
     fn init_view(&mut self) {
+        let identifier_label = self.identifier_label.clone();
+        let item_priorities = self.model.item_priorities.clone();
+        self.status_bar_box.connect_size_allocate(move |status_bar, allocation|
+            layout_items(status_bar, allocation, &identifier_label, &item_priorities));
+        let stream = self.model.relm.stream().clone();
+        self.command_entry.connect_preedit_changed(move |entry, preedit| {
+            let was_composing = is_composing();
+            PREEDIT_ACTIVE.with(|preedit_active| preedit_active.set(!preedit.is_empty()));
+            // NOTE: the changes of the text are ignored while composing, so the text committed by
+            // the input method is sent once the composition ends.
+            if was_composing && preedit.is_empty() {
+                stream.emit(EntryChanged(entry.get_text()));
+            }
+        });
+
         // Adjust the look of the entry.
         let style_context = self.command_entry.get_style_context().unwrap();
         // TODO: remove the next line when relm supports css.
@@ -89,8 +148,10 @@ impl Widget for StatusBar {
 
     fn model(relm: &Relm<Self>, _: ()) -> Model {
         Model {
+            identifier_hidden: false,
             identifier_label: ":",
             identifier_visible: false,
+            item_priorities: Rc::new(RefCell::new(HashMap::new())),
             relm: relm.clone(),
         }
     }
@@ -108,6 +169,12 @@ impl Widget for StatusBar {
         }
     }
 
+    /// Set whether the identifier can be shown.
+    /// When false, the identifier stays hidden even when the entry is shown.
+    pub fn set_identifier_visible(&mut self, visible: bool) {
+        self.model.identifier_hidden = !visible;
+    }
+
     /// Show the identifier.
     fn show_identifier(&mut self) {
         self.model.identifier_visible = true;
@@ -115,6 +182,9 @@ impl Widget for StatusBar {
 
     fn update(&mut self, msg: Msg) {
         match msg {
+            ActivateEntry => {
+                self.command_entry.activate();
+            },
             Copy => self.copy(),
             Cut => self.cut(),
             DeleteNextChar => self.delete_next_char(),
@@ -124,13 +194,17 @@ impl Widget for StatusBar {
             EntryActivate(_) | EntryChanged(_) => (), // NOTE: to be listened by the user.
             EntryShown(visible) => self.set_entry_shown(visible),
             EntryText(input) => self.set_input(&input),
+            EntryVisibility(visible) => self.command_entry.set_visibility(visible),
             Identifier(identifier) => self.set_identifier(&identifier),
+            IdentifierVisible(visible) => self.set_identifier_visible(visible),
+            ItemPriority(label, priority) => self.set_item_priority(label, priority),
             NextChar => self.next_char(),
             NextWord => self.next_word(),
             Paste => self.paste(),
             PasteSelection => self.paste_selection(),
             PreviousChar => self.previous_char(),
             PreviousWord => self.previous_word(),
+            SelectAll => self.command_entry.select_region(0, -1),
             ShowIdentifier => self.show_identifier(),
             SmartHome => self.smart_home(),
         }
@@ -138,13 +212,14 @@ impl Widget for StatusBar {
 
     view! {
         #[container]
+        #[name="status_bar_box"]
         gtk::Box {
             property_height_request: 20, // TODO: is this still useful?
             orientation: Horizontal,
             #[name="identifier_label"]
             gtk::Label {
                 text: self.model.identifier_label,
-                visible: self.model.identifier_visible,
+                visible: self.model.identifier_visible && !self.model.identifier_hidden,
             },
             #[name="command_entry"]
             gtk::Entry {
@@ -323,6 +398,18 @@ impl StatusBar {
         self.command_entry.set_position(position as i32);
     }
 
+    /// Set the priority of a status bar item.
+    fn set_item_priority(&self, label: Label, priority: i32) {
+        let is_new = self.model.item_priorities.borrow_mut().insert(label.clone(), priority).is_none();
+        if is_new {
+            let item_priorities = self.model.item_priorities.clone();
+            label.connect_destroy(move |label| {
+                item_priorities.borrow_mut().remove(label);
+            });
+        }
+        label.queue_resize();
+    }
+
     /// Seth the prefix identifier shown at the left of the command entry.
     fn set_identifier(&self, identifier: &str) {
         self.identifier_label.set_text(identifier);
@@ -357,6 +444,77 @@ impl StatusBar {
     }
 }
 
+/// Show the items by decreasing priority while they fit and hide the others.
+/// The items are labels which are ellipsized, so they only take their minimum width when there is
+/// not enough space; an item is only hidden when even its minimum width does not fit.
+fn layout_items(status_bar: &gtk::Box, allocation: &Allocation, identifier_label: &Label,
+    item_priorities: &ItemPriorities)
+{
+    let mut items = vec![];
+    let mut available_width = allocation.width;
+    {
+        let priorities = item_priorities.borrow();
+        for child in status_bar.get_children() {
+            match child.clone().downcast::<Label>() {
+                Ok(ref label) if label != identifier_label => {
+                    let priority = priorities.get(label).cloned().unwrap_or(DEFAULT_ITEM_PRIORITY);
+                    items.push((priority, label.clone()));
+                },
+                _ if child.get_visible() => available_width -= child.get_preferred_width().0,
+                _ => (),
+            }
+        }
+    }
+    // NOTE: sort by decreasing priority, keeping the packing order for the same priority.
+    items.sort_by(|&(priority1, _), &(priority2, _)| priority2.cmp(&priority1));
+    let mut changes = vec![];
+    for (_, label) in items {
+        let width = label.get_preferred_width().0 + 2 * ITEM_PADDING;
+        let visible = width <= available_width;
+        if visible {
+            available_width -= width;
+        }
+        if label.get_visible() != visible {
+            changes.push((label, visible));
+        }
+    }
+    if !changes.is_empty() {
+        // NOTE: changing the visibility queues a new allocation, so it is done after this one.
+        gtk::idle_add(move || {
+            for &(ref label, visible) in &changes {
+                label.set_visible(visible);
+            }
+            Continue(false)
+        });
+    }
+}
+
+/// Check if an input method is composing text in the command entry.
+pub fn is_composing() -> bool {
+    PREEDIT_ACTIVE.with(Cell::get)
+}
+
+/// Give the key to the input method of the command entry when it has the focus and the key is not
+/// a chord with control or alt.
+/// Returns true if the input method consumed the key, which must then not be handled as a
+/// shortcut nor given to the entry again.
+pub fn filter_input_method_key_press(entry: &Entry, key: &EventKey) -> bool {
+    let chord = key.get_state().intersects(ModifierType::CONTROL_MASK | ModifierType::MOD1_MASK);
+    let consumed = !chord && entry.is_visible() && entry.has_focus() && entry.im_context_filter_keypress(key);
+    INPUT_METHOD_CONSUMED_KEY.with(|consumed_key| consumed_key.set(consumed));
+    consumed
+}
+
+/// Forget that the input method consumed the last key, for the keys not given to it.
+pub fn reset_input_method_consumed_key() {
+    INPUT_METHOD_CONSUMED_KEY.with(|consumed_key| consumed_key.set(false));
+}
+
+/// Check if the input method consumed the last key given by `filter_input_method_key_press()`.
+pub fn input_method_consumed_key() -> bool {
+    INPUT_METHOD_CONSUMED_KEY.with(Cell::get)
+}
+
 #[derive(Msg)]
 pub enum ItemMsg {
     /// Set the color of the status bar item.
@@ -386,7 +544,7 @@ impl Widget for StatusBarItem {
             ellipsize: EllipsizeMode::End,
             child: {
                 pack_type: PackType::End,
-                padding: 3,
+                padding: ITEM_PADDING as u32,
             },
         }
     }
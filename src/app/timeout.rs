@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::time::Duration;
+
+use mg_settings::Value;
+
+/// The maximum value, in seconds, of the timeout settings (one day).
+pub const MAX_TIMEOUT_SECONDS: i64 = 86_400;
+
+/// Convert a duration to the number of milliseconds expected by `gtk::timeout_add()`.
+/// The duration is saturated to the maximum number of milliseconds instead of overflowing.
+pub fn duration_to_milliseconds(duration: Duration) -> u32 {
+    let milliseconds = duration.as_secs()
+        .saturating_mul(1000)
+        .saturating_add(u64::from(duration.subsec_nanos() / 1_000_000));
+    if milliseconds > u64::from(u32::max_value()) {
+        u32::max_value()
+    }
+    else {
+        milliseconds as u32
+    }
+}
+
+/// Convert the value of a timeout setting, a number of seconds where 0 means no timeout.
+pub fn parse_timeout_setting(name: &str, value: &Value) -> Result<Option<Duration>, String> {
+    match *value {
+        Value::Int(0) => Ok(None),
+        Value::Int(seconds) if seconds > 0 && seconds <= MAX_TIMEOUT_SECONDS =>
+            Ok(Some(Duration::from_secs(seconds as u64))),
+        _ => Err(format!("Expecting an integer between 0 and {} for setting {}", MAX_TIMEOUT_SECONDS, name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mg_settings::Value;
+
+    use super::{MAX_TIMEOUT_SECONDS, duration_to_milliseconds, parse_timeout_setting};
+
+    #[test]
+    fn test_duration_to_milliseconds() {
+        assert_eq!(duration_to_milliseconds(Duration::from_millis(1500)), 1500);
+        assert_eq!(duration_to_milliseconds(Duration::from_secs(MAX_TIMEOUT_SECONDS as u64)), 86_400_000);
+        assert_eq!(duration_to_milliseconds(Duration::from_secs(5_000_000)), u32::max_value());
+        assert_eq!(duration_to_milliseconds(Duration::from_secs(u64::max_value())), u32::max_value());
+    }
+
+    #[test]
+    fn test_parse_timeout_setting() {
+        assert_eq!(parse_timeout_setting("timeout", &Value::Int(0)), Ok(None));
+        assert_eq!(parse_timeout_setting("timeout", &Value::Int(3)), Ok(Some(Duration::from_secs(3))));
+        assert_eq!(parse_timeout_setting("timeout", &Value::Int(MAX_TIMEOUT_SECONDS)),
+            Ok(Some(Duration::from_secs(86_400))));
+        let error = Err("Expecting an integer between 0 and 86400 for setting timeout".to_string());
+        assert_eq!(parse_timeout_setting("timeout", &Value::Int(MAX_TIMEOUT_SECONDS + 1)), error);
+        assert_eq!(parse_timeout_setting("timeout", &Value::Int(5_000_000)), error);
+        assert_eq!(parse_timeout_setting("timeout", &Value::Int(-1)), error);
+        assert_eq!(parse_timeout_setting("timeout", &Value::Str("1".to_string())), error);
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use mg_settings::{self, EnumFromStr, EnumMetaData, SettingCompletion, SpecialCommand};
+use mg_settings::errors::Error;
+
+use app::Mg;
+use app::alias::parse_definition;
+use app::command::split_commands;
+use app::help::command_names;
+
+/// The name of the command defining a user command.
+pub const USER_COMMAND_COMMAND: &str = "command";
+
+/// The maximum number of nested user commands, to stop the recursive definitions.
+const MAX_USER_COMMAND_DEPTH: usize = 10;
+
+/// The placeholder replaced by the arguments of the user command.
+const ARGS_PLACEHOLDER: &str = "<args>";
+
+impl<COMM, SETT> Mg<COMM, SETT>
+    where COMM: Clone + EnumFromStr + EnumMetaData + SpecialCommand + 'static,
+          SETT: Default + EnumMetaData + mg_settings::settings::Settings + SettingCompletion + 'static,
+{
+    /// Add a command executing the commands of the expansion, separated by `;`.
+    /// The arguments of the command replace `<args>` in the expansion.
+    pub fn add_user_command(&mut self, name: &str, expansion: &str) {
+        if command_names::<COMM>().iter().any(|command_name| command_name == name) {
+            self.warning(&format!("The command {} shadows an existing command", name));
+        }
+        self.model.user_commands.insert(name.to_string(), expansion.to_string());
+        self.update_command_completer();
+    }
+
+    /// Add a user command defined in the config, to remove it when the config is reloaded.
+    pub fn add_config_user_command(&mut self, name: &str, expansion: &str) {
+        self.model.config_user_commands.insert(name.to_string());
+        self.add_user_command(name, expansion);
+    }
+
+    /// Handle the `command name expansion` command.
+    pub fn user_command_definition(&mut self, arguments: &str) {
+        match parse_definition(arguments) {
+            Some((name, expansion)) => {
+                // NOTE: the command redefined at runtime is kept when the config is reloaded.
+                self.model.config_user_commands.remove(&name);
+                self.add_user_command(&name, &expansion);
+            },
+            None => self.error(Error::Msg("Argument required".to_string())),
+        }
+    }
+
+    /// Execute the commands of the user command.
+    pub fn run_user_command(&mut self, name: &str, arguments: &str, prefix: Option<u32>) {
+        let expansion =
+            match self.model.user_commands.get(name) {
+                Some(expansion) => expansion.clone(),
+                None => return,
+            };
+        if self.model.user_command_depth >= MAX_USER_COMMAND_DEPTH {
+            self.error(Error::Msg(format!("Too many nested user commands in {}", name)));
+        }
+        else {
+            let expansion = expansion.replace(ARGS_PLACEHOLDER, arguments);
+            self.model.user_command_depth += 1;
+            for sub_command in split_commands(&expansion) {
+                let sub_command = sub_command.trim().trim_left_matches(':').to_string();
+                if let Some(message) = self.handle_command(Some(sub_command), false, prefix) {
+                    self.model.relm.stream().emit(message);
+                }
+            }
+            self.model.user_command_depth -= 1;
+        }
+    }
+}
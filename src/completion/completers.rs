@@ -19,33 +19,191 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 
 use mg_settings::{EnumMetaData, SettingCompletion};
 
-use completion::{Completer, CompletionResult};
+use app::command_descriptions;
+use completion::{Column, Completer, CompletionCell, CompletionResult};
+use lexer::lex_command_line;
+
+/// The category of the entries for which the application did not specify one.
+pub const DEFAULT_CATEGORY: &str = "misc";
+/// The color of the category shown as the header of a group of entries.
+const CATEGORY_FOREGROUND: &str = "gray";
+
+/// A read-only completer listing all the settings with their current value and description.
+pub struct AllSettingsCompleter {
+    categories: HashMap<String, String>,
+    settings: Vec<(String, String, String)>,
+}
+
+impl AllSettingsCompleter {
+    /// Create a new completer from the names, values and descriptions of the settings.
+    pub fn new(mut settings: Vec<(String, String, String)>) -> Self {
+        settings.sort();
+        AllSettingsCompleter {
+            categories: HashMap::new(),
+            settings,
+        }
+    }
+
+    /// Group the settings by category.
+    pub fn categories(mut self, categories: &HashMap<String, String>) -> Self {
+        self.categories = categories.clone();
+        self
+    }
+}
+
+impl Completer for AllSettingsCompleter {
+    fn columns(&self) -> Vec<Column> {
+        grouped_columns(&self.categories, vec![Column::AllVisible, Column::AllVisible, Column::Expand])
+    }
+
+    fn complete_result(&self, value: &str) -> String {
+        format!("set {} ", value)
+    }
+
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        let input = input.trim().to_lowercase();
+        let settings = self.settings.iter()
+            .filter(|&&(ref setting, _, ref help)|
+                    setting.to_lowercase().contains(&input) ||
+                    help.to_lowercase().contains(&input));
+        if self.categories.is_empty() {
+            settings
+                .map(|&(ref col1, ref col2, ref col3)| CompletionResult::new(&[col1, col2, col3]))
+                .collect()
+        }
+        else {
+            let categories = &self.categories;
+            grouped_results(settings
+                .map(|&(ref col1, ref col2, ref col3)|
+                     (category(categories, col1), vec![col1.clone(), col2.clone(), col3.clone()]))
+                .collect())
+        }
+    }
+
+    fn text_column(&self) -> i32 {
+        grouped_text_column(&self.categories)
+    }
+}
+
+/// A completer for a command taking several arguments, each one completed by its own completer.
+/// The completer of an argument is only given the text of this argument and the selected
+/// completion replaces it, so that accepting it with `complete-accept-next` switches to the
+/// completer of the next argument.
+/// NOTE: the completers of the arguments must have the same columns as the first one.
+pub struct ArgumentsCompleter {
+    command: String,
+    completers: Vec<Box<Completer>>,
+    current_index: usize,
+    previous_arguments: String,
+}
+
+impl ArgumentsCompleter {
+    /// Create a new completer for the command from the completers of its arguments, in order.
+    pub fn new(command: &str, completers: Vec<Box<Completer>>) -> Self {
+        ArgumentsCompleter {
+            command: command.to_string(),
+            completers,
+            current_index: 0,
+            previous_arguments: String::new(),
+        }
+    }
+}
+
+impl Completer for ArgumentsCompleter {
+    fn columns(&self) -> Vec<Column> {
+        self.completers.first()
+            .map(|completer| completer.columns())
+            .unwrap_or_else(|| vec![Column::Expand, Column::Expand])
+    }
+
+    fn complete_result(&self, value: &str) -> String {
+        let argument =
+            match self.completers.get(self.current_index) {
+                Some(completer) => completer.complete_result(value),
+                None => value.to_string(),
+            };
+        format!("{} {}{}", self.command, self.previous_arguments, argument)
+    }
+
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        let tokens = lex_command_line(input);
+        // NOTE: after a whitespace, the next argument is being typed.
+        let (index, start) =
+            match tokens.last() {
+                Some(token) if token.span.end == input.len() => (tokens.len() - 1, token.span.start),
+                _ => (tokens.len(), input.len()),
+            };
+        self.current_index = index;
+        self.previous_arguments = input[..start].to_string();
+        match self.completers.get_mut(index) {
+            Some(completer) => completer.completions(&input[start..]),
+            None => vec![],
+        }
+    }
+
+    fn text_column(&self) -> i32 {
+        self.completers.get(self.current_index)
+            .or_else(|| self.completers.first())
+            .map(|completer| completer.text_column())
+            .unwrap_or(0)
+    }
+}
 
 /// A command completer.
 pub struct CommandCompleter<T: Clone> {
+    categories: HashMap<String, String>,
     metadata: Vec<(String, String)>,
     _phantom: PhantomData<T>,
 }
 
 impl<T: Clone + EnumMetaData> CommandCompleter<T> {
+    /// Complete the aliases, with their expansion as description.
+    pub fn aliases(mut self, aliases: &HashMap<String, String>) -> Self {
+        for (alias, expansion) in aliases {
+            self.metadata.push((alias.clone(), format!("alias: {}", expansion)));
+        }
+        self.metadata.sort();
+        self
+    }
+
+    /// Complete the application commands registered by the application, with their description.
+    pub fn app_commands(mut self, app_commands: &HashMap<String, String>) -> Self {
+        for (name, description) in app_commands {
+            self.metadata.push((name.clone(), description.clone()));
+        }
+        self.metadata.sort();
+        self
+    }
+
+    /// Group the commands by category.
+    /// The aliases and the commands defined by the user are in the default category.
+    pub fn categories(mut self, categories: &HashMap<String, String>) -> Self {
+        self.categories = categories.clone();
+        self
+    }
+
+    /// Complete the commands defined by the user, with their expansion as description.
+    pub fn user_commands(mut self, user_commands: &HashMap<String, String>) -> Self {
+        for (name, expansion) in user_commands {
+            self.metadata.push((name.clone(), format!("command: {}", expansion)));
+        }
+        self.metadata.sort();
+        self
+    }
+
     #[allow(unknown_lints, new_without_default_derive)]
     /// Create a new command completer.
+    /// The built-in commands shadowed by a command of the application are not included.
     pub fn new() -> CommandCompleter<T> {
-        let mut data: Vec<_> =
-            T::get_metadata().iter()
-                .filter(|&(_, metadata)| !metadata.completion_hidden)
-                .map(|(setting_name, metadata)| (setting_name.clone(), metadata.help_text.clone()))
-                .collect();
-        data.push(("map".to_string(), "Create a new key binding".to_string()));
-        data.push(("set".to_string(), "Change the value of a setting".to_string()));
-        data.push(("unmap".to_string(), "Delete a key binding".to_string()));
+        let mut data: Vec<_> = command_descriptions::<T>().into_iter().collect();
         data.sort();
         CommandCompleter {
+            categories: HashMap::new(),
             metadata: data,
             _phantom: PhantomData,
         }
@@ -53,11 +211,160 @@ impl<T: Clone + EnumMetaData> CommandCompleter<T> {
 }
 
 impl<T: Clone> Completer for CommandCompleter<T> {
+    fn columns(&self) -> Vec<Column> {
+        grouped_columns(&self.categories, vec![Column::Expand, Column::Expand])
+    }
+
     fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
-        self.metadata.iter()
+        let commands = self.metadata.iter()
             .filter(|&&(ref command, ref help)|
                     command.to_lowercase().contains(&input) ||
-                    help.to_lowercase().contains(&input))
+                    help.to_lowercase().contains(&input));
+        if self.categories.is_empty() {
+            commands
+                .map(|&(ref col1, ref col2)| CompletionResult::new(&[col1, col2]))
+                .collect()
+        }
+        else {
+            let categories = &self.categories;
+            grouped_results(commands
+                .map(|&(ref col1, ref col2)| (category(categories, col1), vec![col1.clone(), col2.clone()]))
+                .collect())
+        }
+    }
+
+    fn text_column(&self) -> i32 {
+        grouped_text_column(&self.categories)
+    }
+}
+
+/// A read-only completer listing the help of the commands and settings.
+pub struct HelpCompleter {
+    categories: HashMap<String, String>,
+    entries: Vec<(String, String)>,
+}
+
+impl HelpCompleter {
+    /// Create a new help completer from the names and descriptions of the commands and settings.
+    pub fn new(entries: Vec<(String, String)>) -> Self {
+        HelpCompleter {
+            categories: HashMap::new(),
+            entries,
+        }
+    }
+
+    /// Group the entries by category.
+    /// The names of the settings are prefixed by `set`, as in the entries.
+    pub fn categories(mut self, categories: &HashMap<String, String>) -> Self {
+        self.categories = categories.clone();
+        self
+    }
+}
+
+impl Completer for HelpCompleter {
+    fn columns(&self) -> Vec<Column> {
+        grouped_columns(&self.categories, vec![Column::Expand, Column::Expand])
+    }
+
+    fn complete_result(&self, value: &str) -> String {
+        format!("help {}", value)
+    }
+
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        let input = input.trim().to_lowercase();
+        let input = input.as_str();
+        if self.categories.is_empty() {
+            return self.entries.iter()
+                .filter(|&&(ref name, ref help)|
+                        name.to_lowercase().contains(input) ||
+                        help.to_lowercase().contains(input))
+                .map(|&(ref col1, ref col2)| CompletionResult::new(&[col1, col2]))
+                .collect();
+        }
+        let categories = &self.categories;
+        let entries = self.entries.iter()
+            .map(|&(ref name, ref help)| (category(categories, name), vec![name.clone(), help.clone()]));
+        // NOTE: when the input is a category, only the entries of this category are shown.
+        let is_category = input == DEFAULT_CATEGORY ||
+            categories.values().any(|category| category.to_lowercase() == input);
+        let entries: Vec<_> =
+            if is_category {
+                entries
+                    .filter(|&(ref category, _)| category.to_lowercase() == input)
+                    .collect()
+            }
+            else {
+                entries
+                    .filter(|&(_, ref columns)|
+                            columns.iter().any(|column| column.to_lowercase().contains(input)))
+                    .collect()
+            };
+        grouped_results(entries)
+    }
+
+    fn text_column(&self) -> i32 {
+        grouped_text_column(&self.categories)
+    }
+}
+
+/// A completer listing the commands of the history.
+pub struct HistoryCompleter {
+    commands: Vec<String>,
+}
+
+impl HistoryCompleter {
+    /// Create a new history completer from the commands, in the order they are shown.
+    pub fn new(commands: Vec<String>) -> Self {
+        HistoryCompleter {
+            commands,
+        }
+    }
+}
+
+impl Completer for HistoryCompleter {
+    fn columns(&self) -> Vec<Column> {
+        vec![Column::Expand]
+    }
+
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        let input = input.trim();
+        self.commands.iter()
+            .filter(|command| command.contains(input))
+            .map(|command| CompletionResult::new(&[command]))
+            .collect()
+    }
+
+    fn have_command(&self) -> bool {
+        false
+    }
+}
+
+/// A read-only completer listing the messages shown in the status bar.
+pub struct MessageCompleter {
+    messages: Vec<(String, String)>,
+}
+
+impl MessageCompleter {
+    /// Create a new message completer from the time and level, and the text of the messages.
+    /// The messages are shown in the specified order.
+    pub fn new(messages: Vec<(String, String)>) -> Self {
+        MessageCompleter {
+            messages,
+        }
+    }
+}
+
+impl Completer for MessageCompleter {
+    fn complete_result(&self, _value: &str) -> String {
+        "messages".to_string()
+    }
+
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        let input = input.trim().to_lowercase();
+        self.messages.iter()
+            .filter(|&&(ref level, ref message)|
+                    level.to_lowercase().contains(&input) ||
+                    message.to_lowercase().contains(&input))
             .map(|&(ref col1, ref col2)| CompletionResult::new(&[col1, col2]))
             .collect()
     }
@@ -88,7 +395,9 @@ impl Completer for NoCompleter {
 
 /// A setting completer.
 pub struct SettingCompleter<T> {
+    list_elements: HashMap<String, Vec<String>>,
     selected_name: Option<String>,
+    selected_operator: String,
     setting_names: Vec<(String, String)>,
     setting_values: HashMap<String, Vec<String>>,
     _phantom: PhantomData<T>,
@@ -105,18 +414,44 @@ impl<T: EnumMetaData + SettingCompletion> SettingCompleter<T> {
                 .collect();
         data.sort();
         SettingCompleter {
+            list_elements: HashMap::new(),
             selected_name: None,
+            selected_operator: "=".to_string(),
             setting_names: data,
             setting_values: T::get_value_completions(),
             _phantom: PhantomData,
         }
     }
+
+    /// Show the bounds of the numeric settings in their description.
+    pub fn bounds(mut self, bounds: &HashMap<String, (f64, f64)>) -> Self {
+        for &mut (ref name, ref mut help) in &mut self.setting_names {
+            if let Some(&(min, max)) = bounds.get(name) {
+                *help = format!("{} (between {} and {})", help, min, max);
+            }
+        }
+        self
+    }
+
+    /// Complete the current elements of the list settings when removing one with `-=`.
+    pub fn list_elements(mut self, list_elements: HashMap<String, Vec<String>>) -> Self {
+        self.list_elements = list_elements;
+        self
+    }
+
+    /// Complete the specified values for the specified settings.
+    pub fn values(mut self, settings: &HashSet<String>, values: &[String]) -> Self {
+        for setting in settings {
+            self.setting_values.insert(setting.clone(), values.to_vec());
+        }
+        self
+    }
 }
 
 impl<T> Completer for SettingCompleter<T> {
     fn complete_result(&self, value: &str) -> String {
         if let Some(ref name) = self.selected_name {
-            format!("set {} = {}", name, value)
+            format!("set {} {} {}", name, self.selected_operator, value)
         }
         else {
             format!("set {} =", value)
@@ -127,10 +462,18 @@ impl<T> Completer for SettingCompleter<T> {
         if input.contains("= ") {
             let mut iter = input.split_whitespace();
             if let Some(name) = iter.next() {
-                if let Some(values) = self.setting_values.get(name) {
-                    iter.next(); // Skip the equal token.
+                let operator = iter.next().unwrap_or("=");
+                let values =
+                    if operator == "-=" {
+                        self.list_elements.get(name)
+                    }
+                    else {
+                        self.setting_values.get(name)
+                    };
+                if let Some(values) = values {
                     let input_value = iter.next().unwrap_or_default();
                     self.selected_name = Some(name.to_string());
+                    self.selected_operator = operator.to_string();
                     return values.iter()
                         .filter(|value| value.contains(input_value))
                         .map(|value| CompletionResult::new(&[value, &String::new()]))
@@ -140,7 +483,9 @@ impl<T> Completer for SettingCompleter<T> {
             vec![]
         }
         else {
-            let input = input.trim();
+            // NOTE: the toggle (!) and query (?) suffixes are removed to keep completing the
+            // setting name.
+            let input = input.trim().trim_right_matches(|c| c == '!' || c == '?');
             self.selected_name = None;
             self.setting_names.iter()
                 .filter(|&&(ref setting, ref help)|
@@ -151,3 +496,85 @@ impl<T> Completer for SettingCompleter<T> {
         }
     }
 }
+
+/// A read-only completer listing the settings with their current value.
+pub struct SettingValueCompleter {
+    values: Vec<(String, String)>,
+}
+
+impl SettingValueCompleter {
+    /// Create a new setting value completer from the names and values of the settings.
+    pub fn new(mut values: Vec<(String, String)>) -> Self {
+        values.sort();
+        SettingValueCompleter {
+            values,
+        }
+    }
+}
+
+impl Completer for SettingValueCompleter {
+    fn complete_result(&self, value: &str) -> String {
+        format!("set {} ", value)
+    }
+
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        let input = input.trim();
+        self.values.iter()
+            .filter(|&&(ref setting, _)| setting.to_lowercase().contains(input))
+            .map(|&(ref col1, ref col2)| CompletionResult::new(&[col1, col2]))
+            .collect()
+    }
+}
+
+/// Get the category of the entry, falling back to the default category.
+fn category(categories: &HashMap<String, String>, name: &str) -> String {
+    categories.get(name)
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_CATEGORY.to_string())
+}
+
+/// Add the column of the categories before the columns when the entries are grouped by category.
+fn grouped_columns(categories: &HashMap<String, String>, mut columns: Vec<Column>) -> Vec<Column> {
+    if !categories.is_empty() {
+        columns.insert(0, Column::AllVisible);
+    }
+    columns
+}
+
+/// Convert the entries, made of their category and columns, to results grouped by category.
+/// The groups are sorted by category, with the default category last, and the category is only
+/// shown on the first entry of its group, so that it looks like a header.
+fn grouped_results(mut entries: Vec<(String, Vec<String>)>) -> Vec<CompletionResult> {
+    entries.sort_by(|&(ref category1, ref columns1), &(ref category2, ref columns2)|
+        (category1 == DEFAULT_CATEGORY, category1, columns1)
+            .cmp(&(category2 == DEFAULT_CATEGORY, category2, columns2)));
+    let mut results = vec![];
+    let mut previous_category: Option<&str> = None;
+    for &(ref category, ref columns) in &entries {
+        let header =
+            if previous_category == Some(category.as_str()) {
+                ""
+            }
+            else {
+                category.as_str()
+            };
+        previous_category = Some(category.as_str());
+        let mut cells = vec![CompletionCell::new(header).foreground(CATEGORY_FOREGROUND)];
+        cells.extend(columns.iter().map(|column| CompletionCell::new(column)));
+        results.push(CompletionResult {
+            columns: cells,
+        });
+    }
+    results
+}
+
+/// Get the column of the names, which is after the column of the categories when the entries
+/// are grouped by category.
+fn grouped_text_column(categories: &HashMap<String, String>) -> i32 {
+    if categories.is_empty() {
+        0
+    }
+    else {
+        1
+    }
+}
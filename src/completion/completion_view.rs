@@ -21,7 +21,7 @@
 
 use std::cmp::max;
 
-use glib::{Cast, Object};
+use glib::{Cast, Object, ToValue};
 use gtk;
 use gtk::{
     Align,
@@ -31,7 +31,10 @@ use gtk::{
     IsA,
     ListStore,
     ListStoreExt,
+    ListStoreExtManual,
     ScrolledWindowExt,
+    Tooltip,
+    TooltipExt,
     TreeIter,
     TreeModel,
     TreeModelExt,
@@ -47,12 +50,30 @@ use relm::{Relm, Widget};
 use relm_attributes::widget;
 
 use app::COMMAND_MODE;
-use completion::Completers;
+use completion::{Completers, command_end, extends_input};
+use lexer::lex_command_line;
 use completion::Column::{self, Expand};
 use self::Msg::*;
 use super::{Completer, Completion, DEFAULT_COMPLETER_IDENT, NO_COMPLETER_IDENT};
 
+/// The minimum width, in characters, of the expanding columns when their text is ellipsized.
+const COMPLETION_COLUMN_MIN_WIDTH_CHARS: i32 = 20;
 const COMPLETION_VIEW_MAX_HEIGHT: i32 = 300;
+/// The text shown in the mark column of the marked rows.
+const MARK: &str = "✓";
+
+/// What happens when selecting the next item after the last one, or the previous item before the
+/// first one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompletionWrap {
+    /// Stay on the last (or first) item.
+    Stop,
+    /// Unselect the item and restore the original input before selecting the first (or last) item
+    /// again.
+    Unselect,
+    /// Select the first (or last) item.
+    Wrap,
+}
 
 #[allow(missing_docs)]
 pub struct Model {
@@ -60,6 +81,7 @@ pub struct Model {
     original_input: String,
     relm: Relm<CompletionView>,
     visible: bool,
+    wrap: CompletionWrap,
 }
 
 pub type Mode = String;
@@ -67,16 +89,29 @@ pub type Text = String;
 
 #[derive(Msg)]
 pub enum Msg {
+    AcceptNext,
+    ActivateSelected,
     AddCompleters(Completers),
+    CompleteCommonPrefix,
     Completer(String),
+    CompletionAccepted(String),
+    CompletionActivated(String),
     CompletionChange(String),
+    CompletionsDeleted(String, Vec<String>),
     DeleteCurrentCompletionItem,
+    DeleteMarked,
+    Refresh,
+    SelectFirst,
+    SelectLast,
     SelectNext,
     SelectPrevious,
+    SelectionChanged(bool),
     SetOriginalInput(String),
     ShowCompletion,
+    ToggleMark,
     UpdateCompletions(Mode, Text, bool),
     Visible(bool),
+    Wrap(CompletionWrap),
 }
 
 /// A widget to show completions for the command entry.
@@ -90,6 +125,14 @@ impl Widget for CompletionView {
 
     fn init_view(&mut self) {
         self.add_columns(2);
+        self.tree_view.set_has_tooltip(true);
+        self.tree_view.connect_query_tooltip(|tree_view, x, y, keyboard_mode, tooltip|
+            show_cell_tooltip(tree_view, x, y, keyboard_mode, tooltip));
+        let stream = self.model.relm.stream().clone();
+        self.tree_view.get_selection().connect_changed(move |selection| {
+            let shown = selection.get_tree_view().map_or(false, |tree_view| tree_view.is_drawable());
+            stream.emit(SelectionChanged(shown && selection.get_selected().is_some()));
+        });
     }
 
     fn model(relm: &Relm<Self>, completers: Completers) -> Model {
@@ -100,6 +143,7 @@ impl Widget for CompletionView {
             original_input: String::new(),
             relm: relm.clone(),
             visible: false,
+            wrap: CompletionWrap::Unselect,
         }
     }
 
@@ -112,18 +156,36 @@ impl Widget for CompletionView {
 
     fn update(&mut self, msg: Msg) {
         match msg {
+            AcceptNext => self.accept_next(),
+            ActivateSelected => self.activate_selected(),
             AddCompleters(completers) => self.add_completers(completers),
+            CompleteCommonPrefix => self.complete_common_prefix(),
             Completer(completer) => self.set_completer(&completer, ""),
             // NOTE: to be listened by the user.
-            CompletionChange(_) => (),
+            CompletionAccepted(_) | CompletionActivated(_) | CompletionChange(_) | CompletionsDeleted(_, _) |
+                SelectionChanged(_) => (),
             DeleteCurrentCompletionItem => self.delete_current_completion_item(),
+            DeleteMarked => self.delete_marked(),
+            Refresh => self.refresh(),
+            SelectFirst => self.select_first(),
+            SelectLast => self.select_last(),
             SelectNext => self.select_next(),
             SelectPrevious => self.select_previous(),
             SetOriginalInput(input) => self.set_original_input(&input),
             ShowCompletion => self.show_completion(),
+            ToggleMark => self.toggle_mark(),
             UpdateCompletions(mode, text, is_normal_command) =>
                 self.update_completions(&mode, &text, is_normal_command),
-            Visible(visible) => self.model.visible = visible,
+            Visible(visible) => {
+                self.model.visible = visible;
+                if !visible {
+                    self.clear_marks();
+                }
+                // NOTE: a selection in the hidden view is not a selected completion.
+                let selected = visible && self.tree_view.get_selection().get_selected().is_some();
+                self.model.relm.stream().emit(SelectionChanged(selected));
+            },
+            Wrap(wrap) => self.model.wrap = wrap,
         }
     }
 
@@ -145,12 +207,39 @@ impl Widget for CompletionView {
 }
 
 impl CompletionView {
+    /// Accept the selected completion followed by a space, so that the next argument can be
+    /// completed: the completions are updated from the new input, which switches to the completer
+    /// of the next argument for an ArgumentsCompleter.
+    /// Select the next item if none is selected.
+    fn accept_next(&self) {
+        let selection = self.tree_view.get_selection();
+        match self.model.completion.complete_result(&selection) {
+            Some(mut completion) => {
+                if !completion.ends_with(' ') {
+                    completion.push(' ');
+                }
+                self.model.relm.stream().emit(CompletionAccepted(completion));
+            },
+            None => self.select_next(),
+        }
+    }
+
+    /// Activate the selected completion, to execute it without accepting it in the entry first.
+    fn activate_selected(&self) {
+        let selection = self.tree_view.get_selection();
+        if let Some(completion) = self.model.completion.complete_result(&selection) {
+            self.model.relm.stream().emit(CompletionActivated(completion));
+        }
+    }
+
     /// Add a column to the tree view.
     fn add_column(&self, index: i32, foreground_index: i32, column: Column) {
         let view_column = TreeViewColumn::new();
         let cell = CellRendererText::new();
         if column == Expand {
+            // NOTE: the full text is shown in the tooltip of the row.
             cell.set_property_ellipsize(EllipsizeMode::End);
+            cell.set_property_width_chars(COMPLETION_COLUMN_MIN_WIDTH_CHARS);
             view_column.set_expand(true);
         }
         view_column.pack_start(&cell, true);
@@ -165,6 +254,7 @@ impl CompletionView {
         for i in 0 .. column_count {
             self.add_column(i, column_count + i, Expand);
         }
+        self.add_mark_column(column_count * 2);
     }
 
     /// Add the specified number of columns.
@@ -176,6 +266,16 @@ impl CompletionView {
             let i = i as i32;
             self.add_column(i, column_count + i, *column);
         }
+        self.add_mark_column(column_count * 2);
+    }
+
+    /// Add the column showing the marks of the rows after the other columns.
+    fn add_mark_column(&self, index: i32) {
+        let view_column = TreeViewColumn::new();
+        let cell = CellRendererText::new();
+        view_column.pack_start(&cell, false);
+        view_column.add_attribute(&cell, "text", index);
+        self.tree_view.append_column(&view_column);
     }
 
     /// Adjust the columns from the completer.
@@ -183,6 +283,12 @@ impl CompletionView {
         self.add_columns_from_completer(completer);
     }
 
+    /// Send the values of the deleted items with the completer they come from.
+    fn emit_deleted(&self, values: Vec<String>) {
+        let completer = self.model.completion.current_completer_ident().to_string();
+        self.model.relm.stream().emit(CompletionsDeleted(completer, values));
+    }
+
     /// Adjust the policy of the scrolled window to avoid having extra space around the tree view.
     fn adjust_policy<M: IsA<Object> + IsA<TreeModel>>(&self, model: &M) {
         self.tree_view.set_model(Some(model));
@@ -196,6 +302,74 @@ impl CompletionView {
         self.scrolled_window.set_policy(Never, policy);
     }
 
+    /// Extend the input to the longest common prefix of the completions when it is longer than the
+    /// input, like a shell does.
+    /// Select the next item otherwise.
+    fn complete_common_prefix(&mut self) {
+        let prefix =
+            if self.tree_view.get_selection().get_selected().is_none() {
+                self.common_prefix()
+            }
+            else {
+                None
+            };
+        match prefix {
+            Some(ref prefix) if extends_input(prefix, &self.model.original_input) => {
+                self.model.original_input = prefix.clone();
+                self.model.relm.stream().emit(CompletionChange(prefix.clone()));
+            },
+            _ => self.select_next(),
+        }
+    }
+
+    /// Get the longest common prefix of the results of the completions.
+    fn common_prefix(&self) -> Option<String> {
+        let model = self.tree_view.get_model()?;
+        let completer = self.model.completion.current_completer()?;
+        let iter = model.get_iter_first()?;
+        let mut prefix: Option<String> = None;
+        loop {
+            if let Some(value) = self.model.completion.row_value(&model, &iter) {
+                let result = completer.complete_result(&value);
+                prefix = Some(match prefix {
+                    Some(prefix) => {
+                        let length = prefix.chars().zip(result.chars())
+                            .take_while(|&(char1, char2)| char1 == char2)
+                            .map(|(character, _)| character.len_utf8())
+                            .sum();
+                        prefix[..length].to_string()
+                    },
+                    None => result,
+                });
+            }
+            if !model.iter_next(&iter) {
+                break;
+            }
+        }
+        prefix
+    }
+
+    /// Remove the marks of all the rows.
+    fn clear_marks(&self) {
+        let mark_column =
+            match self.model.completion.mark_column() {
+                Some(mark_column) => mark_column,
+                None => return,
+            };
+        if let Some(model) = self.tree_view.get_model() {
+            if let Ok(store) = model.clone().downcast::<ListStore>() {
+                if let Some(iter) = model.get_iter_first() {
+                    loop {
+                        store.set_value(&iter, mark_column as u32, &"".to_value());
+                        if !model.iter_next(&iter) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Complete the result for the selection using the current completer.
     fn complete_result(&self) {
         let selection = self.tree_view.get_selection();
@@ -204,15 +378,60 @@ impl CompletionView {
         }
     }
 
-    /// Delete the current completion item.
+    /// Delete the current completion item and send its value.
     fn delete_current_completion_item(&self) {
         if let Some((model, iter)) = self.tree_view.get_selection().get_selected() {
+            let value = self.model.completion.row_value(&model, &iter);
             if let Ok(model) = model.downcast::<ListStore>() {
                 self.select_next();
                 model.remove(&iter);
                 self.adjust_policy(&model);
+                if let Some(value) = value {
+                    self.emit_deleted(vec![value]);
+                }
+            }
+        }
+    }
+
+    /// Delete the marked items and send their values in a single message.
+    fn delete_marked(&self) {
+        let mark_column =
+            match self.model.completion.mark_column() {
+                Some(mark_column) => mark_column,
+                None => return,
+            };
+        let model =
+            match self.tree_view.get_model() {
+                Some(model) => model,
+                None => return,
+            };
+        let store =
+            match model.clone().downcast::<ListStore>() {
+                Ok(store) => store,
+                Err(_) => return,
+            };
+        let mut deleted = vec![];
+        if let Some(iter) = model.get_iter_first() {
+            loop {
+                if is_marked(&model, &iter, mark_column) {
+                    if let Some(value) = self.model.completion.row_value(&model, &iter) {
+                        deleted.push(value);
+                    }
+                    // NOTE: the iterator points to the next row after the removal.
+                    if !store.remove(&iter) {
+                        break;
+                    }
+                }
+                else if !model.iter_next(&iter) {
+                    break;
+                }
             }
         }
+        if !deleted.is_empty() {
+            self.unselect();
+            self.adjust_policy(&store);
+            self.emit_deleted(deleted);
+        }
     }
 
     /// Adjust the policy of the scrolled window to avoid having extra space around the tree view.
@@ -231,6 +450,22 @@ impl CompletionView {
         }
     }
 
+    /// Refresh the completions from the original input.
+    /// The selection is kept if the selected value is still there.
+    fn refresh(&mut self) {
+        let previous_selection = self.tree_view.get_selection().get_selected()
+            .map(|(model, iter)| {
+                let index = model.get_path(&iter)
+                    .and_then(|path| path.get_indices().first().cloned());
+                (self.model.completion.row_value(&model, &iter), index)
+            });
+        let input = self.model.original_input.clone();
+        self.filter(&input);
+        if let Some((value, index)) = previous_selection {
+            self.restore_selection(value, index);
+        }
+    }
+
     /// Remove all the columns.
     fn remove_columns(&self) {
         for column in &self.tree_view.get_columns() {
@@ -238,6 +473,36 @@ impl CompletionView {
         }
     }
 
+    /// Select the row having the specified value, falling back to the row at the specified index.
+    /// The entry text is only changed when the value is not there anymore.
+    fn restore_selection(&self, value: Option<String>, index: Option<i32>) {
+        if let Some(model) = self.tree_view.get_model() {
+            let selection = self.tree_view.get_selection();
+            if value.is_some() {
+                if let Some(iter) = model.get_iter_first() {
+                    loop {
+                        if self.model.completion.row_value(&model, &iter) == value {
+                            selection.select_iter(&iter);
+                            self.scroll(&model, &iter);
+                            return;
+                        }
+                        if !model.iter_next(&iter) {
+                            break;
+                        }
+                    }
+                }
+            }
+            if let Some(iter) = index.and_then(|index| model.iter_nth_child(None, index)) {
+                selection.select_iter(&iter);
+                self.scroll(&model, &iter);
+                self.complete_result();
+                return;
+            }
+        }
+        self.unselect();
+        self.model.relm.stream().emit(CompletionChange(self.model.original_input.clone()));
+    }
+
     /// Scroll to the selected row.
     fn scroll(&self, model: &TreeModel, iter: &TreeIter) {
         if let Some(path) = model.get_path(iter) {
@@ -258,10 +523,10 @@ impl CompletionView {
 
     /// Select the completer based on the currently typed command.
     fn select_completer(&mut self, command_entry_text: &str, is_normal_command: bool) {
-        let text = command_entry_text.trim_left();
+        let tokens = lex_command_line(command_entry_text);
         let completer =
-            if let Some(space_index) = text.find(' ') {
-                &text[..space_index]
+            if command_end(command_entry_text).is_some() {
+                tokens[0].text.as_str()
             }
             else if is_normal_command {
                 DEFAULT_COMPLETER_IDENT
@@ -272,8 +537,33 @@ impl CompletionView {
         self.set_completer(completer, command_entry_text);
     }
 
+    /// Select the first item.
+    fn select_first(&self) {
+        if let Some(model) = self.tree_view.get_model() {
+            let first = model.get_iter_first();
+            self.select_iter(&model, first);
+        }
+    }
+
+    /// Select the item if any and complete its result.
+    fn select_iter(&self, model: &TreeModel, iter: Option<TreeIter>) {
+        if let Some(iter) = iter {
+            self.tree_view.get_selection().select_iter(&iter);
+            self.scroll(model, &iter);
+            self.complete_result();
+        }
+    }
+
+    /// Select the last item.
+    fn select_last(&self) {
+        if let Some(model) = self.tree_view.get_model() {
+            let last = model.iter_nth_child(None, max(0, model.iter_n_children(None) - 1));
+            self.select_iter(&model, last);
+        }
+    }
+
     /// Select the next item.
-    /// This loops with the value that started the completion.
+    /// What happens after the last item depends on the wrap mode.
     fn select_next(&self) {
         if let Some(model) = self.tree_view.get_model() {
             let selection = self.tree_view.get_selection();
@@ -283,8 +573,7 @@ impl CompletionView {
                     self.scroll(&model, &selected_iter);
                 }
                 else {
-                    self.unselect();
-                    self.model.relm.stream().emit(CompletionChange(self.model.original_input.clone()));
+                    self.select_past_end(&model, model.get_iter_first());
                 }
             }
             else if let Some(iter) = model.get_iter_first() {
@@ -295,8 +584,27 @@ impl CompletionView {
         }
     }
 
+    /// Handle the selection going past the last (or first) item according to the wrap mode.
+    /// `other_end` is the first (or last) item.
+    fn select_past_end(&self, model: &TreeModel, other_end: Option<TreeIter>) {
+        match self.model.wrap {
+            // NOTE: the selection is not changed, so it stays on the current item.
+            CompletionWrap::Stop => (),
+            CompletionWrap::Unselect => {
+                self.unselect();
+                self.model.relm.stream().emit(CompletionChange(self.model.original_input.clone()));
+            },
+            CompletionWrap::Wrap => {
+                if let Some(iter) = other_end {
+                    self.tree_view.get_selection().select_iter(&iter);
+                    self.scroll(model, &iter);
+                }
+            },
+        }
+    }
+
     /// Select the previous item.
-    /// This loops with the value that started the completion.
+    /// What happens before the first item depends on the wrap mode.
     fn select_previous(&self) {
         if let Some(model) = self.tree_view.get_model() {
             let selection = self.tree_view.get_selection();
@@ -306,8 +614,8 @@ impl CompletionView {
                     self.scroll(&model, &selected_iter);
                 }
                 else {
-                    self.unselect();
-                    self.model.relm.stream().emit(CompletionChange(self.model.original_input.clone()));
+                    let last = model.iter_nth_child(None, max(0, model.iter_n_children(None) - 1));
+                    self.select_past_end(&model, last);
                 }
             }
             else if let Some(iter) = model.iter_nth_child(None, max(0, model.iter_n_children(None) - 1)) {
@@ -336,6 +644,28 @@ impl CompletionView {
         self.model.original_input = input.to_string();
     }
 
+    /// Toggle the mark of the selected item and select the next one.
+    fn toggle_mark(&self) {
+        let mark_column =
+            match self.model.completion.mark_column() {
+                Some(mark_column) => mark_column,
+                None => return,
+            };
+        if let Some((model, iter)) = self.tree_view.get_selection().get_selected() {
+            if let Ok(store) = model.clone().downcast::<ListStore>() {
+                let mark =
+                    if is_marked(&model, &iter, mark_column) {
+                        ""
+                    }
+                    else {
+                        MARK
+                    };
+                store.set_value(&iter, mark_column as u32, &mark.to_value());
+                self.select_next();
+            }
+        }
+    }
+
     /// Unselect the item.
     fn unselect(&self) {
         let selection = self.tree_view.get_selection();
@@ -363,3 +693,42 @@ impl CompletionView {
         self.unselect();
     }
 }
+
+/// Check if the row is marked.
+fn is_marked(model: &TreeModel, iter: &TreeIter, mark_column: i32) -> bool {
+    let mark: Option<String> = model.get_value(iter, mark_column).get();
+    mark.map_or(false, |mark| !mark.is_empty())
+}
+
+/// Show the full text of the hovered cell in the tooltip of its row, since the long texts are
+/// ellipsized.
+/// The text is set as plain text, so that it is not interpreted as markup.
+fn show_cell_tooltip(tree_view: &gtk::TreeView, x: i32, y: i32, keyboard_mode: bool, tooltip: &Tooltip) -> bool {
+    if keyboard_mode {
+        return false;
+    }
+    let (bin_x, bin_y) = tree_view.convert_widget_to_bin_window_coords(x, y);
+    let (path, column) =
+        match tree_view.get_path_at_pos(bin_x, bin_y) {
+            Some((Some(path), Some(column), _, _)) => (path, column),
+            _ => return false,
+        };
+    let index =
+        match tree_view.get_columns().iter().position(|view_column| *view_column == column) {
+            Some(index) => index as i32,
+            None => return false,
+        };
+    let text: Option<String> = tree_view.get_model()
+        // NOTE: the last column shows the marks.
+        .filter(|model| index < (model.get_n_columns() - 1) / 2)
+        .and_then(|model| model.get_iter(&path)
+            .and_then(|iter| model.get_value(&iter, index).get()));
+    match text {
+        Some(ref text) if !text.is_empty() => {
+            tooltip.set_text(Some(text.as_str()));
+            tree_view.set_tooltip_row(tooltip, &path);
+            true
+        },
+        _ => false,
+    }
+}
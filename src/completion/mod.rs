@@ -32,22 +32,48 @@ use gtk::{
     ListStore,
     ListStoreExt,
     ListStoreExtManual,
+    TreeIter,
+    TreeModel,
     TreeModelExt,
     TreeSelection,
     TreeSelectionExt,
     Type,
 };
 
+use lexer::lex_command_line;
 use self::Column::Expand;
-pub use self::completers::{CommandCompleter, NoCompleter, SettingCompleter};
+pub use self::completers::{
+    AllSettingsCompleter,
+    ArgumentsCompleter,
+    CommandCompleter,
+    HelpCompleter,
+    HistoryCompleter,
+    MessageCompleter,
+    NoCompleter,
+    SettingCompleter,
+    SettingValueCompleter,
+    DEFAULT_CATEGORY,
+};
 pub use self::completion_view::CompletionView;
 
 /// The identifier of the default completer.
 pub const DEFAULT_COMPLETER_IDENT: &str = "__mg_default";
 
+/// The maximum number of suggestions for an unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The identifier of the completer listing the command history.
+pub const HISTORY_COMPLETER_IDENT: &str = "__mg_history";
+
+/// The identifier of the completer listing the message log.
+pub const MESSAGES_COMPLETER_IDENT: &str = "__mg_messages";
+
 /// The identifier of the null completer.
 pub const NO_COMPLETER_IDENT: &str = "__mg_no_completer";
 
+/// The identifier of the completer listing the current setting values.
+pub const SETTING_VALUES_COMPLETER_IDENT: &str = "__mg_setting_values";
+
 #[doc(hidden)]
 pub type Completers = HashMap<&'static str, Box<Completer>>;
 
@@ -164,8 +190,9 @@ impl Completion {
     pub fn filter(&mut self, input: &str) -> Option<ListStore> {
         self.current_completer_mut()
             .map(|completer| {
-                // Multiply by 2 because each column has a foreground column.
-                let columns = vec![Type::String; completer.column_count() * 2];
+                // Multiply by 2 because each column has a foreground column, and add the column
+                // of the marks.
+                let columns = vec![Type::String; completer.column_count() * 2 + 1];
                 let model = ListStore::new(&columns);
 
                 // Remove the command to only send the value to the completer.
@@ -173,11 +200,11 @@ impl Completion {
                     if !completer.have_command() {
                         input
                     }
-                    else if let Some(index) = input.find(' ') {
-                        input[index + 1 ..].trim_left()
-                    }
                     else {
-                        input
+                        match command_end(input) {
+                            Some(index) => input[index..].trim_left(),
+                            None => input,
+                        }
                     };
 
                 for &CompletionResult { ref columns } in &completer.completions(key) {
@@ -194,6 +221,18 @@ impl Completion {
             })
     }
 
+    /// Get the index of the model column containing the marks of the rows.
+    pub fn mark_column(&self) -> Option<i32> {
+        self.current_completer()
+            .map(|completer| completer.column_count() as i32 * 2)
+    }
+
+    /// Get the value of the row that would be used as the result.
+    pub fn row_value(&self, model: &TreeModel, iter: &TreeIter) -> Option<String> {
+        self.current_completer()
+            .and_then(|completer| model.get_value(iter, completer.text_column()).get())
+    }
+
     /// Set all the completers.
     pub fn set_completers(&mut self, mut completers: Completers) {
         completers.insert(NO_COMPLETER_IDENT, Box::new(NoCompleter::new()));
@@ -201,6 +240,54 @@ impl Completion {
     }
 }
 
+/// Get the end of the command name if the command line contains arguments after it.
+fn command_end(input: &str) -> Option<usize> {
+    match lex_command_line(input).first() {
+        Some(token) if token.span.end < input.len() => Some(token.span.end),
+        _ => None,
+    }
+}
+
+/// Check if the common prefix of the completions extends the input, i.e. it is longer and starts
+/// with the input.
+/// The case is ignored, like when filtering the completions.
+pub fn extends_input(prefix: &str, input: &str) -> bool {
+    prefix.chars().count() > input.chars().count() && prefix.to_lowercase().starts_with(&input.to_lowercase())
+}
+
+/// Get up to 3 candidates close to the name, to suggest them when the name is unknown.
+/// A candidate is close when it starts with the name or when only a few characters differ.
+pub fn closest_matches(name: &str, candidates: &[String]) -> Vec<String> {
+    let max_distance = ::std::cmp::max(2, name.chars().count() / 3);
+    let mut matches: Vec<_> = candidates.iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, candidate)| distance <= max_distance || candidate.starts_with(name))
+        .collect();
+    matches.sort();
+    matches.into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+/// Get the number of character insertions, deletions and substitutions needed to change a string
+/// into the other (i.e. the Levenshtein distance).
+pub fn edit_distance(string1: &str, string2: &str) -> usize {
+    let chars2: Vec<_> = string2.chars().collect();
+    let mut previous_row: Vec<_> = (0..chars2.len() + 1).collect();
+    for (index1, char1) in string1.chars().enumerate() {
+        let mut current_row = vec![index1 + 1];
+        for (index2, &char2) in chars2.iter().enumerate() {
+            let substitution = previous_row[index2] + if char1 == char2 { 0 } else { 1 };
+            let insertion = current_row[index2] + 1;
+            let deletion = previous_row[index2 + 1] + 1;
+            current_row.push(substitution.min(insertion).min(deletion));
+        }
+        previous_row = current_row;
+    }
+    previous_row[chars2.len()]
+}
+
 /// A completion cell is the value with attributes of one data in a row.
 #[derive(Clone)]
 pub struct CompletionCell {
@@ -19,15 +19,143 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use std::fs::File;
-use std::path::Path;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
 use mg_settings::errors::{Error, Result};
 
-pub fn open<P: AsRef<Path>>(path: P) -> Result<File> {
-    match File::open(&path) {
-        Ok(file) => Ok(file),
-        Err(error) => Err(Error::Msg(format!("failed to open included file `{}`: {}", path.as_ref().to_string_lossy(),
-            error.to_string()))),
+use app::expand_path;
+
+/// An error of an include command.
+/// The include is skipped and the rest of the config is still parsed.
+#[derive(Debug)]
+pub enum IncludeError {
+    /// The file includes itself, with the names of the files of the cycle.
+    Cycle(Vec<String>),
+    /// The maximum include depth would be exceeded by including the file.
+    MaxDepth {
+        max_depth: usize,
+        path: PathBuf,
+    },
+    /// The included file cannot be read.
+    Read {
+        error: io::Error,
+        path: PathBuf,
+    },
+}
+
+impl Display for IncludeError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            IncludeError::Cycle(ref files) => write!(formatter, "include cycle: {}", files.join(" → ")),
+            IncludeError::MaxDepth { max_depth, ref path } =>
+                write!(formatter, "maximum include depth ({}) exceeded when including `{}`", max_depth,
+                    path.to_string_lossy()),
+            IncludeError::Read { ref error, ref path } =>
+                write!(formatter, "failed to open included file `{}`: {}", path.to_string_lossy(), error),
+        }
+    }
+}
+
+/// Read the whole file.
+fn read(path: &Path) -> io::Result<String> {
+    let mut content = String::new();
+    File::open(path)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// The command used to include a file in a config file.
+const INCLUDE_COMMAND: &str = "include";
+
+/// A line of a config file, with its position in the file it comes from.
+#[derive(Clone, Debug)]
+pub struct SourceLine {
+    /// The file containing the line, which is an included file for the lines of an include.
+    pub file: PathBuf,
+    /// The number of the line in its file, starting at 1.
+    pub number: usize,
+    /// The text of the line.
+    pub text: String,
+}
+
+impl SourceLine {
+    /// Create an error telling from which file and line it comes.
+    pub fn error(&self, message: &str) -> Error {
+        Error::Msg(format!("{}:{}: {}", self.file.to_string_lossy(), self.number, message))
+    }
+}
+
+/// Read a config file, replacing its include commands by the lines of the included files.
+/// Each line keeps the position it has in its file, so that the errors report the right line.
+/// An include that would create a cycle or exceed the maximum include depth is skipped and
+/// reported in `errors`, with the line of the include command.
+pub fn read_with_includes(filename: &Path, include_path: Option<&Path>, max_depth: usize,
+    errors: &mut Vec<(SourceLine, IncludeError)>) -> Result<Vec<SourceLine>>
+{
+    let content = read(filename)
+        .map_err(|error| Error::Msg(format!("failed to open `{}`: {}", filename.to_string_lossy(), error)))?;
+    let mut stack = vec![];
+    let mut lines = vec![];
+    expand_includes(filename, &content, include_path, max_depth, &mut stack, &mut lines, errors);
+    Ok(lines)
+}
+
+/// Add the lines of the file to `lines`, replacing its include commands by the lines of the
+/// included files.
+/// `stack` contains the files being included, the first one being the config file.
+fn expand_includes(filename: &Path, content: &str, include_path: Option<&Path>, max_depth: usize,
+    stack: &mut Vec<PathBuf>, lines: &mut Vec<SourceLine>, errors: &mut Vec<(SourceLine, IncludeError)>)
+{
+    stack.push(canonical_path(filename));
+    for (index, line) in content.lines().enumerate() {
+        let source_line = SourceLine {
+            file: filename.to_path_buf(),
+            number: index + 1,
+            text: line.to_string(),
+        };
+        let mut words = line.split_whitespace();
+        if let (Some(INCLUDE_COMMAND), Some(name)) = (words.next(), words.next()) {
+            // NOTE: joining an absolute path (like an expanded `~/`) replaces the include path.
+            let name = expand_path(name);
+            let included = include_path.map(|path| path.join(&name)).unwrap_or(name);
+            let canonical_included = canonical_path(&included);
+            if let Some(index) = stack.iter().position(|file| *file == canonical_included) {
+                let cycle: Vec<_> = stack[index..].iter()
+                    .chain(Some(&canonical_included))
+                    .map(|file| file_name(file))
+                    .collect();
+                errors.push((source_line, IncludeError::Cycle(cycle)));
+            }
+            // NOTE: the included file would be at the depth stack.len(), the config file being at
+            // the depth 0.
+            else if stack.len() >= max_depth {
+                errors.push((source_line, IncludeError::MaxDepth { max_depth, path: included }));
+            }
+            else {
+                match read(&included) {
+                    Ok(included_content) =>
+                        expand_includes(&included, &included_content, include_path, max_depth, stack, lines, errors),
+                    Err(error) => errors.push((source_line, IncludeError::Read { error, path: included })),
+                }
+            }
+        }
+        else {
+            lines.push(source_line);
+        }
     }
+    stack.pop();
+}
+
+/// Get the canonical path of a file to compare it with the other included files.
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Get the name of a file as shown in the errors.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
 }
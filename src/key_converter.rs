@@ -19,50 +19,336 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+use std::ptr;
+
 use gdk::{EventKey, ModifierType};
+use gdk_sys;
 use gdk::enums::key::{self, A,
     _0, _1, _2, _3, _4, _5, _6, _7, _8, _9, B, C, D, E, F, G, H, I, J, K, KP_0, KP_1, KP_2, KP_3,
     KP_4, KP_5, KP_6, KP_7, KP_8, KP_9, L, M, N, O, P, Q, R, Return, S, T, U, V, W, X, Y, Z, a, b,
     c, d, e, f, g, h, i, j, k, l, m, n, o, p, q, r, s, t, u, v, w, x, y, z};
+use glib::translate::ToGlib;
 use mg_settings::key::Key::{self, Alt, Backspace, Char, Control, Delete, Down, End, Enter, Escape, F1, F2, F3, F4, F5,
     F6, F7, F8, F9, F10, F11, F12, Home, Insert, Left, PageDown, PageUp, Right, Shift, Space, Tab, Up};
 
+/// The last dead key keyval, `dead_longsolidusoverlay`, as the first one is `dead_grave`.
+const LAST_DEAD_KEY: u32 = 0xfe93;
+
+/// Check if the keyval is a dead key or the compose key, which are used by the input method to
+/// compose a character with the next keys.
+pub fn is_dead_or_compose_key(keyval: u32) -> bool {
+    (keyval >= key::dead_grave && keyval <= LAST_DEAD_KEY) || keyval == key::Multi_key
+}
+
 /// Convert a GDK key to an MG Key.
 pub fn gdk_key_to_key(key: &EventKey) -> Option<Key> {
-    let alt_pressed = key.get_state().contains(ModifierType::MOD1_MASK);
-    let control_pressed = key.get_state().contains(ModifierType::CONTROL_MASK);
-    let shift_pressed = key.get_state().contains(ModifierType::SHIFT_MASK);
-    let key =
-        match to_key(key) {
-            Some(key) => key,
-            None => return None,
-        };
+    keyval_to_key(key.get_keyval(), key.get_state())
+}
+
+/// Convert a GDK keyval with the state of the modifiers to an MG Key.
+/// The keyval of a shifted symbol is already the logical character (i.e. `!` on a US layout), so
+/// the shift modifier is only kept for the non-printable keys, like `<S-Tab>` or `<S-F5>`.
+/// The keys pressed with the super modifier are unsupported and give None (see
+/// `has_super_modifier()`): the MG Key cannot represent this modifier, so they would otherwise
+/// trigger the mappings of the key without modifier.
+pub fn keyval_to_key(keyval: u32, modifiers: ModifierType) -> Option<Key> {
+    if has_super_modifier(modifiers) {
+        return None;
+    }
+    to_key(keyval).map(|base_key| with_modifiers(base_key, modifiers))
+}
 
-    let control_constructor: fn(Key) -> Key =
-        if control_pressed {
-            |key| Control(Box::new(key))
+/// Check if the super modifier is pressed, which cannot be used in the mappings.
+pub fn has_super_modifier(modifiers: ModifierType) -> bool {
+    modifiers.intersects(ModifierType::SUPER_MASK | ModifierType::MOD4_MASK)
+}
+
+/// Convert the hardware keycode of a GDK key to an MG Key.
+/// The keycode is translated with the first group of the keyboard layout, so that a physical key
+/// gives the same key whatever the active layout is.
+pub fn gdk_keycode_to_key(key: &EventKey) -> Option<Key> {
+    let state = key.get_state();
+    let mut keyval = 0;
+    // NOTE: gdk 0.8 has no binding for the keymap.
+    let translated = unsafe {
+        let keymap = gdk_sys::gdk_keymap_get_default();
+        if keymap.is_null() {
+            return None;
         }
-        else {
-            |key| key
-        };
+        gdk_sys::gdk_keymap_translate_keyboard_state(keymap, u32::from(key.get_hardware_keycode()), state.to_glib(), 0,
+            &mut keyval, ptr::null_mut(), ptr::null_mut(), ptr::null_mut())
+    };
+    if translated == 0 {
+        return None;
+    }
+    keyval_to_key(keyval, state)
+}
+
+/// Wrap the key with the modifiers, in the canonical order: control, alt, shift.
+pub fn with_modifiers(key: Key, modifiers: ModifierType) -> Key {
+    let alt_pressed = modifiers.contains(ModifierType::MOD1_MASK);
+    let control_pressed = modifiers.contains(ModifierType::CONTROL_MASK);
+    let shift_pressed = modifiers.contains(ModifierType::SHIFT_MASK);
+    build_key(key, control_pressed, alt_pressed, shift_pressed)
+}
+
+/// Normalize a key so that a chord is always represented the same way.
+/// The modifiers are put in the canonical order and the shift modifier is applied to the
+/// characters, so that `<C-S-t>` and `<C-T>` are the same key.
+pub fn normalize_key(key: Key) -> Key {
+    let mut alt = false;
+    let mut control = false;
+    let mut shift = false;
+    let mut key = key;
+    loop {
+        key =
+            match key {
+                Alt(inner) => {
+                    alt = true;
+                    *inner
+                },
+                Control(inner) => {
+                    control = true;
+                    *inner
+                },
+                Shift(inner) => {
+                    shift = true;
+                    *inner
+                },
+                key => break build_key(shifted(key, shift), control, alt, shift),
+            };
+    }
+}
+
+/// Build a key with the specified modifiers.
+fn build_key(key: Key, control: bool, alt: bool, shift: bool) -> Key {
+    let mut key = key;
+    // NOTE: the shift modifier is already applied to the characters (i.e. T instead of t).
+    if shift && !is_char(&key) {
+        key = Shift(Box::new(key));
+    }
+    if alt {
+        key = Alt(Box::new(key));
+    }
+    if control {
+        key = Control(Box::new(key));
+    }
+    key
+}
 
-    let alt_constructor: fn(Key) -> Key =
-        if alt_pressed {
-            |key| Alt(Box::new(key))
+/// Check if the key applies the shift modifier to a symbol, like `<S-1>`.
+/// Such a key can never be pressed since the keyval of a shifted symbol is already the shifted
+/// character, which depends on the keyboard layout (i.e. `!` for `<S-1>` on a US layout).
+pub fn is_shifted_symbol(key: &Key) -> bool {
+    match *key {
+        Alt(ref inner) | Control(ref inner) => is_shifted_symbol(inner),
+        Shift(ref inner) => {
+            match **inner {
+                Char(character) => !character.is_alphabetic(),
+                ref inner => is_shifted_symbol(inner),
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Apply the shift modifier to a letter.
+fn shifted(key: Key, shift: bool) -> Key {
+    if let Char(character) = key {
+        if shift && character.is_lowercase() {
+            let mut upper = character.to_uppercase();
+            if let (Some(upper), None) = (upper.next(), upper.next()) {
+                return Char(upper);
+            }
         }
-        else {
-            |key| key
-        };
+    }
+    key
+}
+
+/// Convert an MG Key to its string representation as written in the config file.
+/// The named keys and the keys with modifiers are written between angle brackets (i.e. `<Space>` or
+/// `<C-a>`) and a literal `<` is written `<lt>`.
+pub fn key_to_string(key: &Key) -> String {
+    let mut modifiers = String::new();
+    let mut key = key;
+    loop {
+        match *key {
+            Alt(ref inner) => {
+                modifiers.push_str("A-");
+                key = inner;
+            },
+            Control(ref inner) => {
+                modifiers.push_str("C-");
+                key = inner;
+            },
+            Shift(ref inner) => {
+                modifiers.push_str("S-");
+                key = inner;
+            },
+            _ => break,
+        }
+    }
+    match (key_name(key), modifiers.is_empty()) {
+        (Some(name), _) => format!("<{}{}>", modifiers, name),
+        // NOTE: Show the literal < as <lt> to avoid confusion with the named keys.
+        (None, true) if *key == Char('<') => "<lt>".to_string(),
+        (None, true) => key.to_string(),
+        (None, false) => format!("<{}{}>", modifiers, key),
+    }
+}
+
+/// Convert keys to their string representation as written in the config file.
+pub fn keys_to_string(keys: &[Key]) -> String {
+    let strings: Vec<_> = keys.iter().map(key_to_string).collect();
+    strings.join("")
+}
 
-    let shift_constructor: fn(Key) -> Key =
-        if shift_pressed && !is_char(&key) {
-            |key| Shift(Box::new(key))
+/// Parse a single key written as in the config file, like `x`, `<lt>` or `<C-d>`.
+/// Returns None if the string is not exactly one key or if it applies the shift modifier to a
+/// symbol (see is_shifted_symbol()).
+pub fn string_to_key(string: &str) -> Option<Key> {
+    if string == "<lt>" {
+        return Some(Char('<'));
+    }
+    if !string.starts_with('<') || !string.ends_with('>') || string.len() < 3 {
+        let mut chars = string.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(character), None) => Some(Char(character)),
+            _ => None,
+        };
+    }
+    let mut name = &string[1..string.len() - 1];
+    let mut alt = false;
+    let mut control = false;
+    let mut shift = false;
+    loop {
+        if name.starts_with("A-") && name.len() > 2 {
+            alt = true;
+        }
+        else if name.starts_with("C-") && name.len() > 2 {
+            control = true;
+        }
+        else if name.starts_with("S-") && name.len() > 2 {
+            shift = true;
         }
         else {
-            |key| key
+            break;
+        }
+        name = &name[2..];
+    }
+    let key =
+        match named_key(name) {
+            Some(key) => key,
+            None => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(character), None) => Char(character),
+                    _ => return None,
+                }
+            },
+        };
+    if let Char(character) = key {
+        if shift && !character.is_alphabetic() {
+            return None;
+        }
+    }
+    Some(build_key(shifted(key, shift), control, alt, shift))
+}
+
+/// Parse keys written as in the config file, like `gt` or `<C-w>v`.
+/// Returns None if one of the keys is invalid.
+pub fn string_to_keys(string: &str) -> Option<Vec<Key>> {
+    let mut keys = vec![];
+    let mut rest = string;
+    while let Some(first) = rest.chars().next() {
+        let end =
+            if first == '<' {
+                rest.find('>').map(|index| index + 1).unwrap_or(1)
+            }
+            else {
+                first.len_utf8()
+            };
+        keys.push(string_to_key(&rest[..end])?);
+        rest = &rest[end..];
+    }
+    if keys.is_empty() {
+        None
+    }
+    else {
+        Some(keys)
+    }
+}
+
+/// Get the special key having this name, as returned by key_name().
+fn named_key(name: &str) -> Option<Key> {
+    let key =
+        match name {
+            "BS" => Backspace,
+            "Del" => Delete,
+            "Down" => Down,
+            "End" => End,
+            "Enter" => Enter,
+            "Esc" => Escape,
+            "F1" => F1,
+            "F2" => F2,
+            "F3" => F3,
+            "F4" => F4,
+            "F5" => F5,
+            "F6" => F6,
+            "F7" => F7,
+            "F8" => F8,
+            "F9" => F9,
+            "F10" => F10,
+            "F11" => F11,
+            "F12" => F12,
+            "Home" => Home,
+            "Insert" => Insert,
+            "Left" => Left,
+            "PageDown" => PageDown,
+            "PageUp" => PageUp,
+            "Right" => Right,
+            "Space" => Space,
+            "Tab" => Tab,
+            "Up" => Up,
+            _ => return None,
         };
+    Some(key)
+}
 
-    Some(control_constructor(alt_constructor(shift_constructor(key))))
+/// Get the name of a special key or None if it is a character.
+fn key_name(key: &Key) -> Option<&'static str> {
+    let name =
+        match *key {
+            Backspace => "BS",
+            Delete => "Del",
+            Down => "Down",
+            End => "End",
+            Enter => "Enter",
+            Escape => "Esc",
+            F1 => "F1",
+            F2 => "F2",
+            F3 => "F3",
+            F4 => "F4",
+            F5 => "F5",
+            F6 => "F6",
+            F7 => "F7",
+            F8 => "F8",
+            F9 => "F9",
+            F10 => "F10",
+            F11 => "F11",
+            F12 => "F12",
+            Home => "Home",
+            Insert => "Insert",
+            Left => "Left",
+            PageDown => "PageDown",
+            PageUp => "PageUp",
+            Right => "Right",
+            Space => "Space",
+            Tab => "Tab",
+            Up => "Up",
+            _ => return None,
+        };
+    Some(name)
 }
 
 fn is_char(key: &Key) -> bool {
@@ -75,9 +361,9 @@ fn is_char(key: &Key) -> bool {
 }
 
 #[allow(non_upper_case_globals)]
-fn to_key(key: &EventKey) -> Option<Key> {
+fn to_key(keyval: u32) -> Option<Key> {
     let key =
-        match key.get_keyval() {
+        match keyval {
             _0 | KP_0 => Char('0'),
             _1 | KP_1 => Char('1'),
             _2 | KP_2 => Char('2'),
@@ -145,6 +431,7 @@ fn to_key(key: &EventKey) -> Option<Key> {
             key::apostrophe => Char('\''),
             key::asciicircum => Char('^'),
             key::asciitilde => Char('~'),
+            key::asterisk | key::KP_Multiply => Char('*'),
             key::ampersand => Char('&'),
             key::at => Char('@'),
             key::backslash => Char('\\'),
@@ -156,13 +443,14 @@ fn to_key(key: &EventKey) -> Option<Key> {
             key::bracketright => Char(']'),
             key::ccedilla => Char('ç'),
             key::Ccedilla => Char('Ç'),
+            key::colon => Char(':'),
             key::comma => Char(','),
-            key::Delete => Delete,
+            key::Delete | key::KP_Delete => Delete,
             key::dollar => Char('$'),
-            key::Down => Down,
+            key::Down | key::KP_Down => Down,
             key::eacute => Char('é'),
             key::Eacute => Char('É'),
-            key::End => End,
+            key::End | key::KP_End => End,
             key::egrave => Char('è'),
             key::Egrave => Char('È'),
             key::equal => Char('='),
@@ -180,32 +468,55 @@ fn to_key(key: &EventKey) -> Option<Key> {
             key::F10 => F10,
             key::F11 => F11,
             key::F12 => F12,
-            key::Home => Home,
-            key::Insert => Insert,
+            key::grave => Char('`'),
+            key::Home | key::KP_Home => Home,
+            key::Insert | key::KP_Insert => Insert,
             key::ISO_Left_Tab | key::Tab => Tab,
-            key::Left => Left,
-            key::leftanglebracket => Char('<'),
+            key::Left | key::KP_Left => Left,
+            key::leftanglebracket | key::less => Char('<'),
             key::minus => Char('-'),
             key::multiply => Char('*'),
             key::numbersign => Char('#'),
-            key::Page_Down => PageDown,
-            key::Page_Up => PageUp,
+            key::Page_Down | key::KP_Page_Down => PageDown,
+            key::Page_Up | key::KP_Page_Up => PageUp,
             key::parenleft => Char('('),
             key::parenright => Char(')'),
             key::percent => Char('%'),
             key::period => Char('.'),
             key::plus => Char('+'),
-            Return => Enter,
-            key::Right => Right,
-            key::rightanglebracket => Char('>'),
+            Return | key::KP_Enter => Enter,
+            key::Right | key::KP_Right => Right,
+            key::greater | key::rightanglebracket => Char('>'),
             key::question => Char('?'),
             key::quotedbl => Char('"'),
             key::semicolon => Char(';'),
             key::slash => Char('/'),
-            key::space => Space,
+            key::space | key::KP_Space => Space,
             key::underscore => Char('_'),
-            key::Up => Up,
+            key::Up | key::KP_Up => Up,
             _ => return None,
         };
     Some(key)
 }
+
+#[cfg(test)]
+mod tests {
+    use gdk::enums::key;
+
+    use super::is_dead_or_compose_key;
+
+    #[test]
+    fn test_dead_and_compose_keys() {
+        // These keys are left to the input method to compose a character.
+        assert!(is_dead_or_compose_key(key::dead_grave));
+        assert!(is_dead_or_compose_key(key::dead_acute));
+        assert!(is_dead_or_compose_key(key::dead_circumflex));
+        assert!(is_dead_or_compose_key(key::dead_diaeresis));
+        assert!(is_dead_or_compose_key(key::Multi_key));
+        // The characters themselves are not dead keys.
+        assert!(!is_dead_or_compose_key(key::acute));
+        assert!(!is_dead_or_compose_key(key::asciicircum));
+        assert!(!is_dead_or_compose_key(key::a));
+        assert!(!is_dead_or_compose_key(key::Escape));
+    }
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+//! Command line lexer.
+
+use std::ops::Range;
+
+/// A word of the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    /// The byte range of the token in the command line, including the quotes.
+    pub span: Range<usize>,
+    /// The text of the token, without the quotes and the escaping backslashes.
+    pub text: String,
+}
+
+/// Split the command line into words.
+/// The words are separated by whitespaces, unless they are in double quotes or escaped with a
+/// backslash. A backslash also escapes a double quote or another backslash.
+/// An unterminated quote runs until the end of the command line.
+pub fn lex_command_line(command_line: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut current: Option<Token> = None;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (index, character) in command_line.char_indices() {
+        let end = index + character.len_utf8();
+        if !escaped && !in_quotes && character.is_whitespace() {
+            if let Some(token) = current.take() {
+                tokens.push(token);
+            }
+            continue;
+        }
+        let token = current.get_or_insert_with(|| Token {
+            span: index..index,
+            text: String::new(),
+        });
+        token.span.end = end;
+        if escaped {
+            token.text.push(character);
+            escaped = false;
+        }
+        else if character == '\\' {
+            escaped = true;
+        }
+        else if character == '"' {
+            in_quotes = !in_quotes;
+        }
+        else {
+            token.text.push(character);
+        }
+    }
+    if let Some(token) = current {
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Get the index of the argument under the cursor at the end of the command line.
+/// The command name has the index 0.
+/// When the command line ends with a whitespace, a new argument is started.
+pub fn current_argument_index(command_line: &str) -> usize {
+    let tokens = lex_command_line(command_line);
+    match tokens.last() {
+        Some(token) if token.span.end == command_line.len() => tokens.len() - 1,
+        _ => tokens.len(),
+    }
+}
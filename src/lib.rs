@@ -25,7 +25,6 @@
  * TODO: smart selection (select all on first time, select all except the prefix on the second).
  * TODO: remove blocking dialogs?
  * FIXME: can block in input mode.
- * TODO: use MainLoop::new() instead of gtk::main()?
  * FIXME: the commands in the config file are not executed.
  * TODO: take advantage of IntoOption and IntoPair.
  * TODO: automatically connect the AppClose message to Quit if it exists.
@@ -49,12 +48,19 @@
 )]
 
 extern crate gdk;
+extern crate gdk_sys;
+extern crate gio;
+extern crate gio_sys;
 extern crate glib;
 extern crate gtk;
+extern crate libc;
 #[macro_use]
 extern crate log;
 #[macro_use]
 extern crate mg_settings;
+#[cfg(test)]
+#[macro_use]
+extern crate mg_settings_macros;
 extern crate pango;
 #[macro_use]
 extern crate relm;
@@ -66,6 +72,7 @@ mod app;
 pub mod completion;
 mod file;
 mod key_converter;
+mod lexer;
 
 /// List of modes
 pub type Modes = &'static [Mode];
@@ -79,45 +86,123 @@ pub struct Mode {
     pub show_count: bool,
 }
 
-pub use app::{Mg, parse_config};
+pub use app::{
+    CloseDecision,
+    CloseReason,
+    CommandFilterResult,
+    CommandSource,
+    ConfigSource,
+    FilteredCommand,
+    LogEntry,
+    Mapping,
+    MessageKind,
+    Mg,
+    MgBuilder,
+    PrefillMode,
+    builtin_application_commands,
+    expand_path,
+    parse_color,
+    parse_config,
+};
 pub use app::Msg::{
     Alert,
+    Alias,
     AppClose,
+    BackgroundError,
+    CloseHandler,
+    CloseRequested,
     CloseWin,
+    ColorSetting,
+    CommandCategory,
+    CommandContext,
+    CommandExecuted,
+    CommandFilter,
+    CommandResult,
+    CompleteNextAccepts,
     Completers,
+    CompletionItemsDeleted,
     CompletionViewChange,
+    Confirm,
     CustomCommand,
+    CustomCommandId,
     CustomDialog,
     DarkTheme,
+    DefaultConfigCreated,
     DeleteCompletionItem,
+    DialogAnswer,
+    DialogShortcut,
     Error,
+    ErrorColorSetting,
+    ErrorSticky,
+    ExecuteCommand,
+    ExecuteRemoteCommand,
+    FlushMessageLog,
+    HistoryFilter,
+    HistoryPersistence,
+    IdentifierPrefill,
+    IdentifierVisible,
     Info,
+    InfoWithDuration,
+    InitialCommandsExecuted,
+    InputEscapeChord,
+    ListSetting,
+    ListSettingChanged,
+    LocalSettingChanged,
+    MapMouseButton,
     Message,
     ModeChanged,
     Question,
+    QuestionAsync,
+    Ready,
+    RefreshCompletions,
+    RemoveDialogShortcut,
+    SetMessageLogFile,
     SetMode,
     SetSetting,
+    SettingBounds,
+    SettingCategory,
     SettingChanged,
+    SettingTransition,
+    SettingValidator,
+    SettingsPersistence,
+    ShellCommandCompleted,
+    ShellCommands,
+    SlowCommandThreshold,
+    StatusBarItemPriority,
+    StopCommandChainOnError,
     Title,
+    UserCommand,
     Variables,
     Warning,
+    WatchConfig,
 };
 pub use app::dialog::{
     BlockingInputDialog,
     DialogBuilder,
     DialogResult,
+    IdentifiedDialog,
     InputDialog,
     Responder,
     blocking_dialog,
     blocking_input,
+    blocking_input_with_default,
+    blocking_password_input,
     blocking_question,
     blocking_yes_no_question,
+    confirm,
+    confirm_with_timeout,
     input,
+    input_with_completion,
+    input_with_default,
+    labeled_question,
+    password_input,
     question,
     yes_no_question,
 };
-pub use app::settings::{DefaultConfig, NoSettings};
-pub use app::status_bar::{StatusBar, StatusBarItem};
+pub use app::settings::{DefaultConfig, NoSettings, SettingValues};
+pub use key_converter::{has_super_modifier, keyval_to_key, string_to_key};
+pub use lexer::{Token, current_argument_index, lex_command_line};
+pub use app::status_bar::{DEFAULT_ITEM_PRIORITY, StatusBar, StatusBarItem};
 pub use app::status_bar::ItemMsg::{Color, Text};
 
 #[macro_export]
@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use gtk::Cast;
+use libxdo::XDo;
+use mg::{
+    Alias,
+    ConfigSource,
+    CustomCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, emit_after, has_label, has_row, run_main_loop};
+
+pub struct Model {
+    checked: usize,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    CheckCompletion,
+    CheckWarning,
+    Command(AppCommand),
+}
+
+/// Write the config of the test, which defines an alias.
+fn config_path() -> PathBuf {
+    let path = env::temp_dir().join("mg-test-aliases");
+    let mut file = File::create(&path).expect("create config");
+    write!(file, "alias o open\n").expect("write config");
+    path
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(Alias("quit".to_string(), "open".to_string()));
+        emit_after(&self.model.relm, 100, || CheckWarning);
+        emit_after(&self.model.relm, 800, || CheckCompletion);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            checked: 0,
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+        match event {
+            CheckCompletion => {
+                // The alias is completed with its expansion.
+                assert!(has_row(&window, "alias: open"));
+                self.model.checked += 1;
+            },
+            CheckWarning => {
+                assert!(has_label(&window, "The alias quit shadows the command quit"));
+                self.model.checked += 1;
+            },
+            Command(Open(url)) => {
+                // The alias of the config is replaced by its expansion.
+                assert_eq!(self.model.checked, 2);
+                assert_eq!(url, "crates.io");
+                gtk::main_quit();
+            },
+            Command(Quit) => panic!("The alias must be expanded"),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[]).config(ConfigSource::new(config_path()))) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_aliases() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        xdo.enter_text(":o", 0).unwrap();
+        thread::sleep(Duration::from_millis(700));
+        xdo.enter_text(" crates.io", 0).unwrap();
+        xdo.send_keysequence("Return", 0).unwrap();
+    });
+
+    run_main_loop();
+}
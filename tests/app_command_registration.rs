@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+
+use mg::{MgBuilder, NoSettings};
+
+#[derive(Commands)]
+pub enum AppCommand {
+    Open(String),
+    Quit,
+}
+
+#[test]
+#[should_panic(expected = "Cannot register the application command entry-end: it is a built-in command.")]
+fn test_app_command_builtin_name() {
+    let _builder: MgBuilder<AppCommand, NoSettings> = MgBuilder::new(&[])
+        .app_command("entry-end", "Move to the end", |_| ());
+}
+
+#[test]
+#[should_panic(expected = "Duplicate application command open-home.")]
+fn test_app_command_duplicate_name() {
+    let _builder: MgBuilder<AppCommand, NoSettings> = MgBuilder::new(&[])
+        .app_command("open-home", "Open the home page", |_| ())
+        .app_command("open-home", "Open the home page", |_| ());
+}
@@ -0,0 +1,75 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use mg::completion::{ArgumentsCompleter, Completer, CompletionResult};
+
+/// A completer listing the items starting with the input.
+struct ListCompleter {
+    items: Vec<&'static str>,
+}
+
+impl Completer for ListCompleter {
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        self.items.iter()
+            .filter(|item| item.starts_with(input))
+            .map(|item| CompletionResult::new(&[*item]))
+            .collect()
+    }
+}
+
+fn names(results: Vec<CompletionResult>) -> Vec<String> {
+    results.into_iter()
+        .map(|result| result.columns[0].value.clone())
+        .collect()
+}
+
+fn tag_completer() -> ArgumentsCompleter {
+    ArgumentsCompleter::new("tag", vec![
+        Box::new(ListCompleter { items: vec!["add", "remove"] }),
+        Box::new(ListCompleter { items: vec!["green", "red"] }),
+    ])
+}
+
+#[test]
+fn test_arguments_completer_first_argument() {
+    let mut completer = tag_completer();
+    assert_eq!(names(completer.completions("")), vec!["add", "remove"]);
+    assert_eq!(names(completer.completions("re")), vec!["remove"]);
+    assert_eq!(completer.complete_result("remove"), "tag remove");
+}
+
+#[test]
+fn test_arguments_completer_next_argument() {
+    let mut completer = tag_completer();
+    // After accepting the first argument, which appends a space, the next argument is completed.
+    assert_eq!(names(completer.completions("add ")), vec!["green", "red"]);
+    assert_eq!(completer.complete_result("red"), "tag add red");
+    assert_eq!(names(completer.completions("add g")), vec!["green"]);
+    assert_eq!(completer.complete_result("green"), "tag add green");
+}
+
+#[test]
+fn test_arguments_completer_extra_argument() {
+    let mut completer = tag_completer();
+    assert!(completer.completions("add red ").is_empty());
+}
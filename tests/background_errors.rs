@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::Cast;
+use libxdo::XDo;
+use mg::{
+    BackgroundError,
+    Mg,
+    MgBuilder,
+    NoSettings,
+    input,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, emit_after, has_label, has_label_containing, run_main_loop};
+
+pub struct Model {
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(Option<String>),
+    CheckDelayed,
+    CheckImmediate,
+    CheckShown,
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        input(&self.mg, &self.model.relm, "Name".to_string(), String::new(), Answered);
+        self.mg.emit(BackgroundError("first background error".into()));
+        self.mg.emit(BackgroundError("second background error".into()));
+        emit_after(&self.model.relm, 200, || CheckDelayed);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+        match event {
+            Answered(answer) => {
+                assert_eq!(answer, None);
+                emit_after(&self.model.relm, 200, || CheckShown);
+            },
+            CheckDelayed => {
+                // The errors do not interrupt the input and are only counted.
+                assert!(!has_label_containing(&window, "background error"));
+                assert!(has_label(&window, "2⚠"));
+                thread::spawn(|| {
+                    let xdo = XDo::new(None).unwrap();
+                    thread::sleep(Duration::from_millis(200));
+                    xdo.send_keysequence("Escape", 0).unwrap();
+                });
+            },
+            CheckImmediate => {
+                assert!(has_label_containing(&window, "third background error"));
+                gtk::main_quit();
+            },
+            CheckShown => {
+                // The errors are shown when returning to the normal mode.
+                assert!(has_label_containing(&window, "second background error (and 1 other background errors)"));
+                assert!(!has_label(&window, "2⚠"));
+                // Without input, a background error is shown immediately.
+                self.mg.emit(BackgroundError("third background error".into()));
+                emit_after(&self.model.relm, 200, || CheckImmediate);
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_background_errors() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    run_main_loop();
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::Cast;
+use libxdo::XDo;
+use mg::{
+    CustomCommand,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    Mode,
+    ModeChanged,
+    Modes,
+    SetMode,
+    SettingChanged,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::AppSettingsVariant::{self, Boolean};
+use utils::{AppCommand, AppSettings, XDoExt, emit_after, entry_text, run_main_loop};
+
+static MODES: Modes = &[
+    Mode { name: "insert", prefix: "i", show_count: false },
+];
+
+pub struct Model {
+    booleans: Vec<bool>,
+    mode: String,
+    opened: Vec<String>,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Batch,
+    CheckBatch,
+    Command(AppCommand),
+    NewMode(String),
+    Setting(AppSettingsVariant),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(SetMode("insert"));
+        emit_after(&self.model.relm, 300, || Batch);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            booleans: vec![],
+            mode: String::new(),
+            opened: vec![],
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Batch => {
+                // The commands executed programmatically are not activated.
+                self.mg.emit(ExecuteCommand("set boolean = true".to_string()));
+                self.mg.emit(ExecuteCommand("nmap x open x".to_string()));
+                emit_after(&self.model.relm, 300, || CheckBatch);
+            },
+            CheckBatch => {
+                // The batch does not change the mode nor the entry.
+                let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+                assert_eq!(self.model.mode, "insert");
+                assert_eq!(entry_text(&window), Some(String::new()));
+                assert_eq!(self.model.booleans, vec![true]);
+                self.mg.emit(SetMode("normal"));
+                thread::spawn(|| {
+                    let xdo = XDo::new(None).unwrap();
+                    thread::sleep(Duration::from_millis(200));
+                    xdo.enter_command("set boolean = false");
+                    xdo.enter_command("nmap y open y");
+                    xdo.send_keysequence("x", 0).unwrap();
+                    xdo.send_keysequence("y", 0).unwrap();
+                });
+            },
+            Command(Open(url)) => {
+                self.model.opened.push(url);
+                if self.model.opened.len() == 2 {
+                    // The same commands activated from the entry have the same effect, and then
+                    // return to the normal mode with an empty entry.
+                    let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+                    assert_eq!(self.model.opened, vec!["x".to_string(), "y".to_string()]);
+                    assert_eq!(self.model.mode, "normal");
+                    assert_eq!(entry_text(&window), Some(String::new()));
+                    assert_eq!(self.model.booleans, vec![true, false]);
+                    gtk::main_quit();
+                }
+            },
+            Command(Quit) => (),
+            NewMode(mode) => self.model.mode = mode,
+            Setting(Boolean(boolean)) => self.model.booleans.push(boolean),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(MODES)) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+            ModeChanged(ref mode) => NewMode(mode.clone()),
+            SettingChanged(ref setting) => Setting(setting.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_batch_and_activated_commands() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    run_main_loop();
+}
@@ -0,0 +1,182 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::Cast;
+use libxdo::XDo;
+use mg::{
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    SettingValues,
+};
+use mg_settings::Value;
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, emit_after, has_label, has_row, run_main_loop};
+
+/// The commands executed by the test, with the check of the window done after each command.
+const STEPS: &[(&str, fn(&gtk::Widget) -> bool)] = &[
+    ("set error-color = notacolor", shows_invalid_color_error),
+    ("set error-color?", keeps_previous_color),
+    ("set error-color = \"rgba(0, 0, 255, 0.5)\"", |_| true),
+    ("set error-color?", sets_rgba_color),
+    ("set error-color = \"#0f0\"", |_| true),
+    ("set error-color?", sets_hex_color),
+];
+
+#[derive(Settings)]
+pub struct AppSettings {
+    error_color: String,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            error_color: "red".to_string(),
+        }
+    }
+}
+
+impl SettingValues for AppSettings {
+    fn get_value(&self, name: &str) -> Option<Value> {
+        match name {
+            "error-color" => Some(Value::Str(self.error_color.clone())),
+            _ => None,
+        }
+    }
+}
+
+pub struct Model {
+    relm: Relm<Win>,
+    step: usize,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Check,
+    CheckCompletion,
+    Next,
+}
+
+/// Check that an invalid color is reported with the offending text.
+fn shows_invalid_color_error(window: &gtk::Widget) -> bool {
+    has_label(window, "Invalid color for setting error-color: notacolor")
+}
+
+/// Check that an invalid color does not change the setting.
+fn keeps_previous_color(window: &gtk::Widget) -> bool {
+    has_label(window, "error-color=red")
+}
+
+/// Check that a color with an alpha component is accepted.
+fn sets_rgba_color(window: &gtk::Widget) -> bool {
+    has_label(window, "error-color=rgba(0, 0, 255, 0.5)")
+}
+
+/// Check that a short hexadecimal color is accepted.
+fn sets_hex_color(window: &gtk::Widget) -> bool {
+    has_label(window, "error-color=#0f0")
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        emit_after(&self.model.relm, 500, || Next);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+            step: 0,
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+        match event {
+            Check => {
+                let (command, check) = STEPS[self.model.step];
+                assert!(check(&window), "Unexpected result for the command {}", command);
+                self.model.step += 1;
+                if self.model.step < STEPS.len() {
+                    self.model.relm.stream().emit(Next);
+                }
+                else {
+                    thread::spawn(|| {
+                        let xdo = XDo::new(None).unwrap();
+                        thread::sleep(Duration::from_millis(200));
+                        xdo.enter_text(":set error-color = ma", 0).unwrap();
+                    });
+                    emit_after(&self.model.relm, 1500, || CheckCompletion);
+                }
+            },
+            CheckCompletion => {
+                // The named colors are completed.
+                assert!(has_row(&window, "magenta"));
+                assert!(!has_row(&window, "blue"));
+                gtk::main_quit();
+            },
+            Next => {
+                self.mg.emit(ExecuteCommand(STEPS[self.model.step].0.to_string()));
+                emit_after(&self.model.relm, 300, || Check);
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[])
+            .error_color_setting("error-color"))
+        {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_color_setting() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    run_main_loop();
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+
+use mg::completion::{CommandCompleter, Completer, CompletionResult};
+
+#[derive(Commands)]
+pub enum AppCommand {
+    Open(String),
+    Quit,
+}
+
+fn names(results: Vec<CompletionResult>) -> Vec<String> {
+    results.into_iter()
+        .map(|result| result.columns[0].value.clone())
+        .collect()
+}
+
+#[test]
+fn test_command_completer_lists_builtin_commands() {
+    let names = names(CommandCompleter::<AppCommand>::new().completions(""));
+    for name in &["alias", "command", "entry-end", "help", "map", "open", "set", "unmap"] {
+        assert!(names.contains(&name.to_string()), "{} is not completed", name);
+    }
+}
+
+#[test]
+fn test_command_completer_application_quit() {
+    let names = names(CommandCompleter::<AppCommand>::new().completions("quit"));
+    assert_eq!(names.iter().filter(|name| *name == "quit").count(), 1);
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use gtk::Continue;
+use libxdo::XDo;
+use mg::{
+    CommandContext,
+    CommandSource,
+    ConfigSource,
+    CustomCommand,
+    ExecuteCommand,
+    ExecuteRemoteCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, XDoExt, run_main_loop};
+
+pub struct Model {
+    /// The source received before each open command, with the argument of the command.
+    commands: Vec<(String, CommandSource)>,
+    relm: Relm<Win>,
+    source: Option<CommandSource>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Check,
+    Command(AppCommand),
+    Context(CommandSource),
+}
+
+/// Write the config of the test, which executes a command on its second line.
+fn config_path() -> PathBuf {
+    let path = env::temp_dir().join("mg-test-command-source");
+    let mut file = File::create(&path).expect("create config");
+    write!(file, "nmap x open mapping\nopen config\n").expect("write config");
+    path
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(ExecuteCommand("open programmatic".to_string()));
+        self.mg.emit(ExecuteRemoteCommand("open remote".to_string()));
+        let stream = self.model.relm.stream().clone();
+        gtk::timeout_add(3000, move || {
+            stream.emit(Check);
+            Continue(false)
+        });
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            commands: vec![],
+            relm: relm.clone(),
+            source: None,
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Check => {
+                let source = |argument: &str| {
+                    self.model.commands.iter()
+                        .find(|&&(ref command_argument, _)| command_argument == argument)
+                        .map(|&(_, ref source)| source.clone())
+                };
+                match source("config") {
+                    Some(CommandSource::Config { file, line }) => {
+                        assert_eq!(file, Some(config_path()));
+                        assert_eq!(line, Some(2));
+                    },
+                    source => panic!("Unexpected source for the config: {:?}", source),
+                }
+                match source("mapping") {
+                    Some(CommandSource::Mapping { ref keys }) => assert_eq!(keys.len(), 1),
+                    source => panic!("Unexpected source for the mapping: {:?}", source),
+                }
+                match source("programmatic") {
+                    Some(CommandSource::Programmatic) => (),
+                    source => panic!("Unexpected source for execute_command(): {:?}", source),
+                }
+                match source("remote") {
+                    Some(CommandSource::Remote) => (),
+                    source => panic!("Unexpected source for execute_remote_command(): {:?}", source),
+                }
+                match source("typed") {
+                    Some(CommandSource::Typed) => (),
+                    source => panic!("Unexpected source for the typed command: {:?}", source),
+                }
+                gtk::main_quit();
+            },
+            Command(Open(argument)) => {
+                let source = self.model.source.take().expect("command context");
+                self.model.commands.push((argument, source));
+            },
+            Command(Quit) => (),
+            Context(source) => self.model.source = Some(source),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[]).config(ConfigSource::new(config_path()))) {
+            gtk::Label {
+            },
+            CommandContext(ref source) => Context(source.clone()),
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_command_source() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        thread::sleep(Duration::from_millis(1000));
+        let xdo = XDo::new(None).unwrap();
+        xdo.enter_command("open typed");
+        xdo.send_keysequence("x", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -42,13 +42,19 @@ use libxdo::XDo;
 use mg::{
     CustomCommand,
     Mg,
+    MgBuilder,
+    Modes,
+    SettingValues,
 };
+use mg_settings::Value;
 use relm::{Widget, init_test};
 use relm_attributes::widget;
 
 use self::AppCommand::*;
 use self::Msg::*;
-use utils::XDoExt;
+use utils::{XDoExt, run_main_loop};
+
+static MODES: Modes = &[];
 
 #[derive(Commands)]
 pub enum AppCommand {
@@ -62,6 +68,15 @@ pub struct AppSettings {
     boolean: bool,
 }
 
+impl SettingValues for AppSettings {
+    fn get_value(&self, name: &str) -> Option<Value> {
+        match name {
+            "boolean" => Some(Value::Bool(self.boolean)),
+            _ => None,
+        }
+    }
+}
+
 pub struct Model {
     text: String,
 }
@@ -92,7 +107,8 @@ impl Widget for Win {
 
     view! {
         #[name="mg"]
-        Mg<AppCommand, AppSettings>(&[], Ok("examples/main.conf".into()), None, vec![]) {
+        // NOTE: the model parameter before MgBuilder is still accepted.
+        Mg<AppCommand, AppSettings>(MgBuilder::from((MODES, Ok("examples/main.conf".into()), None, vec![]))) {
             #[name="label"]
             gtk::Label {
                 text: &self.model.text,
@@ -117,7 +133,7 @@ fn test_basic_command() {
         xdo.enter_command("quit");
     });
 
-    gtk::main();
+    run_main_loop();
 
     // TODO: send a message with a Sender to fetch te text?
     //assert_eq!(Some("Showing text: test".to_string()), win.widget().label.get_text());
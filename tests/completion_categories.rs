@@ -0,0 +1,84 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+
+extern crate mg;
+
+use std::collections::HashMap;
+
+use mg::completion::{Completer, CompletionResult, HelpCompleter};
+
+fn entries() -> Vec<(String, String)> {
+    vec![
+        ("back".to_string(), "Go back in the history".to_string()),
+        ("forward".to_string(), "Go forward in the history".to_string()),
+        ("open".to_string(), "Open an URL".to_string()),
+        ("set home".to_string(), "The home page".to_string()),
+    ]
+}
+
+fn rows(results: Vec<CompletionResult>) -> Vec<Vec<String>> {
+    results.into_iter()
+        .map(|result| result.columns.into_iter().map(|cell| cell.value).collect())
+        .collect()
+}
+
+#[test]
+fn test_help_without_category() {
+    let mut completer = HelpCompleter::new(entries());
+    assert_eq!(completer.column_count(), 2);
+    assert_eq!(completer.text_column(), 0);
+    assert_eq!(rows(completer.completions("open")), vec![vec!["open", "Open an URL"]]);
+}
+
+#[test]
+fn test_help_grouped_by_category() {
+    let mut categories = HashMap::new();
+    categories.insert("back".to_string(), "navigation".to_string());
+    categories.insert("forward".to_string(), "navigation".to_string());
+    categories.insert("set home".to_string(), "browsing".to_string());
+    let mut completer = HelpCompleter::new(entries())
+        .categories(&categories);
+    assert_eq!(completer.column_count(), 3);
+    assert_eq!(completer.text_column(), 1);
+    assert_eq!(rows(completer.completions("")), vec![
+        vec!["browsing", "set home", "The home page"],
+        vec!["navigation", "back", "Go back in the history"],
+        vec!["", "forward", "Go forward in the history"],
+        vec!["misc", "open", "Open an URL"],
+    ]);
+    assert_eq!(rows(completer.completions("navigation")), vec![
+        vec!["navigation", "back", "Go back in the history"],
+        vec!["", "forward", "Go forward in the history"],
+    ]);
+    assert_eq!(rows(completer.completions("misc")), vec![vec!["misc", "open", "Open an URL"]]);
+}
+
+#[test]
+fn test_help_ignores_case() {
+    let mut categories = HashMap::new();
+    categories.insert("back".to_string(), "navigation".to_string());
+    let mut completer = HelpCompleter::new(entries());
+    assert_eq!(rows(completer.completions("OPEN")), vec![vec!["open", "Open an URL"]]);
+    let mut completer = HelpCompleter::new(entries())
+        .categories(&categories);
+    assert_eq!(rows(completer.completions("Navigation")), vec![vec!["navigation", "back", "Go back in the history"]]);
+}
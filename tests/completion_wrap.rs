@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use libxdo::XDo;
+use mg::{
+    ConfigSource,
+    CustomCommand,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+};
+use relm::{Widget, init_test};
+use relm_attributes::widget;
+
+use self::AppCommand::*;
+use self::Msg::*;
+use utils::{AppSettings, run_main_loop};
+
+#[derive(Commands, Debug, PartialEq)]
+pub enum AppCommand {
+    #[help(text="Zz first command")]
+    Za,
+    #[help(text="Zz second command")]
+    Zb,
+    #[help(text="Zz third command")]
+    Zc,
+    Open(String),
+}
+
+pub struct Model {
+    executed: Vec<AppCommand>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Command(AppCommand),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(ExecuteCommand("set completion-wrap = wrap".to_string()));
+    }
+
+    fn model() -> Model {
+        Model {
+            executed: vec![],
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Command(command) => {
+                self.model.executed.push(command);
+                match self.model.executed.len() {
+                    1 => self.mg.emit(ExecuteCommand("set completion-wrap = stop".to_string())),
+                    2 => self.mg.emit(ExecuteCommand("set completion-wrap = unselect".to_string())),
+                    _ => {
+                        // Five complete-next over three completions:
+                        // wrap selects the first one again after the last one;
+                        // stop stays on the last one;
+                        // unselect restores the input after the last one.
+                        assert_eq!(self.model.executed, vec![Zb, Zc, Za]);
+                        gtk::main_quit();
+                    },
+                }
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[]).config(ConfigSource::new("examples/main.conf"))) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_completion_wrap() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(500));
+            xdo.enter_text(":zz", 0).unwrap();
+            for _ in 0..5 {
+                xdo.send_keysequence("Tab", 0).unwrap();
+            }
+            xdo.send_keysequence("Return", 0).unwrap();
+        }
+    });
+
+    run_main_loop();
+}
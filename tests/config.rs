@@ -0,0 +1,175 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+
+use std::env;
+use std::fs::{File, create_dir_all};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use mg::{ConfigSource, parse_config};
+
+#[derive(Commands)]
+pub enum AppCommand {
+    Open(String),
+    Quit,
+}
+
+/// Create an empty directory for the files of a test.
+fn test_dir(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("mg-test-config-{}", name));
+    let _ = ::std::fs::remove_dir_all(&directory);
+    create_dir_all(&directory).expect("create test directory");
+    directory
+}
+
+fn write_file(path: &Path, content: &str) {
+    let mut file = File::create(path).expect("create file");
+    write!(file, "{}", content).expect("write file");
+}
+
+/// Get the messages of the errors of the config.
+fn errors(config: &Path, include_path: Option<PathBuf>) -> Vec<String> {
+    source_errors(ConfigSource::new(config), include_path)
+}
+
+/// Get the messages of the errors of the config source.
+fn source_errors(source: ConfigSource, include_path: Option<PathBuf>) -> Vec<String> {
+    let (_, parse_result, _) = parse_config::<AppCommand>(vec![source], &[], include_path);
+    parse_result.errors.iter()
+        .map(|error| error.to_string())
+        .collect()
+}
+
+/// Check that an error ends with the message.
+fn assert_error(errors: &[String], message: &str) {
+    assert!(errors.iter().any(|error| error.ends_with(message)), "no error {} in {:?}", message, errors);
+}
+
+#[test]
+fn test_missing_include() {
+    let directory = test_dir("missing-include");
+    let config = directory.join("config");
+    write_file(&config, "include missing.conf\n");
+
+    let errors = errors(&config, Some(directory.clone()));
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("failed to open included file"), "unexpected error: {}", errors[0]);
+}
+
+#[test]
+fn test_missing_include_path() {
+    let directory = test_dir("missing-include-path");
+    let config = directory.join("config");
+    write_file(&config, "include missing.conf\n");
+
+    // NOTE: the include path is not in a user directory, so it is not created and the include is
+    // only a warning.
+    let errors = errors(&config, Some(directory.join("includes")));
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+}
+
+#[test]
+fn test_include_cycle() {
+    let directory = test_dir("include-cycle");
+    let config = directory.join("config");
+    write_file(&config, "include a.conf\n");
+    write_file(&directory.join("a.conf"), "quit\ninclude config\n");
+
+    let errors = errors(&config, Some(directory.clone()));
+    assert_eq!(errors.len(), 1, "unexpected errors: {:?}", errors);
+    assert_error(&errors, "a.conf:2: include cycle: config → a.conf → config");
+}
+
+#[test]
+fn test_max_include_depth() {
+    let directory = test_dir("max-include-depth");
+    let config = directory.join("config");
+    write_file(&config, "include a.conf\n");
+    write_file(&directory.join("a.conf"), "quit\ninclude b.conf\n");
+    write_file(&directory.join("b.conf"), "quit\n");
+
+    // NOTE: b.conf is at the depth 2.
+    let source = ConfigSource::new(&config).max_include_depth(3);
+    assert!(source_errors(source, Some(directory.clone())).is_empty());
+
+    let source = ConfigSource::new(&config).max_include_depth(2);
+    let errors = source_errors(source, Some(directory.clone()));
+    assert_eq!(errors.len(), 1, "unexpected errors: {:?}", errors);
+    assert_error(&errors, &format!("a.conf:2: maximum include depth (2) exceeded when including `{}`",
+        directory.join("b.conf").to_string_lossy()));
+}
+
+#[test]
+fn test_error_line_numbers() {
+    let directory = test_dir("error-line-numbers");
+    let config = directory.join("config");
+    write_file(&config, "# Include the other file.\ninclude a.conf\nunknown-command\n");
+    write_file(&directory.join("a.conf"), "quit\n\nother-command\n");
+
+    let errors = errors(&config, Some(directory.clone()));
+    assert_eq!(errors.len(), 2, "unexpected errors: {:?}", errors);
+    assert_error(&errors, "a.conf:3: Not a command: other-command");
+    assert_error(&errors, "config:3: Not a command: unknown-command");
+}
+
+#[test]
+fn test_definition_error_line_numbers() {
+    let directory = test_dir("definition-error-line-numbers");
+    let config = directory.join("config");
+    write_file(&config, "keycode-map n <Bogus>\nalias\nkeycode-map n\nunknown-command\n");
+
+    let errors = errors(&config, None);
+    assert_eq!(errors.len(), 4, "unexpected errors: {:?}", errors);
+    assert_error(&errors, "config:1: keycode-map: Invalid keys <Bogus>");
+    assert_error(&errors, "config:2: alias: Argument required");
+    assert_error(&errors, "config:3: keycode-map: Argument required");
+    assert_error(&errors, "config:4: Not a command: unknown-command");
+}
+
+#[test]
+fn test_dialog_map_error_line_numbers() {
+    let directory = test_dir("dialog-map-error-line-numbers");
+    let config = directory.join("config");
+    write_file(&config, "dialog-map <C-y> y\n\ndialog-map <Bogus> n\nunknown-command\n");
+
+    let errors = errors(&config, None);
+    assert_eq!(errors.len(), 2, "unexpected errors: {:?}", errors);
+    assert_error(&errors, "config:3: dialog-map: Invalid key <Bogus>");
+    assert_error(&errors, "config:4: Not a command: unknown-command");
+}
+
+#[test]
+fn test_unknown_command_suggestion() {
+    let directory = test_dir("unknown-command-suggestion");
+    let config = directory.join("config");
+    write_file(&config, "quitt\n");
+
+    let errors = errors(&config, None);
+    assert_eq!(errors.len(), 1, "unexpected errors: {:?}", errors);
+    assert_error(&errors, "config:1: Not a command: quitt, did you mean: quit?");
+}
@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use std::env;
+use std::path::PathBuf;
+
+use mg::DefaultConfig;
+
+fn path(config: DefaultConfig) -> PathBuf {
+    match config {
+        DefaultConfig::Dir(path) => path.expect("directory path"),
+        DefaultConfig::File(path, _) => path.expect("file path"),
+        DefaultConfig::Tree(path, _) => path.expect("tree path"),
+    }
+}
+
+// NOTE: the environment is shared by the tests, so the overrides are all tested in the same test.
+#[test]
+fn test_xdg_dirs() {
+    env::set_var("HOME", "/home/user");
+    env::remove_var("XDG_CACHE_HOME");
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("XDG_DATA_HOME");
+    assert_eq!(path(DefaultConfig::xdg_cache_dir("app")), PathBuf::from("/home/user/.cache/app"));
+    assert_eq!(path(DefaultConfig::xdg_config_dir("app")), PathBuf::from("/home/user/.config/app"));
+    assert_eq!(path(DefaultConfig::xdg_config_file("app", "config", "")),
+        PathBuf::from("/home/user/.config/app/config"));
+    assert_eq!(path(DefaultConfig::xdg_data_dir("app")), PathBuf::from("/home/user/.local/share/app"));
+
+    env::set_var("XDG_CACHE_HOME", "/tmp/cache");
+    env::set_var("XDG_CONFIG_HOME", "/tmp/config");
+    env::set_var("XDG_DATA_HOME", "/tmp/data");
+    assert_eq!(path(DefaultConfig::xdg_cache_dir("app")), PathBuf::from("/tmp/cache/app"));
+    assert_eq!(path(DefaultConfig::xdg_config_dir("app")), PathBuf::from("/tmp/config/app"));
+    assert_eq!(path(DefaultConfig::xdg_config_file("app", "config", "")), PathBuf::from("/tmp/config/app/config"));
+    assert_eq!(path(DefaultConfig::xdg_data_dir("app")), PathBuf::from("/tmp/data/app"));
+
+    // A relative path is ignored.
+    env::set_var("XDG_CONFIG_HOME", "config");
+    assert_eq!(path(DefaultConfig::xdg_config_dir("app")), PathBuf::from("/home/user/.config/app"));
+
+    env::remove_var("HOME");
+    env::remove_var("XDG_CONFIG_HOME");
+    match DefaultConfig::xdg_config_dir("app") {
+        DefaultConfig::Dir(path) => assert!(path.is_err()),
+        _ => panic!("expected a directory"),
+    }
+}
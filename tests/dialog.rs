@@ -0,0 +1,118 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+
+use libxdo::XDo;
+use mg::{
+    ConfigSource,
+    Mg,
+    MgBuilder,
+    input,
+    yes_no_question,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, AppSettings, run_main_loop};
+
+pub struct Model {
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(String, Option<String>),
+    Confirmed(String, bool),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        // NOTE: the String is moved in the callback, which can thus only be called once.
+        let greeting = "hello".to_string();
+        input(&self.mg, &self.model.relm, "Name".to_string(), String::new(),
+            move |answer| Answered(greeting, answer));
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Answered(greeting, answer) => {
+                assert_eq!(greeting, "hello");
+                assert_eq!(answer, Some("world".to_string()));
+                let question = "Quit".to_string();
+                yes_no_question(&self.mg, &self.model.relm, "Quit?".to_string(),
+                    move |answer| Confirmed(question, answer));
+            },
+            Confirmed(question, answer) => {
+                assert_eq!(question, "Quit");
+                assert!(answer);
+                gtk::main_quit();
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[]).config(ConfigSource::new("examples/main.conf"))) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_callbacks_called_once() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        xdo.enter_text("world", 0).unwrap();
+        xdo.send_keysequence("Return", 0).unwrap();
+        xdo.enter_text("y", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use libxdo::XDo;
+use mg::{
+    Mg,
+    MgBuilder,
+    NoSettings,
+    confirm,
+    confirm_with_timeout,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, run_main_loop};
+
+pub struct Model {
+    answers: Vec<bool>,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(bool),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        confirm_with_timeout(&self.mg, &self.model.relm, "Reload?".to_string(), false, Duration::from_millis(300),
+            Answered);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            answers: vec![],
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Answered(answer) => {
+                self.model.answers.push(answer);
+                match self.model.answers.len() {
+                    // The first question is answered by its timeout.
+                    1 => confirm_with_timeout(&self.mg, &self.model.relm, "Close?".to_string(), false,
+                        Duration::from_millis(500), Answered),
+                    // The timeout of the second question is canceled by a key press.
+                    2 => confirm_with_timeout(&self.mg, &self.model.relm, "Save?".to_string(), false,
+                        Duration::from_millis(1000), Answered),
+                    // The timeout of the answered question does not answer the next one.
+                    3 => confirm(&self.mg, &self.model.relm, "Quit?".to_string(), true, Answered),
+                    _ => {
+                        assert_eq!(self.model.answers, vec![false, true, true, true]);
+                        gtk::main_quit();
+                    },
+                }
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_dialog_timeout() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        xdo.enter_text("x", 0).unwrap();
+        thread::sleep(Duration::from_millis(800));
+        xdo.enter_text("y", 0).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        xdo.enter_text("y", 0).unwrap();
+        thread::sleep(Duration::from_millis(1300));
+        xdo.send_keysequence("Return", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+
+use gtk::{BinExt, BoxExt, Cast, GtkWindowExt, WidgetExt};
+use libxdo::XDo;
+use mg::{
+    CustomCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, run_main_loop};
+
+#[derive(Msg)]
+pub enum Msg {
+    Command(AppCommand),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        let window = self.mg.widget().clone();
+        window.set_titlebar(&gtk::HeaderBar::new());
+        // Add a widget between the central widget and the status bar.
+        let container = window.get_child()
+            .and_then(|child| child.downcast::<gtk::Box>().ok())
+            .expect("container");
+        let label = gtk::Label::new("Embedded");
+        container.pack_start(&label, false, false, 0);
+        container.reorder_child(&label, 1);
+        window.show_all();
+    }
+
+    fn model() -> () {
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Command(Open(url)) => {
+                // The command entry of the status bar still works with a title bar and an embedded widget.
+                assert_eq!(url, "crates.io");
+                gtk::main_quit();
+            },
+            Command(Quit) => (),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_embedding() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        xdo.enter_text(":open crates.io", 0).unwrap();
+        xdo.send_keysequence("Return", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::Cast;
+use libxdo::XDo;
+use mg::{
+    ErrorSticky,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use mg_settings::errors::Error;
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, emit_after, has_label_containing, run_main_loop};
+
+pub struct Model {
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    CheckClosed,
+    CheckDetails,
+    ShowDetails,
+}
+
+/// Count the windows having a label containing the text.
+fn windows_with_label_containing(text: &str) -> usize {
+    gtk::Window::list_toplevels().iter()
+        .filter(|window| has_label_containing(&(*window).clone().upcast::<gtk::Widget>(), text))
+        .count()
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(ErrorSticky(Error::Msg("failure".to_string())));
+        emit_after(&self.model.relm, 300, || ShowDetails);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            CheckClosed => {
+                // Escape closes the details, while the error stays in the status bar.
+                assert_eq!(windows_with_label_containing("failure"), 1);
+                gtk::main_quit();
+            },
+            CheckDetails => {
+                assert_eq!(windows_with_label_containing("failure"), 2);
+                emit_after(&self.model.relm, 700, || CheckClosed);
+            },
+            ShowDetails => {
+                self.mg.emit(ExecuteCommand("show-error-details".to_string()));
+                emit_after(&self.model.relm, 300, || CheckDetails);
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_error_details() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(1000));
+        xdo.send_keysequence("Escape", 0).unwrap();
+    });
+
+    run_main_loop();
+}
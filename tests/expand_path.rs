@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use std::env;
+use std::path::PathBuf;
+
+use mg::expand_path;
+
+#[test]
+fn test_expand_home() {
+    env::set_var("HOME", "/home/mg");
+    assert_eq!(expand_path("~"), PathBuf::from("/home/mg"));
+    assert_eq!(expand_path("~/config/mg"), PathBuf::from("/home/mg/config/mg"));
+    assert_eq!(expand_path("/tmp/~"), PathBuf::from("/tmp/~"));
+}
+
+#[test]
+fn test_expand_user_home() {
+    // NOTE: the root user exists on every system and its home directory is not set by $HOME.
+    assert_eq!(expand_path("~root"), PathBuf::from("/root"));
+    assert_eq!(expand_path("~root/config"), PathBuf::from("/root/config"));
+    assert_eq!(expand_path("~no-such-mg-user/config"), PathBuf::from("~no-such-mg-user/config"));
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use libxdo::XDo;
+use mg::{
+    InputEscapeChord,
+    Mg,
+    MgBuilder,
+    NoSettings,
+    input,
+};
+use mg_settings::key::Key::Char;
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, run_main_loop};
+
+pub struct Model {
+    answers: Vec<Option<String>>,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(Option<String>),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(InputEscapeChord(vec![Char('j'), Char('k')], Duration::from_millis(500), true));
+        input(&self.mg, &self.model.relm, "Name".to_string(), String::new(), Answered);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            answers: vec![],
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Answered(answer) => {
+                self.model.answers.push(answer);
+                if self.model.answers.len() == 1 {
+                    input(&self.mg, &self.model.relm, "Name".to_string(), String::new(), Answered);
+                }
+                else {
+                    // The chord typed slowly is inserted in the entry.
+                    // The chord typed quickly exits the input and is removed from the text.
+                    assert_eq!(self.model.answers, vec![Some("jk".to_string()), Some("ab".to_string())]);
+                    gtk::main_quit();
+                }
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_input_escape_chord() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        xdo.enter_text("j", 0).unwrap();
+        thread::sleep(Duration::from_millis(1000));
+        xdo.enter_text("k", 0).unwrap();
+        xdo.send_keysequence("Return", 0).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        xdo.enter_text("abjk", 0).unwrap();
+    });
+
+    run_main_loop();
+}
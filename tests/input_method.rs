@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+
+use libxdo::XDo;
+use mg::{
+    ConfigSource,
+    CustomCommand,
+    Mg,
+    MgBuilder,
+};
+use relm::{Widget, init_test};
+use relm_attributes::widget;
+
+use self::AppCommand::*;
+use self::Msg::*;
+use utils::{AppSettings, run_main_loop};
+
+#[derive(Commands)]
+pub enum AppCommand {
+    #[help(text="Show the text")]
+    Show(String),
+    Quit,
+}
+
+pub struct Model {
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Command(AppCommand),
+}
+
+#[widget]
+impl Widget for Win {
+    fn model() -> Model {
+        Model {
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Command(command) => {
+                match command {
+                    Show(text) => {
+                        // NOTE: the dead key is composed with the next key by the input method
+                        // of the command entry instead of being handled as a shortcut.
+                        assert_eq!(text, "é");
+                        gtk::main_quit();
+                    },
+                    Quit => (),
+                }
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[]).config(ConfigSource::new("examples/main.conf"))) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_compose_in_command_entry() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        xdo.enter_text(":show ", 0).unwrap();
+        xdo.send_keysequence("dead_acute", 0).unwrap();
+        xdo.send_keysequence("e", 0).unwrap();
+        xdo.send_keysequence("Return", 0).unwrap();
+    });
+
+    run_main_loop();
+}
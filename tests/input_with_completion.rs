@@ -0,0 +1,141 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use gtk::{Cast, Continue};
+use libxdo::XDo;
+use mg::{
+    Completers,
+    ConfigSource,
+    Mg,
+    MgBuilder,
+    input_with_completion,
+};
+use mg::completion::{Completer, CompletionResult};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, AppSettings, has_row, run_main_loop};
+
+/// A completer listing the bookmarks starting with the input.
+struct BookmarkCompleter;
+
+impl Completer for BookmarkCompleter {
+    fn completions(&mut self, input: &str) -> Vec<CompletionResult> {
+        ["crates.io", "docs.rs", "github.com"].iter()
+            .filter(|bookmark| bookmark.starts_with(input))
+            .map(|bookmark| CompletionResult::new(&[*bookmark]))
+            .collect()
+    }
+}
+
+pub struct Model {
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(Option<String>),
+    Check,
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        let mut completers: HashMap<_, Box<Completer>> = HashMap::new();
+        completers.insert("bookmark", Box::new(BookmarkCompleter));
+        self.mg.emit(Completers(completers));
+        input_with_completion(&self.mg, &self.model.relm, "Bookmark:".to_string(), "bookmark", Answered);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Answered(answer) => {
+                // The answer was filled from the selected completion.
+                assert_eq!(answer, Some("docs.rs".to_string()));
+                let stream = self.model.relm.stream().clone();
+                gtk::timeout_add(1000, move || {
+                    stream.emit(Check);
+                    Continue(false)
+                });
+            },
+            Check => {
+                // The default completer is used again in the command mode.
+                let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+                assert!(has_row(&window, "open"));
+                assert!(!has_row(&window, "docs.rs"));
+                gtk::main_quit();
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[]).config(ConfigSource::new("examples/main.conf"))) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_input_with_completion() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        xdo.enter_text("d", 0).unwrap();
+        xdo.send_keysequence("Tab", 0).unwrap();
+        xdo.send_keysequence("Return", 0).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        xdo.enter_text(":op", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate gdk;
+extern crate mg;
+extern crate mg_settings;
+
+use gdk::ModifierType;
+use gdk::enums::key;
+use mg::{has_super_modifier, keyval_to_key, string_to_key};
+use mg_settings::key::Key::{Alt, Char, Control, End, Enter, F5, Home, PageUp, Shift, Space, Tab, Up};
+
+#[test]
+fn test_shifted_symbols() {
+    // The keyvals sent for the shifted keys of a US layout.
+    let keys = [
+        (key::exclam, '!'),
+        (key::question, '?'),
+        (key::colon, ':'),
+        (key::less, '<'),
+        (key::greater, '>'),
+        (key::underscore, '_'),
+    ];
+    for &(keyval, character) in &keys {
+        assert_eq!(keyval_to_key(keyval, ModifierType::SHIFT_MASK), Some(Char(character)));
+        assert_eq!(keyval_to_key(keyval, ModifierType::empty()), Some(Char(character)));
+    }
+}
+
+#[test]
+fn test_shifted_non_printable_keys() {
+    assert_eq!(keyval_to_key(key::ISO_Left_Tab, ModifierType::SHIFT_MASK), Some(Shift(Box::new(Tab))));
+    assert_eq!(keyval_to_key(key::F5, ModifierType::SHIFT_MASK), Some(Shift(Box::new(F5))));
+    assert_eq!(keyval_to_key(key::T, ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK),
+        Some(Control(Box::new(Char('T')))));
+}
+
+#[test]
+fn test_keypad_keys() {
+    // The keypad keys are converted to the named keys of the main keyboard.
+    assert_eq!(keyval_to_key(key::KP_Enter, ModifierType::empty()), Some(Enter));
+    assert_eq!(keyval_to_key(key::KP_Space, ModifierType::empty()), Some(Space));
+    assert_eq!(keyval_to_key(key::KP_Home, ModifierType::empty()), Some(Home));
+    assert_eq!(keyval_to_key(key::KP_End, ModifierType::empty()), Some(End));
+    assert_eq!(keyval_to_key(key::KP_Page_Up, ModifierType::empty()), Some(PageUp));
+    assert_eq!(keyval_to_key(key::KP_Up, ModifierType::empty()), Some(Up));
+    assert_eq!(keyval_to_key(key::space, ModifierType::empty()), Some(Space));
+}
+
+#[test]
+fn test_super_modifier() {
+    // NOTE: the keys pressed with super must not trigger the mappings of the key without modifier.
+    assert_eq!(keyval_to_key(key::t, ModifierType::SUPER_MASK), None);
+    assert_eq!(keyval_to_key(key::t, ModifierType::MOD4_MASK | ModifierType::CONTROL_MASK), None);
+    assert_eq!(keyval_to_key(key::t, ModifierType::empty()), Some(Char('t')));
+    assert!(has_super_modifier(ModifierType::MOD4_MASK));
+    assert!(!has_super_modifier(ModifierType::CONTROL_MASK | ModifierType::MOD1_MASK));
+}
+
+#[test]
+fn test_string_to_key_modifiers() {
+    assert_eq!(string_to_key("<C-S-t>"), Some(Control(Box::new(Char('T')))));
+    assert_eq!(string_to_key("<S-C-t>"), string_to_key("<C-T>"));
+    assert_eq!(string_to_key("<A-C-x>"), Some(Control(Box::new(Alt(Box::new(Char('x')))))));
+    assert_eq!(string_to_key("<S-Tab>"), Some(Shift(Box::new(Tab))));
+    assert_eq!(string_to_key("<lt>"), Some(Char('<')));
+}
+
+#[test]
+fn test_string_to_key_shifted_symbol() {
+    // The shifted symbol depends on the keyboard layout, so shift cannot be applied to a symbol.
+    assert_eq!(string_to_key("<S-1>"), None);
+    assert_eq!(string_to_key("<C-S-/>"), None);
+    assert_eq!(string_to_key("!"), Some(Char('!')));
+    assert_eq!(string_to_key("<C-!>"), Some(Control(Box::new(Char('!')))));
+}
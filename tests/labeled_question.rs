@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::{Cast, Continue};
+use libxdo::XDo;
+use mg::{
+    Mg,
+    MgBuilder,
+    NoSettings,
+    labeled_question,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, has_label_containing, run_main_loop};
+
+pub struct Model {
+    prompt_shown: bool,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(Option<String>),
+    CheckPrompt,
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        let choices = [('y', "yes"), ('n', "no"), ('a', "always"), ('d', "keep the draft")];
+        labeled_question(&self.mg, &self.model.relm, "Save?".to_string(), &choices, Answered);
+        let stream = self.model.relm.stream().clone();
+        gtk::timeout_add(300, move || {
+            stream.emit(CheckPrompt);
+            Continue(false)
+        });
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            prompt_shown: false,
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Answered(answer) => {
+                assert!(self.model.prompt_shown);
+                assert_eq!(answer, Some("a".to_string()));
+                gtk::main_quit();
+            },
+            CheckPrompt => {
+                // The key is inside the label when the label starts with it.
+                let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+                assert!(has_label_containing(&window, "Save? [y]es [n]o [a]lways [d] keep the draft"));
+                self.model.prompt_shown = true;
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_labeled_question() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(600));
+        xdo.enter_text("a", 0).unwrap();
+    });
+
+    run_main_loop();
+}
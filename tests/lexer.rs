@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use mg::{Token, current_argument_index, lex_command_line};
+
+fn texts(command_line: &str) -> Vec<String> {
+    lex_command_line(command_line).into_iter()
+        .map(|token| token.text)
+        .collect()
+}
+
+#[test]
+fn test_lex_words() {
+    assert_eq!(texts("open  http://duckduckgo.com"), vec!["open", "http://duckduckgo.com"]);
+    assert_eq!(texts("  set title = Mg "), vec!["set", "title", "=", "Mg"]);
+    assert!(lex_command_line("").is_empty());
+}
+
+#[test]
+fn test_lex_quotes_and_escapes() {
+    assert_eq!(texts(r#"open "a file.txt""#), vec!["open", "a file.txt"]);
+    assert_eq!(texts(r#"open "unterminated quote"#), vec!["open", "unterminated quote"]);
+    assert_eq!(texts(r"open a\ file.txt"), vec!["open", "a file.txt"]);
+    assert_eq!(texts(r#"echo \"quoted\" \\"#), vec!["echo", "\"quoted\"", "\\"]);
+}
+
+#[test]
+fn test_lex_spans() {
+    assert_eq!(lex_command_line(r#"open "a b""#), vec![
+        Token { span: 0..4, text: "open".to_string() },
+        Token { span: 5..10, text: "a b".to_string() },
+    ]);
+}
+
+#[test]
+fn test_current_argument_index() {
+    assert_eq!(current_argument_index(""), 0);
+    assert_eq!(current_argument_index("se"), 0);
+    assert_eq!(current_argument_index("set "), 1);
+    assert_eq!(current_argument_index("set ti"), 1);
+    assert_eq!(current_argument_index(r"open a\ "), 1);
+    assert_eq!(current_argument_index(r#"open "a "#), 1);
+    assert_eq!(current_argument_index(r#"open "a" "#), 2);
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::{Cast, Continue};
+use libxdo::XDo;
+use mg::{
+    CustomCommand,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, has_label, run_main_loop};
+
+pub struct Model {
+    opened: Vec<String>,
+    relm: Relm<Win>,
+    shortcut_shown: bool,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    CheckShortcut,
+    Command(AppCommand),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(ExecuteCommand("nmap <lt>x open <lt>Enter>".to_string()));
+        self.mg.emit(ExecuteCommand("nmap <lt>y :open a<lt>Enter><Enter>".to_string()));
+        let stream = self.model.relm.stream().clone();
+        gtk::timeout_add(700, move || {
+            stream.emit(CheckShortcut);
+            Continue(false)
+        });
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            opened: vec![],
+            relm: relm.clone(),
+            shortcut_shown: false,
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            CheckShortcut => {
+                // The literal < is shown escaped while the shortcut is typed.
+                let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+                assert!(has_label(&window, "<lt>"));
+                self.model.shortcut_shown = true;
+            },
+            Command(Open(url)) => {
+                self.model.opened.push(url);
+                if self.model.opened.len() == 2 {
+                    // The escaped <Enter> is a literal text and does not complete the command.
+                    assert!(self.model.shortcut_shown);
+                    assert_eq!(self.model.opened, vec!["<Enter>".to_string(), "a<Enter>".to_string()]);
+                    gtk::main_quit();
+                }
+            },
+            Command(Quit) => (),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_lt_escape() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        xdo.send_keysequence("less", 0).unwrap();
+        thread::sleep(Duration::from_millis(1000));
+        xdo.send_keysequence("x", 0).unwrap();
+        xdo.send_keysequence("less", 0).unwrap();
+        xdo.send_keysequence("y", 0).unwrap();
+    });
+
+    run_main_loop();
+}
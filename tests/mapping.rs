@@ -0,0 +1,62 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use mg::Mapping;
+
+fn mapping(action: &str, description: Option<&str>) -> Mapping {
+    Mapping {
+        action: action.to_string(),
+        description: description.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_mapping_description() {
+    assert_eq!(Mapping::new(":goto-definition<Enter> -- \"Go to definition\""),
+        mapping(":goto-definition<Enter>", Some("Go to definition")));
+    assert_eq!(Mapping::new("show-error-details -- \"Say \\\"hi\\\" \\\\ bye\""),
+        mapping("show-error-details", Some("Say \"hi\" \\ bye")));
+}
+
+#[test]
+fn test_mapping_quoted_argument() {
+    // A trailing quoted argument without the separator is part of the action.
+    assert_eq!(Mapping::new("open \"foo\""), mapping("open \"foo\"", None));
+    assert_eq!(Mapping::new("open \"foo\" -- \"Open foo\""), mapping("open \"foo\"", Some("Open foo")));
+    // An invalid quoted string after the separator is part of the action.
+    assert_eq!(Mapping::new("open -- \"a\" b\""), mapping("open -- \"a\" b\"", None));
+    assert_eq!(Mapping::new("open --"), mapping("open --", None));
+    assert_eq!(Mapping::new(" -- \"description\""), mapping(" -- \"description\"", None));
+}
+
+#[test]
+fn test_mapping_to_config() {
+    let mappings = vec![
+        mapping("open \"foo\"", None),
+        mapping("show-error-details", Some("Say \"hi\" \\ bye -- \"now\"")),
+        mapping(":set boolean?<Enter>", Some("")),
+    ];
+    for original in mappings {
+        assert_eq!(Mapping::new(&original.to_config()), original);
+    }
+}
@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use libxdo::XDo;
+use mg::{
+    ConfigSource,
+    CustomCommand,
+    Mg,
+    MgBuilder,
+    Mode,
+    Modes,
+    NoSettings,
+    SetMode,
+};
+use relm::{Widget, init_test};
+use relm_attributes::widget;
+
+use self::AppCommand::*;
+use self::Msg::*;
+use utils::run_main_loop;
+
+#[derive(Commands, Debug, PartialEq)]
+pub enum AppCommand {
+    Insert,
+    InsertX,
+    NormalX,
+    Open(String),
+}
+
+static MODES: Modes = &[
+    Mode { name: "insert", prefix: "i", show_count: false },
+];
+
+pub struct Model {
+    executed: Vec<AppCommand>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Command(AppCommand),
+}
+
+/// Write the config of the test, which maps x differently in the normal and the insert modes.
+fn config_path() -> PathBuf {
+    let path = env::temp_dir().join("mg-test-mapping-mode-change");
+    let mut file = File::create(&path).expect("create config");
+    write!(file, "nmap i insert\nnmap x normal-x\nimap x insert-x\n").expect("write config");
+    path
+}
+
+#[widget]
+impl Widget for Win {
+    fn model() -> Model {
+        Model {
+            executed: vec![],
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Command(command) => {
+                self.model.executed.push(command);
+                if self.model.executed.len() == 2 {
+                    // The key typed right after the mapping is handled in the mode set by this mapping.
+                    assert_eq!(self.model.executed, vec![Insert, InsertX]);
+                    gtk::main_quit();
+                }
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(MODES).config(ConfigSource::new(config_path()))) {
+            gtk::Label {
+            },
+            CustomCommand(Insert) => mg@SetMode("insert"),
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_mapping_mode_change() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        // NOTE: both keys are sent at once, without any delay between them.
+        xdo.send_keysequence("i x", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+
+use libxdo::XDo;
+use mg::{
+    CustomCommand,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, emit_after, has_toplevel_label, run_main_loop};
+
+pub struct Model {
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    CheckShown,
+    Command(AppCommand),
+    Show,
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        // NOTE: a mapping added at runtime is listed too.
+        self.mg.emit(ExecuteCommand("nmap x open x".to_string()));
+        emit_after(&self.model.relm, 200, || Show);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            CheckShown => {
+                assert!(has_toplevel_label("x"));
+                assert!(has_toplevel_label("open x"));
+                thread::spawn(|| {
+                    let xdo = XDo::new(None).unwrap();
+                    xdo.send_keysequence("x", 0).unwrap();
+                });
+            },
+            Command(Open(url)) => {
+                // The mapped key closes the overlay and still executes its mapping.
+                assert_eq!(url, "x");
+                assert!(!has_toplevel_label("open x"));
+                gtk::main_quit();
+            },
+            Command(Quit) => (),
+            Show => {
+                assert!(!has_toplevel_label("open x"));
+                self.mg.emit(ExecuteCommand("show-mappings".to_string()));
+                emit_after(&self.model.relm, 200, || CheckShown);
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_mappings_overlay() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    run_main_loop();
+}
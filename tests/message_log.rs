@@ -0,0 +1,51 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use mg::LogEntry;
+
+fn entry_at(seconds: u64) -> LogEntry {
+    LogEntry {
+        level: "ERROR".to_string(),
+        message: "message".to_string(),
+        time: UNIX_EPOCH + Duration::from_secs(seconds),
+    }
+}
+
+#[test]
+fn test_iso_time_string() {
+    assert_eq!(entry_at(0).iso_time_string(), "1970-01-01T00:00:00Z");
+    assert_eq!(entry_at(1_522_585_800).iso_time_string(), "2018-04-01T12:30:00Z");
+    // Leap days.
+    assert_eq!(entry_at(951_782_400).iso_time_string(), "2000-02-29T00:00:00Z");
+    assert_eq!(entry_at(1_709_251_199).iso_time_string(), "2024-02-29T23:59:59Z");
+    // 2100 is not a leap year.
+    assert_eq!(entry_at(4_107_542_399).iso_time_string(), "2100-02-28T23:59:59Z");
+    assert_eq!(entry_at(4_107_542_400).iso_time_string(), "2100-03-01T00:00:00Z");
+}
+
+#[test]
+fn test_time_string() {
+    assert_eq!(entry_at(1_522_585_800).time_string(), "12:30:00");
+}
@@ -0,0 +1,141 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gdk;
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use gdk::ModifierType;
+use gtk::{Continue, GtkWindowExt, Inhibit, WidgetExt};
+use libxdo::XDo;
+use mg::{
+    CustomCommand,
+    MapMouseButton,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, run_main_loop};
+
+pub struct Model {
+    // NOTE: shared with the button-press-event handler.
+    clicks: Rc<Cell<usize>>,
+    opened: Vec<String>,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Check,
+    Command(AppCommand),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        let window = self.mg.widget().clone();
+        window.move_(0, 0);
+        window.set_default_size(200, 200);
+        // NOTE: this handler is called after the one of Mg, only if Mg did not inhibit the event.
+        let clicks = self.model.clicks.clone();
+        window.connect_button_press_event(move |_, _| {
+            clicks.set(clicks.get() + 1);
+            Inhibit(false)
+        });
+        self.mg.emit(MapMouseButton("normal", 1, ModifierType::empty(), "open first".to_string()));
+        let stream = self.model.relm.stream().clone();
+        gtk::timeout_add(2000, move || {
+            stream.emit(Check);
+            Continue(false)
+        });
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            clicks: Rc::new(Cell::new(0)),
+            opened: vec![],
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Check => {
+                // The mapped click is inhibited while the other one is propagated.
+                assert_eq!(self.model.opened, vec!["first".to_string()]);
+                assert_eq!(self.model.clicks.get(), 1);
+                gtk::main_quit();
+            },
+            Command(Open(url)) => self.model.opened.push(url),
+            Command(Quit) => (),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_mouse_mapping() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(500));
+        xdo.move_mouse(100, 100, 0).unwrap();
+        xdo.click(1).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        xdo.click(3).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,154 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::{Cast, EntryExt};
+use libxdo::XDo;
+use mg::{
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+    password_input,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, emit_after, entry, has_label_containing, has_row, run_main_loop};
+
+pub struct Model {
+    answers: Vec<Option<String>>,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Answered(Option<String>),
+    CheckHistory,
+    CheckMasked,
+}
+
+/// Type the text in the entry after a delay.
+fn type_text(text: &'static str) {
+    thread::spawn(move || {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        xdo.enter_text(text, 0).unwrap();
+    });
+}
+
+/// Send the key sequence after a delay.
+fn send_keys(keys: &'static str) {
+    thread::spawn(move || {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        xdo.send_keysequence(keys, 0).unwrap();
+    });
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        password_input(&self.mg, &self.model.relm, "Password:".to_string(), Answered);
+        type_text("secret");
+        emit_after(&self.model.relm, 800, || CheckMasked);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            answers: vec![],
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+        let entry = entry(&window).expect("command entry");
+        match event {
+            Answered(answer) => {
+                // The visibility is restored whatever the way the dialog is closed.
+                assert!(entry.get_visibility());
+                self.model.answers.push(answer);
+                if self.model.answers.len() == 1 {
+                    password_input(&self.mg, &self.model.relm, "Password:".to_string(), Answered);
+                    type_text("other");
+                    emit_after(&self.model.relm, 800, || CheckMasked);
+                }
+                else {
+                    assert_eq!(self.model.answers, vec![Some("secret".to_string()), None]);
+                    self.mg.emit(ExecuteCommand("command-history-window".to_string()));
+                    emit_after(&self.model.relm, 300, || CheckHistory);
+                }
+            },
+            CheckHistory => {
+                // The password is neither in the history nor in a message.
+                assert!(!has_row(&window, "secret"));
+                assert!(!has_label_containing(&window, "secret"));
+                gtk::main_quit();
+            },
+            CheckMasked => {
+                assert!(!entry.get_visibility());
+                if self.model.answers.is_empty() {
+                    assert_eq!(entry.get_text(), Some("secret".to_string()));
+                    send_keys("Return");
+                }
+                else {
+                    send_keys("Escape");
+                }
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_password_input() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    run_main_loop();
+}
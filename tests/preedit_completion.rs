@@ -0,0 +1,136 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate glib;
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+
+use glib::ObjectExt;
+use gtk::{Cast, ContainerExt, EntryExt};
+use libxdo::XDo;
+use mg::{
+    Mg,
+    MgBuilder,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::{AppCommand, AppSettings, emit_after, has_row, run_main_loop};
+
+pub struct Model {
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    CheckCommitted,
+    CheckComposing,
+    Compose,
+}
+
+/// Find the command entry in the widget tree.
+fn find_entry(widget: &gtk::Widget) -> Option<gtk::Entry> {
+    if let Ok(entry) = widget.clone().downcast::<gtk::Entry>() {
+        return Some(entry);
+    }
+    if let Ok(container) = widget.clone().downcast::<gtk::Container>() {
+        for child in container.get_children() {
+            if let Some(entry) = find_entry(&child) {
+                return Some(entry);
+            }
+        }
+    }
+    None
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        emit_after(&self.model.relm, 1000, || Compose);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+        let entry = find_entry(&window).expect("command entry");
+        match event {
+            CheckCommitted => {
+                // The completions are updated once the composition ends.
+                assert!(has_row(&window, "boolean"));
+                gtk::main_quit();
+            },
+            CheckComposing => {
+                // The completions are not updated with the half-composed text.
+                assert!(!has_row(&window, "boolean"));
+                entry.emit("preedit-changed", &[&""]).unwrap();
+                emit_after(&self.model.relm, 500, || CheckCommitted);
+            },
+            Compose => {
+                // Simulate an input method composing the text.
+                entry.emit("preedit-changed", &[&"s"]).unwrap();
+                entry.set_text("set ");
+                emit_after(&self.model.relm, 500, || CheckComposing);
+            },
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+        }
+    }
+}
+
+#[test]
+fn test_preedit_completion() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        xdo.enter_text(":", 0).unwrap();
+    });
+
+    run_main_loop();
+}
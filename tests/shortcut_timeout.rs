@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+use std::time::Duration;
+
+use gtk::Cast;
+use libxdo::XDo;
+use mg::{
+    CustomCommand,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    NoSettings,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppCommand::*;
+use utils::{AppCommand, emit_after, has_label, run_main_loop};
+
+pub struct Model {
+    checked: usize,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    CheckCleared,
+    CheckShown,
+    Command(AppCommand),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(ExecuteCommand("set shortcut-timeout = 1".to_string()));
+        self.mg.emit(ExecuteCommand("nmap gx open x".to_string()));
+        emit_after(&self.model.relm, 600, || CheckShown);
+        emit_after(&self.model.relm, 1500, || CheckCleared);
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            checked: 0,
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+        match event {
+            CheckCleared => {
+                // The partial shortcut is cleared after the timeout.
+                assert!(!has_label(&window, "g"));
+                self.model.checked += 1;
+                // With 0, the shortcut is never cleared.
+                self.mg.emit(ExecuteCommand("set shortcut-timeout = 0".to_string()));
+            },
+            CheckShown => {
+                assert!(has_label(&window, "g"));
+                self.model.checked += 1;
+            },
+            Command(Open(url)) => {
+                // The key pressed after the timeout did not complete the cleared shortcut.
+                assert_eq!(self.model.checked, 2);
+                assert_eq!(url, "x");
+                gtk::main_quit();
+            },
+            Command(Quit) => (),
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, NoSettings>(MgBuilder::new(&[])) {
+            gtk::Label {
+            },
+            CustomCommand(ref command) => Command(command.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_shortcut_timeout() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        xdo.send_keysequence("g", 0).unwrap();
+        thread::sleep(Duration::from_millis(1500));
+        xdo.send_keysequence("x", 0).unwrap();
+        thread::sleep(Duration::from_millis(700));
+        xdo.send_keysequence("g", 0).unwrap();
+        thread::sleep(Duration::from_millis(1500));
+        xdo.send_keysequence("x", 0).unwrap();
+    });
+
+    run_main_loop();
+}
@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+#![feature(proc_macro)]
+
+extern crate gtk;
+extern crate libxdo;
+extern crate mg;
+extern crate mg_settings;
+#[macro_use]
+extern crate mg_settings_macros;
+#[macro_use]
+extern crate relm;
+extern crate relm_attributes;
+#[macro_use]
+extern crate relm_derive;
+
+mod utils;
+
+use std::thread;
+
+use gtk::{Cast, Continue};
+use libxdo::XDo;
+use mg::{
+    ConfigSource,
+    ExecuteCommand,
+    Mg,
+    MgBuilder,
+    SettingChanged,
+};
+use relm::{Relm, Widget, init_test};
+use relm_attributes::widget;
+
+use self::Msg::*;
+use utils::AppSettingsVariant::{self, Boolean};
+use utils::{AppCommand, AppSettings, has_label_containing, run_main_loop};
+
+pub struct Model {
+    boolean: bool,
+    relm: Relm<Win>,
+}
+
+#[derive(Msg)]
+pub enum Msg {
+    Check,
+    Setting(AppSettingsVariant),
+}
+
+#[widget]
+impl Widget for Win {
+    fn init_view(&mut self) {
+        self.mg.emit(ExecuteCommand("nmap x <silent>:set boolean!<Enter>:set boolean?<Enter>".to_string()));
+        let stream = self.model.relm.stream().clone();
+        gtk::timeout_add(2000, move || {
+            stream.emit(Check);
+            Continue(false)
+        });
+    }
+
+    fn model(relm: &Relm<Self>, _: ()) -> Model {
+        Model {
+            boolean: false,
+            relm: relm.clone(),
+        }
+    }
+
+    fn update(&mut self, event: Msg) {
+        match event {
+            Check => {
+                // The mapping was executed, but the value of the setting, shown with info(), is
+                // silenced.
+                assert!(self.model.boolean);
+                let window = self.mg.widget().clone().upcast::<gtk::Widget>();
+                assert!(!has_label_containing(&window, "boolean="));
+                gtk::main_quit();
+            },
+            Setting(Boolean(boolean)) => self.model.boolean = boolean,
+        }
+    }
+
+    view! {
+        #[name="mg"]
+        Mg<AppCommand, AppSettings>(MgBuilder::new(&[]).setting_values().config(ConfigSource::new("examples/main.conf"))) {
+            gtk::Label {
+            },
+            SettingChanged(ref setting) => Setting(setting.clone()),
+        }
+    }
+}
+
+#[test]
+fn test_silent_mapping() {
+    gtk::init().unwrap();
+
+    let _win = init_test::<Win>(()).unwrap();
+
+    thread::spawn(|| {
+        let xdo = XDo::new(None).unwrap();
+        xdo.send_keysequence("x", 0).unwrap();
+    });
+
+    run_main_loop();
+}
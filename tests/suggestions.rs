@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2018 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+extern crate mg;
+
+use mg::completion::{closest_matches, edit_distance};
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("quit", "quit"), 0);
+    assert_eq!(edit_distance("", "quit"), 4);
+    assert_eq!(edit_distance("quit", ""), 4);
+    // Insertion, deletion and substitution.
+    assert_eq!(edit_distance("qit", "quit"), 1);
+    assert_eq!(edit_distance("quitt", "quit"), 1);
+    assert_eq!(edit_distance("quot", "quit"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    // The distance counts characters, not bytes.
+    assert_eq!(edit_distance("été", "ete"), 2);
+}
+
+#[test]
+fn test_closest_matches() {
+    let candidates = vec!["open".to_string(), "quit".to_string(), "set".to_string()];
+    assert_eq!(closest_matches("quitt", &candidates), vec!["quit".to_string()]);
+    assert_eq!(closest_matches("st", &candidates), vec!["set".to_string()]);
+    assert!(closest_matches("window-title", &candidates).is_empty());
+}
@@ -19,7 +19,41 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
+#![allow(dead_code)]
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk::{self, Cast, ContainerExt, Continue, EntryExt, LabelExt, TreeModelExt, TreeViewExt};
 use libxdo::XDo;
+use mg::SettingValues;
+use mg_settings::Value;
+use relm::{Relm, Widget};
+
+/// The time, in milliseconds, after which a test still running its main loop fails.
+const GUARD_TIMEOUT: u32 = 10_000;
+
+/// The commands of the tests which do not need their own commands.
+#[derive(Commands)]
+pub enum AppCommand {
+    Open(String),
+    Quit,
+}
+
+/// The settings of the tests which do not need their own settings.
+#[derive(Default, Settings)]
+pub struct AppSettings {
+    boolean: bool,
+}
+
+impl SettingValues for AppSettings {
+    fn get_value(&self, name: &str) -> Option<Value> {
+        match name {
+            "boolean" => Some(Value::Bool(self.boolean)),
+            _ => None,
+        }
+    }
+}
 
 pub trait XDoExt {
     fn enter_command(&self, command: &str);
@@ -31,3 +65,106 @@ impl XDoExt for XDo {
         self.send_keysequence("Return", 0).unwrap();
     }
 }
+
+/// Emit the message to the widget after the number of milliseconds.
+pub fn emit_after<WIDGET>(relm: &Relm<WIDGET>, milliseconds: u32, msg: fn() -> WIDGET::Msg)
+    where WIDGET: Widget,
+          WIDGET::Msg: 'static,
+{
+    let stream = relm.stream().clone();
+    gtk::timeout_add(milliseconds, move || {
+        stream.emit(msg());
+        Continue(false)
+    });
+}
+
+/// Run the main loop until the test quits it.
+/// The test fails if it does not quit the main loop before the guard timeout, so that a missed
+/// event does not hang the test suite.
+pub fn run_main_loop() {
+    let timed_out = Rc::new(Cell::new(false));
+    {
+        let timed_out = timed_out.clone();
+        gtk::timeout_add(GUARD_TIMEOUT, move || {
+            timed_out.set(true);
+            gtk::main_quit();
+            Continue(false)
+        });
+    }
+    gtk::main();
+    assert!(!timed_out.get(), "the test did not quit the main loop before {} ms", GUARD_TIMEOUT);
+}
+
+/// Get the first entry of the widget tree.
+pub fn entry(widget: &gtk::Widget) -> Option<gtk::Entry> {
+    if let Ok(entry) = widget.clone().downcast::<gtk::Entry>() {
+        return Some(entry);
+    }
+    if let Ok(container) = widget.clone().downcast::<gtk::Container>() {
+        return container.get_children().iter()
+            .filter_map(entry)
+            .next();
+    }
+    None
+}
+
+/// Get the text of the first entry of the widget tree.
+pub fn entry_text(widget: &gtk::Widget) -> Option<String> {
+    entry(widget).and_then(|entry| entry.get_text())
+}
+
+/// Check if a label of the widget tree has exactly the text.
+pub fn has_label(widget: &gtk::Widget, text: &str) -> bool {
+    has_label_matching(widget, &|label_text| label_text == text)
+}
+
+/// Check if a label of the widget tree contains the text.
+pub fn has_label_containing(widget: &gtk::Widget, text: &str) -> bool {
+    has_label_matching(widget, &|label_text| label_text.contains(text))
+}
+
+/// Check if the text of a label of the widget tree matches the predicate.
+fn has_label_matching(widget: &gtk::Widget, predicate: &Fn(&str) -> bool) -> bool {
+    if let Ok(label) = widget.clone().downcast::<gtk::Label>() {
+        if label.get_text().map_or(false, |label_text| predicate(&label_text)) {
+            return true;
+        }
+    }
+    if let Ok(container) = widget.clone().downcast::<gtk::Container>() {
+        return container.get_children().iter()
+            .any(|child| has_label_matching(child, predicate));
+    }
+    false
+}
+
+/// Check if a label of a toplevel window, like a popup, has exactly the text.
+pub fn has_toplevel_label(text: &str) -> bool {
+    gtk::Window::list_toplevels().iter()
+        .any(|window| has_label(window, text))
+}
+
+/// Check if a row of a tree view of the widget tree has a column with exactly the text.
+pub fn has_row(widget: &gtk::Widget, text: &str) -> bool {
+    if let Ok(tree_view) = widget.clone().downcast::<gtk::TreeView>() {
+        if let Some(model) = tree_view.get_model() {
+            if let Some(iter) = model.get_iter_first() {
+                loop {
+                    for column in 0..model.get_n_columns() {
+                        let value: Option<String> = model.get_value(&iter, column).get();
+                        if value.as_ref().map(String::as_str) == Some(text) {
+                            return true;
+                        }
+                    }
+                    if !model.iter_next(&iter) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    if let Ok(container) = widget.clone().downcast::<gtk::Container>() {
+        return container.get_children().iter()
+            .any(|child| has_row(child, text));
+    }
+    false
+}